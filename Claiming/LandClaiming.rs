@@ -1,197 +1,4264 @@
+mod admin;
+pub mod api;
+mod args;
+mod audit;
+mod autoclaim;
+mod bans;
+mod blocks;
+mod claims;
+mod combat;
+mod config;
+mod confirm;
+mod dimensions;
+pub mod economy;
+pub mod events;
+mod expansion;
+mod expiration;
+mod flags;
+mod greetings;
+mod groups;
+mod homes;
+mod hud;
+mod interactions;
+mod leaderboard;
+mod lookup;
+mod messages;
+mod migration;
+mod minimap;
+#[cfg(test)]
+mod mock;
+mod notifier;
+mod perks;
+mod permissions;
+mod persistence;
+mod player;
+mod protection;
+mod ratelimit;
+mod rental;
+mod residency;
+mod scheduler;
+mod snapshot;
+pub mod stats;
+mod storage;
+#[cfg(test)]
+mod tests;
+mod tracking;
+mod transfer;
+mod trust;
+mod upkeep;
+mod validation;
+mod visualization;
+mod webmap;
+
+use std::path::Path;
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime};
+use feather_core::blocks::BlockId;
 use feather_core::world::ChunkPosition;
 use feather_core::Position;
 use feather_server::{
     chunk_logic::ChunkHolder,
+    command::{Command, CommandContext, CommandSender},
+    entity::{Entity, SpawnReason},
+    event::{Cancellable, Event},
     player::Player,
     util::{BlockPosition, Gamemode},
+    world::WorldId,
     Game, Plugin,
 };
+use admin::{AdminBypass, ClaimAdminCommand, ClaimReloadCommand};
+use api::{ClaimEvent, ClaimListeners, EventSink, LandClaimingApi};
+use args::{split_location, ArgKind, ArgReader, Syntax};
+use audit::{AuditAction, AuditEntry, ClaimAuditLog};
+use autoclaim::AutoClaim;
+use bans::{ClaimBanCommand, ClaimUnbanCommand};
+use blocks::ClaimBlocks;
+use claims::{Claim, ClaimDenial, ClaimError, ClaimId, ClaimKey, ClaimManager, ReshapeError};
+use combat::CombatTags;
+use config::ConfigHandle;
+use dimensions::Dimension;
+use confirm::{Confirmations, PendingAction};
+use economy::{BuyClaimBlocksCommand, Economy, SellClaimBlocksCommand};
+use events::{ClaimCreateEvent, ClaimDeleteEvent, ClaimEnterEvent, ClaimTrustChangeEvent};
+use expansion::Direction;
+use expiration::{ClaimExpiry, ExpirationAction};
+use flags::{ClaimFlag, ClaimFlagCommand};
+use greetings::EntryMessage;
+use groups::{ClaimGroupCommand, ClaimGroups, GroupRank};
+use homes::{ClaimHome, HomeTeleports};
+use hud::ClaimHud;
+use interactions::Interaction;
+use leaderboard::ClaimTopCommand;
+use lookup::LookupCache;
+use messages::{Arg, Messages};
+use migration::{ImportReport, ImportedClaim};
+use minimap::ClaimMapCommand;
+use notifier::{NoticeKind, Notifier};
+use perks::ClaimFlight;
+use permissions::{PermissionProvider, Permissions};
+use persistence::{ClaimBatch, ClaimSaver, StoredClaim, StoredPlayer};
+use protection::{Action, Decision};
+use ratelimit::{LimitedAction, RateLimiter};
+use rental::{Lease, Rental};
+use residency::{ClaimResidency, IndexedClaim};
+use scheduler::{Scheduler, Task};
+use snapshot::{ClaimSnapshot, SnapshotError};
+use stats::{ClaimStats, StatsSnapshot};
+use storage::Storage;
+use tracking::{ClaimPresence, ClaimTracker};
+use transfer::PendingTransfers;
+use trust::{TrustCommand, TrustLevel, UntrustCommand};
+use upkeep::{Upkeep, UpkeepCharge, UpkeepState};
+use validation::ClaimRules;
+use visualization::ClaimVisualizer;
+use webmap::WebMap;
 
 #[derive(Default)]
 struct LandClaiming {
-    claims: HashMap<ChunkPosition, Claim>,
-}
-
-#[derive(Default)]
-struct Claim {
-    owner: String,
-    members: Vec<String>,
+    claims: ClaimManager,
+    lookups: LookupCache,
+    visualizer: ClaimVisualizer,
+    admin_bypass: AdminBypass,
+    auto_claim: AutoClaim,
+    combat: CombatTags,
+    confirmations: Confirmations,
+    claim_blocks: ClaimBlocks,
+    economy: Economy,
+    groups: ClaimGroups,
+    homes: HomeTeleports,
+    flight: ClaimFlight,
+    hud: ClaimHud,
+    permissions: Permissions,
+    rate_limits: RateLimiter,
+    expiry: ClaimExpiry,
+    upkeep: Upkeep,
+    config: ConfigHandle,
+    messages: Messages,
+    tracker: ClaimTracker,
+    listeners: ClaimListeners,
+    notifier: Notifier,
+    webmap: WebMap,
+    storage: Storage,
+    saver: ClaimSaver,
+    residency: ClaimResidency,
+    audit_log: ClaimAuditLog,
+    stats: ClaimStats,
+    maintenance: Scheduler<LandClaiming>,
+    game: Option<Arc<Game>>,
+    /// The enabled plugin, kept by the instance Feather holds so it can be
+    /// reached again on disable.
+    running: Option<Arc<LandClaiming>>,
 }
 
 impl Plugin for LandClaiming {
     fn on_enable(&mut self, game: Arc<Game>) {
-        let plugin = Arc::new(self.clone());
+        self.game = Some(game.clone());
+        if let Err(err) = self.config.load() {
+            log::warn!("Using default LandClaiming settings: {}", err);
+        }
+        if let Err(err) = self.messages.load() {
+            log::warn!("Using built-in LandClaiming messages: {}", err);
+        }
+        self.storage = Storage::open(&self.config.get().storage);
+        if let Err(err) = self.audit_log.load(&self.storage) {
+            log::warn!("Could not read the LandClaiming audit log: {}", err);
+        }
+        match self.load_claims(&game) {
+            Ok(count) => log::info!("Loaded {} LandClaiming claim(s)", count),
+            Err(err) => log::warn!("Could not read saved LandClaiming claims: {}", err),
+        }
+        if let Err(err) = self.load_players() {
+            log::warn!("Could not read saved LandClaiming player data: {}", err);
+        }
+        let plugin = Arc::new(std::mem::take(self));
+        self.running = Some(plugin.clone());
+        plugin.saver.start(plugin.storage.shared_driver());
+
+        let webmap_dirty = plugin.webmap.dirty_flag();
+        plugin
+            .listeners
+            .subscribe(move |_event| webmap_dirty.store(true, std::sync::atomic::Ordering::SeqCst));
+        let notifying = Arc::downgrade(&plugin);
+        plugin.listeners.subscribe(move |event| {
+            if let Some(plugin) = notifying.upgrade() {
+                plugin.notify_event(event);
+            }
+        });
+        plugin.webmap.start(&plugin.config.get().webmap);
+        plugin.load_resident_claims(&game);
+        plugin.schedule_maintenance();
 
         game.server
             .event_manager()
             .on_player_interact_block(
-                move |event| {
-                    plugin.handle_interact_block(event);
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_interact_block(event)
+                },
+                plugin.clone(),
+            )
+            .on_player_interact_entity(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_interact_entity(event)
                 },
                 plugin.clone(),
             )
             .on_player_move(
-                move |event| {
-                    plugin.handle_player_move(event);
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_player_move(event)
+                },
+                plugin.clone(),
+            )
+            .on_player_break_block(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_break_block(event)
+                },
+                plugin.clone(),
+            )
+            .on_player_place_block(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_place_block(event)
+                },
+                plugin.clone(),
+            )
+            .on_player_bucket_empty(
+                {
+                    let plugin = plugin.clone();
+                    move |event| {
+                        if plugin.deny_bucket(event.player(), event.position()) {
+                            event.set_cancelled(true);
+                        }
+                    }
+                },
+                plugin.clone(),
+            )
+            .on_player_bucket_fill(
+                {
+                    let plugin = plugin.clone();
+                    move |event| {
+                        if plugin.deny_bucket(event.player(), event.position()) {
+                            event.set_cancelled(true);
+                        }
+                    }
+                },
+                plugin.clone(),
+            )
+            .on_player_use_item(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_use_item(event)
+                },
+                plugin.clone(),
+            )
+            .on_projectile_hit(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_projectile_hit(event)
+                },
+                plugin.clone(),
+            )
+            .on_liquid_flow(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_liquid_flow(event)
+                },
+                plugin.clone(),
+            )
+            .on_chunk_load(
+                {
+                    let plugin = plugin.clone();
+                    move |event| {
+                        plugin.chunk_loaded(ClaimKey::new(event.world().id(), event.position()))
+                    }
+                },
+                plugin.clone(),
+            )
+            .on_chunk_unload(
+                {
+                    let plugin = plugin.clone();
+                    move |event| {
+                        let key = ClaimKey::new(event.world().id(), event.position());
+                        plugin.residency.chunk_unloaded(key);
+                    }
+                },
+                plugin.clone(),
+            )
+            .on_entity_damage_by_entity(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_entity_damage(event)
+                },
+                plugin.clone(),
+            )
+            .on_entity_spawn(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_entity_spawn(event)
+                },
+                plugin.clone(),
+            )
+            .on_entity_change_block(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_entity_change_block(event)
+                },
+                plugin.clone(),
+            )
+            .on_entity_explode(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_entity_explode(event)
+                },
+                plugin.clone(),
+            )
+            .on_player_death(
+                {
+                    let plugin = plugin.clone();
+                    move |event| plugin.handle_player_death(event)
+                },
+                plugin.clone(),
+            )
+            .on_player_join(
+                {
+                    let plugin = plugin.clone();
+                    move |event| {
+                        plugin.expiry.record_seen(event.player().name());
+                        plugin.tell_upkeep_notices(event.player());
+                    }
+                },
+                plugin.clone(),
+            )
+            .on_player_leave(
+                {
+                    let plugin = plugin.clone();
+                    move |event| {
+                        plugin.expiry.record_seen(event.player().name());
+                        plugin.tracker.forget(event.player().name());
+                        plugin.lookups.forget(event.player().name());
+                        plugin.auto_claim.disable(event.player().name());
+                        plugin.combat.forget(event.player().name());
+                        plugin.homes.forget(event.player().name());
+                        plugin.end_flight(event.player());
+                        plugin.confirmations.forget(event.player().name());
+                        plugin.rate_limits.forget(event.player().name());
+                    }
+                },
+                plugin.clone(),
+            )
+            .on_server_tick(
+                {
+                    let plugin = plugin.clone();
+                    let game = game.clone();
+                    move |_event| {
+                        plugin.visualizer.expire(&game);
+                        plugin.teleport_homes(&game);
+                        plugin.maintenance.run_due(&plugin);
+                    }
                 },
                 plugin.clone(),
             );
 
+        game.server
+            .plugin_registry()
+            .register_api(LandClaimingApi::new(plugin.clone()));
+
         game.server
             .command_manager()
             .register_command(Box::new(ClaimCommand::new(plugin.clone())))
-            .register_command(Box::new(UnclaimCommand::new(plugin.clone())));
+            .register_command(Box::new(UnclaimCommand::new(plugin.clone())))
+            .register_command(Box::new(AbandonAllClaimsCommand::new(plugin.clone())))
+            .register_command(Box::new(ClaimBanCommand::new(plugin.clone())))
+            .register_command(Box::new(ClaimUnbanCommand::new(plugin.clone())))
+            .register_command(Box::new(ClaimFlagCommand::new(plugin.clone())))
+            .register_command(Box::new(TrustCommand::new(plugin.clone())))
+            .register_command(Box::new(UntrustCommand::new(plugin.clone())))
+            .register_command(Box::new(BuyClaimBlocksCommand::new(plugin.clone())))
+            .register_command(Box::new(SellClaimBlocksCommand::new(plugin.clone())))
+            .register_command(Box::new(ClaimGroupCommand::new(plugin.clone())))
+            .register_command(Box::new(ClaimTopCommand::new(plugin.clone())))
+            .register_command(Box::new(ClaimMapCommand::new(plugin.clone())))
+            .register_command(Box::new(ClaimAdminCommand::new(plugin.clone())))
+            .register_command(Box::new(ClaimReloadCommand::new(plugin.clone())));
+    }
+
+    fn on_disable(&mut self) {
+        if let Some(plugin) = self.running.take() {
+            plugin.end_all_flights();
+            plugin.webmap.stop();
+            plugin.send_chunk_notices();
+            plugin.submit_claim_changes();
+            plugin.saver.stop();
+        }
     }
 }
 
 impl LandClaiming {
+    fn with_permission_provider(provider: impl PermissionProvider + 'static) -> Self {
+        Self {
+            permissions: Permissions::new(provider),
+            ..Self::default()
+        }
+    }
+
+    /// Whether interaction protection leaves `player` alone: it is off, or
+    /// they are in creative mode or bypassing claims.
+    fn ignores_protection(&self, player: &Player) -> bool {
+        !self.config.get().protection.block_interact
+            || player.gamemode() == Gamemode::Creative
+            || self.is_bypassing(player)
+    }
+
     fn handle_interact_block(&self, event: &PlayerInteractBlockEvent) {
         let player = event.player();
+        if self.ignores_protection(player) {
+            return;
+        }
 
-        if player.gamemode() == Gamemode::Creative {
+        let block = event.block().position;
+        let key = ClaimKey::at_block(player.world().id(), block);
+        let action = Interaction::of_block(event.block().id.kind().name());
+        if action == Some(Interaction::Crops) && self.is_public_harvest(key) {
             return;
         }
 
-        let block_pos = event.block().position;
+        if self.deny_interaction(player, key, action, (block.x, block.y, block.z)) {
+            event.set_cancelled(true);
+        }
+    }
 
-        if let Some(claim) = self.get_claim_at(block_pos) {
-            let claim_owner = claim.owner;
-            let player_name = player.name();
+    /// Keeps players who may not harvest in a claim from breaking its
+    /// crops, then records the change for `/claim rollback`.
+    fn handle_break_block(&self, event: &PlayerBreakBlockEvent) {
+        let player = event.player();
+        let position = event.position();
 
-            if claim_owner != player_name && !claim.members.contains(&player_name) {
-                player.send_message(format!(
-                    "This land is claimed by {}. You cannot interact with it.",
-                    claim_owner
-                ));
+        if !self.ignores_protection(player) {
+            let key = ClaimKey::at_block(player.world().id(), position);
+            // Harvesting crops follows the interaction matrix rather than
+            // needing build trust.
+            let denied = if interactions::is_crop(event.block().kind().name()) {
+                let at = (position.x, position.y, position.z);
+                !self.is_public_harvest(key)
+                    && self.deny_interaction(player, key, Some(Interaction::Crops), at)
+            } else {
+                self.deny_building(player, position, key)
+            };
+            if denied {
                 event.set_cancelled(true);
+                return;
             }
         }
+
+        self.record_block_change(player, position, event.block(), BlockId::air());
     }
 
-    fn handle_player_move(&self, event: &PlayerMoveEvent) {
+    fn handle_place_block(&self, event: &PlayerPlaceBlockEvent) {
         let player = event.player();
-        let new_pos = event.new_position();
+        let position = event.position();
 
-        let new_chunk_pos = ChunkPosition::new(new_pos.x as i32, new_pos.z as i32);
-        if self.is_claimed_chunk(new_chunk_pos) {
-            let claim = self.get_claim_at(new_pos);
-            let claim_owner = claim.map(|c| c.owner);
+        let key = ClaimKey::at_block(player.world().id(), position);
+        if !self.ignores_protection(player) && self.deny_building(player, position, key) {
+            event.set_cancelled(true);
+            return;
+        }
 
-            if claim_owner != player.name()
-                && !claim.map(|c| c.members.contains(&player.name())).unwrap_or_default()
-            {
-                player.send_message(format!(
-                    "You entered land claimed by {}. Please respect their property.",
-                    claim_owner.unwrap_or("Unknown")
-                ));
-            }
+        self.record_block_change(player, position, event.replaced(), event.block());
+    }
+
+    /// Players trampling farmland need crop trust even where harvesting is
+    /// public, since it spoils the farm for everyone.
+    fn handle_trample(&self, event: &EntityChangeBlockEvent, player: &Player) {
+        if event.block().kind().name() != interactions::FARMLAND || self.ignores_protection(player)
+        {
+            return;
+        }
+
+        let position = event.position();
+        let key = ClaimKey::at_block(player.world().id(), position);
+        let at = (position.x, position.y, position.z);
+        if self.deny_interaction(player, key, Some(Interaction::Crops), at) {
+            event.set_cancelled(true);
+        }
+    }
+
+    fn is_public_harvest(&self, key: ClaimKey) -> bool {
+        self.flag_at(key, ClaimFlag::PublicHarvest) == Some(true)
+    }
+
+    /// Stops players trading with villagers in claims where their trust
+    /// does not allow it.
+    fn handle_interact_entity(&self, event: &PlayerInteractEntityEvent) {
+        let player = event.player();
+        let entity = event.entity();
+        if !entity.is_villager() || self.ignores_protection(player) {
+            return;
+        }
+
+        let position = entity.position();
+        let key = ClaimKey::at(entity.world().id(), position);
+        let at = (position.x.floor() as i32, position.y.floor() as i32, position.z.floor() as i32);
+        if self.deny_interaction(player, key, Some(Interaction::Villagers), at) {
+            event.set_cancelled(true);
         }
     }
 
-    fn get_claim_at(&self, position: Position) -> Option<&Claim> {
-        let chunk_pos = ChunkPosition::from_block_position(position);
-        self.claims.get(&chunk_pos)
+    /// Emptying or filling a bucket needs build trust where the liquid goes
+    /// or comes from.
+    fn deny_bucket(&self, player: &Player, position: BlockPosition) -> bool {
+        let key = ClaimKey::at_block(player.world().id(), position);
+        !self.ignores_protection(player) && self.deny_building(player, position, key)
     }
 
-    fn is_claimed_chunk(&self, chunk_pos: ChunkPosition) -> bool {
-        self.claims.contains_key(&chunk_pos)
+    /// Spawn eggs, flint and steel and other items that change the world
+    /// without placing a block need build trust where they are used.
+    fn handle_use_item(&self, event: &PlayerUseItemEvent) {
+        let player = event.player();
+        if !interactions::places_with_item(event.item().kind().name())
+            || self.ignores_protection(player)
+        {
+            return;
+        }
+
+        let position = event.target().unwrap_or_else(|| claims::block_of(player.position()));
+        let key = ClaimKey::at_block(player.world().id(), position);
+        if self.deny_building(player, position, key) {
+            event.set_cancelled(true);
+        }
     }
 
-    fn claim_chunk(&mut self, player: &Player, chunk_pos: ChunkPosition) {
-        let claim = Claim {
-            owner: player.name().to_owned(),
-            members: vec![player.name().to_owned()],
+    /// Projectiles are held to the trust of whoever shot them: an egg or
+    /// snowball may not press buttons or trample farmland its thrower could
+    /// not, nor break item frames and armor stands they could not build
+    /// with.
+    fn handle_projectile_hit(&self, event: &ProjectileHitEvent) {
+        let player = match event.projectile().source().and_then(Entity::as_player) {
+            Some(player) => player,
+            None => return,
         };
-        self.claims.insert(chunk_pos, claim);
-        player.send_message("Chunk claimed successfully.");
-    }
+        if self.ignores_protection(player) {
+            return;
+        }
+        let world = event.projectile().world().id();
 
-    fn unclaim_chunk(&mut self, player: &Player, chunk_pos: ChunkPosition) {
-        if let Some(claim) = self.claims.get_mut(&chunk_pos) {
-            if claim.owner == player.name() {
-                self.claims.remove(&chunk_pos);
-                player.send_message("Chunk unclaimed successfully.");
+        let denied = if let Some(block) = event.hit_block() {
+            let hit = event.projectile().world().block_at(block).unwrap_or_else(BlockId::air);
+            let kind = hit.kind().name();
+            let action = if kind == interactions::FARMLAND {
+                Some(Interaction::Crops)
             } else {
-                player.send_message("You do not have permission to unclaim this chunk.");
-            }
+                Interaction::of_block(kind)
+            };
+            let key = ClaimKey::at_block(world, block);
+            let at = (block.x, block.y, block.z);
+            action.is_some() && self.deny_interaction(player, key, action, at)
+        } else if let Some(entity) = event.hit_entity() {
+            let position = claims::block_of(entity.position());
+            (entity.is_item_frame() || entity.is_armor_stand())
+                && self.deny_building(player, position, ClaimKey::at_block(world, position))
         } else {
-            player.send_message("This chunk is not claimed.");
+            false
+        };
+        if denied {
+            event.set_cancelled(true);
         }
     }
-}
 
-struct ClaimCommand {
-    plugin: Arc<LandClaiming>,
-}
+    /// Keeps water and lava poured outside a claim from flowing into it.
+    /// Liquid may still flow between claims of the same owner.
+    fn handle_liquid_flow(&self, event: &LiquidFlowEvent) {
+        let world = event.world().id();
+        let into = self
+            .claims
+            .read(ClaimKey::at_block(world, event.to()), |claim| claim.owner.clone());
+        let into = match into {
+            Some(owner) => owner,
+            None => return,
+        };
 
-impl ClaimCommand {
-    fn new(plugin: Arc<LandClaiming>) -> Self {
-        Self { plugin }
+        let from = self
+            .claims
+            .read(ClaimKey::at_block(world, event.from()), |claim| claim.owner.clone());
+        if from.as_deref() != Some(into.as_str()) {
+            event.set_cancelled(true);
+        }
     }
-}
 
-impl Command for ClaimCommand {
-    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, _args: Vec<String>) {
-        if let Some(player) = sender.as_player() {
-            let player_chunk_pos = ChunkPosition::new(
-                player.position().x.floor() as i32,
-                player.position().z.floor() as i32,
+    /// Tells `player` and records it when they lack build trust in the
+    /// claim at `key`. Returns whether it was denied.
+    fn deny_building(&self, player: &Player, position: BlockPosition, key: ClaimKey) -> bool {
+        let claim = match self.claim_lookup(player, key) {
+            Some(claim) => claim,
+            None => return false,
+        };
+        let level = self.trust_level(&claim, player.name());
+        if protection::decide(&claim, level, Action::Build) == Decision::Allow {
+            return false;
+        }
+
+        let owner = self.owner_label(player, &claim.owner);
+        self.tell(player, "claim.build_denied", &[("owner", &owner)]);
+        self.record_denial(player, &claim, key, (position.x, position.y, position.z));
+        true
+    }
+
+    /// Tells `player` and records it when they may not perform `action` in
+    /// the claim at `key`. `None` is any other interaction, which needs
+    /// some trust but nothing more. Returns whether it was denied.
+    fn deny_interaction(
+        &self,
+        player: &Player,
+        key: ClaimKey,
+        action: Option<Interaction>,
+        position: (i32, i32, i32),
+    ) -> bool {
+        let claim = match self.claim_lookup(player, key) {
+            Some(claim) => claim,
+            None => return false,
+        };
+
+        let level = self.trust_level(&claim, player.name());
+        match protection::decide(&claim, level, Action::Interact(action)) {
+            Decision::Allow => return false,
+            Decision::Untrusted => {
+                let owner = self.owner_label(player, &claim.owner);
+                self.tell(player, "claim.interact_denied", &[("owner", &owner)]);
+            }
+            Decision::Forbidden { level, action } => self.tell(
+                player,
+                "claim.action_denied",
+                &[("action", &action.name()), ("level", &level.name())],
+            ),
+        }
+
+        self.record_denial(player, &claim, key, position);
+        true
+    }
+
+    /// Counts, audits and passes on a denial of `player` in `claim`, and
+    /// warns staff once the same player has been stopped there too often.
+    fn record_denial(
+        &self,
+        player: &Player,
+        claim: &Claim,
+        key: ClaimKey,
+        position: (i32, i32, i32),
+    ) {
+        self.stats.record_denied();
+        let action = AuditAction::InteractDenied { owner: claim.owner.clone() };
+        self.audit(key, Some(player.name()), Some(position), action);
+
+        let world = self.world_name(key.world);
+        let (x, y, z) = position;
+        let args: [Arg; 6] = [
+            ("player", &player.name()),
+            ("owner", &claim.owner),
+            ("world", &world),
+            ("x", &x),
+            ("y", &y),
+            ("z", &z),
+        ];
+        self.notify(NoticeKind::InteractionDenied, &args);
+
+        let config = self.config.get();
+        let notifications = &config.notifications;
+        let threshold = notifications.denial_threshold;
+        let window = notifications.denial_window();
+        if self.notifier.record_denial(claim.id, player.name(), threshold, window) {
+            self.notify(
+                NoticeKind::RepeatedDenials,
+                &[
+                    ("player", &player.name()),
+                    ("owner", &claim.owner),
+                    ("count", &threshold),
+                    ("minutes", &notifications.denial_window_minutes),
+                    ("world", &world),
+                    ("x", &x),
+                    ("z", &z),
+                ],
             );
+        }
+    }
 
-            if !self.plugin.is_claimed_chunk(player_chunk_pos) {
-                self.plugin.claim_chunk(player, player_chunk_pos);
-            } else {
-                player.send_message("This chunk is already claimed.");
+    /// Passes a claim change on to whoever is configured to hear of it.
+    /// Claim changes come one chunk at a time; chunks claimed or unclaimed
+    /// are gathered so that growing or removing a claim is one notice.
+    fn notify_event(&self, event: &ClaimEvent) {
+        match event {
+            ClaimEvent::Created { world, chunk, owner } => {
+                let kind = NoticeKind::ClaimCreated;
+                if self.is_notified(kind) {
+                    self.notifier.add_chunk(kind, owner, self.world_name(*world), *chunk);
+                }
+            }
+            ClaimEvent::Deleted { world, chunk, owner } => {
+                let kind = NoticeKind::ClaimDeleted;
+                if self.is_notified(kind) {
+                    self.notifier.add_chunk(kind, owner, self.world_name(*world), *chunk);
+                }
+            }
+            ClaimEvent::TrustChanged { world, chunk, player, trusted } => {
+                let owner = self
+                    .claims
+                    .read(ClaimKey::new(*world, *chunk), |claim| claim.owner.clone())
+                    .unwrap_or_default();
+                let change = if *trusted { "trusted" } else { "untrusted" };
+                self.notify(
+                    NoticeKind::TrustChanged,
+                    &[
+                        ("player", player),
+                        ("change", &change),
+                        ("owner", &owner),
+                        ("world", &self.world_name(*world)),
+                        ("x", &chunk.x),
+                        ("z", &chunk.z),
+                    ],
+                )
             }
         }
     }
 
-    fn get_name(&self) -> String {
-        String::from("claim")
+    /// Sends the chunk notices gathered since the last call.
+    fn send_chunk_notices(&self) {
+        for notice in self.notifier.take_chunk_notices() {
+            self.notify(
+                notice.kind,
+                &[
+                    ("owner", &notice.owner),
+                    ("count", &notice.count),
+                    ("world", &notice.world),
+                    ("x", &notice.first.x),
+                    ("z", &notice.first.z),
+                ],
+            );
+        }
     }
 
-    fn get_usage(&self) -> String {
-        String::from("/claim")
+    fn is_notified(&self, kind: NoticeKind) -> bool {
+        let notifications = &self.config.get().notifications;
+        (!notifications.webhook_url.is_empty() && notifications.webhook_events.contains(&kind))
+            || notifications.staff_events.contains(&kind)
     }
 
-    fn get_help(&self) -> String {
-        String::from("Claims the current chunk.")
+    /// Sends the `kind` notice to the webhook and to online staff, if it is
+    /// one they are set to get. The webhook gets it in the server locale.
+    fn notify(&self, kind: NoticeKind, args: &[Arg]) {
+        let config = self.config.get();
+        let notifications = &config.notifications;
+
+        if !notifications.webhook_url.is_empty() && notifications.webhook_events.contains(&kind) {
+            let locale = &config.messages.locale;
+            let text = self.messages.render(locale, locale, kind.message_key(), args);
+            self.notifier.post(&notifications.webhook_url, text);
+        }
+        if !notifications.staff_events.contains(&kind) {
+            return;
+        }
+        if let Some(game) = &self.game {
+            for player in game.server.players() {
+                if self.permissions.has(player, permissions::NOTIFY) {
+                    self.tell(player, kind.message_key(), args);
+                }
+            }
+        }
     }
-}
 
-struct UnclaimCommand {
-    plugin: Arc<LandClaiming>,
-}
+    /// The claim covering `key` through `player`'s cached lookup, timed for
+    /// the lookup latency statistics.
+    fn claim_lookup(&self, player: &Player, key: ClaimKey) -> Option<Arc<Claim>> {
+        let started = Instant::now();
+        let claim = self.lookups.claim_at(player.name(), key, &self.claims);
+        self.stats.record_lookup(started.elapsed());
+        claim
+    }
 
-impl UnclaimCommand {
-    fn new(plugin: Arc<LandClaiming>) -> Self {
-        Self { plugin }
+    fn stats(&self) -> StatsSnapshot {
+        let (claims, chunks) = self.claims.counts();
+        self.stats.snapshot(claims, chunks, self.lookups.hits_and_misses())
     }
-}
 
-impl Command for UnclaimCommand {
-    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, _args: Vec<String>) {
-        if let Some(player) = sender.as_player() {
-            let player_chunk_pos = ChunkPosition::new(
-                player.position().x.floor() as i32,
-                player.position().z.floor() as i32,
+    fn handle_player_move(&self, event: &PlayerMoveEvent) {
+        let player = event.player();
+        let new_pos = event.new_position();
+        let config = self.config.get();
+
+        self.visualizer.refresh(player);
+        if self.homes.cancel_if_moved(player.name(), new_pos) {
+            self.tell(player, "home.cancelled", &[]);
+        }
+
+        let new_key = ClaimKey::at(player.world().id(), new_pos);
+        if config.protection.entry_bans && self.is_banned_from(player, new_key) {
+            self.eject_banned(player, event.old_position(), new_key);
+            return;
+        }
+
+        if self.flight.is_flying(player.name())
+            && !self.may_fly_at(player, new_key)
+            && self.end_flight(player)
+        {
+            self.tell(player, "fly.left_claim", &[]);
+        }
+
+        let old_key = ClaimKey::at(player.world().id(), event.old_position());
+        if old_key != new_key && self.auto_claim.is_enabled(player.name()) {
+            self.auto_claim_chunk(player, new_key);
+        }
+
+        let here = self.claim_lookup(player, new_key);
+        let presence = here.map(|claim| ClaimPresence {
+            id: claim.id,
+            key: new_key,
+            owner: claim.owner.clone(),
+        });
+
+        let crossing = match self.tracker.update(player.name(), presence) {
+            Some(crossing) => crossing,
+            None => return,
+        };
+        self.update_hud(player, crossing.entered.as_ref());
+
+        let quiet = config.rate_limits.entry_message_quiet();
+        let farewell = crossing
+            .left
+            .filter(|left| {
+                self.rate_limits.should_show_message(
+                    player.name(),
+                    left.id,
+                    EntryMessage::Farewell,
+                    quiet,
+                )
+            })
+            .and_then(|left| self.claims.read(left.key, |claim| claim.farewell.clone()))
+            .flatten();
+        if let Some(farewell) = farewell {
+            config.messages.display.send(player, &farewell);
+        }
+
+        let entered = match crossing.entered {
+            Some(entered) => entered,
+            None => return,
+        };
+
+        self.fire(ClaimEnterEvent {
+            player: player.name().to_owned(),
+            world: entered.key.world,
+            chunk: entered.key.chunk,
+            owner: entered.owner.clone(),
+        });
+
+        let greeting = EntryMessage::Greeting;
+        if !self.rate_limits.should_show_message(player.name(), entered.id, greeting, quiet) {
+            return;
+        }
+
+        let (greeting, name, sale_price, is_member) = match self.claims.read(entered.key, |claim| {
+            let is_member = self.trust_level(claim, player.name()).is_some();
+            (claim.greeting.clone(), claim.name.clone(), claim.sale_price, is_member)
+        }) {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        if let Some(greeting) = greeting {
+            config.messages.display.send(player, &greeting);
+        } else if !is_member {
+            let owner = self.owner_label(player, &entered.owner);
+            let text = match name {
+                Some(name) => self.text(
+                    player,
+                    "claim.entered_named",
+                    &[("name", &name), ("owner", &owner)],
+                ),
+                None => self.text(player, "claim.entered", &[("owner", &owner)]),
+            };
+            config.messages.display.send(player, &text);
+        }
+        if let (Some(price), false) = (sale_price, is_member) {
+            let price = self.economy.with(|economy| economy.format(price));
+            self.tell(player, "sale.for_sale", &[("price", &price)]);
+        }
+    }
+
+    /// Redraws the claim HUD of `player`, if they have it on, for the claim
+    /// they are now in. Runs on claim crossings only.
+    fn update_hud(&self, player: &Player, presence: Option<&ClaimPresence>) {
+        if !self.hud.is_enabled(player.name()) {
+            return;
+        }
+        let display = self.config.get().hud.display;
+
+        let shown = presence.and_then(|presence| {
+            self.claims.read(presence.key, |claim| {
+                let level = self.trust_level(claim, player.name());
+                (claim.name.clone(), claim.id, claim.owner.clone(), level)
+            })
+        });
+        let (name, id, owner, level) = match shown {
+            Some(shown) => shown,
+            None => {
+                let wilderness = self.text(player, "hud.wilderness", &[]);
+                return display.show(player, &wilderness, &[], &wilderness);
+            }
+        };
+
+        let title = name.unwrap_or_else(|| self.text(player, "hud.unnamed", &[("id", &id)]));
+        let owner = self.owner_label(player, &owner);
+        let trust = match level {
+            Some(level) => level.name().to_owned(),
+            None => self.text(player, "hud.no_trust", &[]),
+        };
+        let lines = [
+            self.text(player, "hud.owner", &[("owner", &owner)]),
+            self.text(player, "hud.trust", &[("trust", &trust)]),
+        ];
+        let summary = self.text(
+            player,
+            "hud.summary",
+            &[("claim", &title), ("owner", &owner), ("trust", &trust)],
+        );
+        display.show(player, &title, &lines, &summary);
+    }
+
+    fn toggle_hud(&self, player: &Player) {
+        if self.hud.toggle(player.name()) {
+            self.tell(player, "hud.enabled", &[]);
+            self.update_hud(player, self.tracker.current(player.name()).as_ref());
+        } else {
+            self.config.get().hud.display.clear(player);
+            self.tell(player, "hud.disabled", &[]);
+        }
+    }
+
+    /// Remembers block changes made inside claims by players without build
+    /// trust, so `/claim rollback` can undo them.
+    fn record_block_change(
+        &self,
+        player: &Player,
+        position: BlockPosition,
+        before: BlockId,
+        after: BlockId,
+    ) {
+        let key = ClaimKey::at_block(player.world().id(), position);
+        let untrusted = self
+            .claims
+            .read(key, |claim| self.trust_level(claim, player.name()) < Some(TrustLevel::Build))
+            .unwrap_or(false);
+
+        if untrusted {
+            self.audit(
+                key,
+                Some(player.name()),
+                Some((position.x, position.y, position.z)),
+                AuditAction::BlockChanged {
+                    before: before.vanilla_id(),
+                    after: after.vanilla_id(),
+                },
             );
+        }
+    }
 
-            self.plugin.unclaim_chunk(player, player_chunk_pos);
+    /// Cancels player-on-player damage where either side stands in a claim
+    /// without PvP, unless the two have been fighting each other within the
+    /// combat tag grace period.
+    fn handle_entity_damage(&self, event: &EntityDamageByEntityEvent) {
+        let victim = match event.victim().as_player() {
+            Some(victim) => victim,
+            None => return,
+        };
+        let attacker = match combat::responsible_player(event.damager()) {
+            Some(attacker) if attacker.name() != victim.name() => attacker,
+            _ => return,
+        };
+
+        let grace = self.config.get().protection.combat_tag();
+        let allowed = (self.pvp_allowed_at(attacker) && self.pvp_allowed_at(victim))
+            || self.combat.are_fighting(attacker.name(), victim.name());
+
+        if allowed {
+            if !grace.is_zero() {
+                self.combat.tag(attacker.name(), victim.name(), grace);
+            }
+        } else {
+            event.set_cancelled(true);
+            attacker.send_action_bar(self.text(attacker, "claim.pvp_denied", &[]));
         }
     }
 
-    fn get_name(&self) -> String {
-        String::from("unclaim")
+    /// PvP is allowed outside claims; inside one, the claim's `pvp` flag
+    /// decides, falling back to the world's setting.
+    fn pvp_allowed_at(&self, player: &Player) -> bool {
+        self.flag_at(ClaimKey::of(player), ClaimFlag::Pvp).unwrap_or(true)
     }
 
-    fn get_usage(&self) -> String {
-        String::from("/unclaim")
+    /// Stops hostile mobs spawning naturally in claims without
+    /// `mob_spawning`. This runs for every spawn attempt, so it is a single
+    /// chunk lookup and only reads the config for claims without the flag.
+    fn handle_entity_spawn(&self, event: &EntitySpawnEvent) {
+        let entity = event.entity();
+        if !entity.is_hostile() || event.reason() != SpawnReason::Natural {
+            return;
+        }
+
+        let key = ClaimKey::at(entity.world().id(), entity.position());
+        if self.flag_at(key, ClaimFlag::MobSpawning) == Some(false) {
+            event.set_cancelled(true);
+        }
     }
 
-    fn get_help(&self) -> String {
-        String::from("Unclaims the current chunk.")
+    /// Endermen, ravagers, silverfish, withers and other mobs changing
+    /// blocks one at a time.
+    fn handle_entity_change_block(&self, event: &EntityChangeBlockEvent) {
+        let entity = event.entity();
+        if let Some(player) = entity.as_player() {
+            return self.handle_trample(event, player);
+        }
+
+        let key = ClaimKey::at_block(entity.world().id(), event.position());
+        if self.flag_at(key, ClaimFlag::MobGriefing) == Some(false) {
+            event.set_cancelled(true);
+        }
+    }
+
+    /// Keeps mob explosions from breaking blocks in claims without
+    /// `mob_griefing`. The explosion still happens and hurts entities;
+    /// only the protected blocks are spared.
+    fn handle_entity_explode(&self, event: &mut EntityExplodeEvent) {
+        let entity = event.entity();
+        if !entity.is_mob() {
+            return;
+        }
+
+        let world = entity.world().id();
+        let mut protected: HashMap<ChunkPosition, bool> = HashMap::new();
+        event.blocks_mut().retain(|&position| {
+            let key = ClaimKey::at_block(world, position);
+            !*protected
+                .entry(key.chunk)
+                .or_insert_with(|| self.flag_at(key, ClaimFlag::MobGriefing) == Some(false))
+        });
+    }
+
+    fn is_banned_from(&self, player: &Player, key: ClaimKey) -> bool {
+        self.claim_lookup(player, key)
+            .map_or(false, |claim| claim.is_banned(player.name()))
+            && !self.is_bypassing(player)
+    }
+
+    fn eject_banned(&self, player: &Player, old_pos: Position, key: ClaimKey) {
+        let old_key = ClaimKey::at(player.world().id(), old_pos);
+
+        if old_key != key && !self.is_banned_from(player, old_key) {
+            player.teleport(old_pos);
+        } else {
+            player.teleport(bans::outside_of(key.chunk, player.position()));
+        }
+        player.send_action_bar(self.text(player, "ban.entry_denied", &[]));
+    }
+
+    fn get_claim_at(&self, world: WorldId, position: Position) -> Option<Claim> {
+        let key = ClaimKey::at(world, position);
+        self.claims.get(key)
+    }
+
+    /// Whether `key` is claimed, counting claims not loaded right now.
+    fn is_claimed_chunk(&self, key: ClaimKey) -> bool {
+        self.claims.contains(key) || self.claim_owner_at(key).is_some()
+    }
+
+    /// Who owns `key`, counting claims not loaded right now.
+    fn claim_owner_at(&self, key: ClaimKey) -> Option<String> {
+        self.claims.read(key, |claim| claim.owner.clone()).or_else(|| {
+            let indexed = self.residency.claim_at(key)?;
+            (!self.claims.holds(indexed.id)).then(|| indexed.owner)
+        })
+    }
+
+    /// `player`'s trust in `claim`, including what their rank gives them if
+    /// a group owns it and any trust given to everyone or to their groups.
+    /// Banned players get nothing from public or group trust.
+    fn trust_level(&self, claim: &Claim, player: &str) -> Option<TrustLevel> {
+        self.trust_level_from(claim, player, true)
+    }
+
+    /// Like `trust_level`, but leaving out public trust unless `public`, for
+    /// perks only players whom the claim names should get.
+    fn trust_level_from(&self, claim: &Claim, player: &str, public: bool) -> Option<TrustLevel> {
+        let via_group = groups::group_of_owner(&claim.owner)
+            .and_then(|group| self.groups.rank_of(group, player))
+            .map(GroupRank::trust);
+        let covers = |subject: &str| {
+            (public || subject != trust::PUBLIC) && self.shares_trust(subject, player)
+        };
+        claim.trust_with(player, covers).max(via_group)
+    }
+
+    /// Whether a trust entry for `subject` covers `player`: everyone is
+    /// covered by public trust, and `@<group>` covers members of that claim
+    /// group or of the permission group of that name.
+    fn shares_trust(&self, subject: &str, player: &str) -> bool {
+        if subject == trust::PUBLIC {
+            return true;
+        }
+        let group = match groups::group_of_owner(subject) {
+            Some(group) => group,
+            None => return false,
+        };
+
+        self.groups.rank_of(group, player).is_some()
+            || self
+                .game
+                .as_ref()
+                .and_then(|game| game.server.player(player))
+                .map_or(false, |online| self.permissions.in_group(online, group))
+    }
+
+    fn can_manage(&self, claim: &Claim, player: &str) -> bool {
+        self.trust_level(claim, player) >= Some(TrustLevel::Manage)
+    }
+
+    /// Like `can_manage`, but also lets staff manage admin claims, which
+    /// have no members of their own.
+    fn can_manage_as(&self, claim: &Claim, player: &Player) -> bool {
+        self.can_manage(claim, player.name())
+            || claim.is_admin_claim() && self.permissions.has(player, permissions::ADMIN)
+    }
+
+    /// The owner, or an officer of the group that owns the claim.
+    fn acts_for_owner(&self, claim: &Claim, player: &str) -> bool {
+        claim.owner == player
+            || groups::group_of_owner(&claim.owner)
+                .and_then(|group| self.groups.rank_of(group, player))
+                .map_or(false, |rank| rank >= GroupRank::Officer)
+    }
+
+    fn is_bypassing(&self, player: &Player) -> bool {
+        self.admin_bypass.is_bypassing(player)
+            && self.permissions.has(player, permissions::ADMIN_BYPASS)
+    }
+
+    fn text(&self, player: &Player, key: &str, args: &[Arg]) -> String {
+        let config = self.config.get();
+        let locale = if config.messages.per_player_locale {
+            player.locale()
+        } else {
+            config.messages.locale.as_str()
+        };
+        self.messages.render(locale, &config.messages.locale, key, args)
+    }
+
+    fn tell(&self, player: &Player, key: &str, args: &[Arg]) {
+        player.send_message(self.text(player, key, args));
+    }
+
+    /// Like `text`, but renders for the console in the configured locale.
+    fn text_sender(&self, sender: &dyn CommandSender, key: &str, args: &[Arg]) -> String {
+        match sender.as_player() {
+            Some(player) => self.text(player, key, args),
+            None => {
+                let locale = &self.config.get().messages.locale;
+                self.messages.render(locale, locale, key, args)
+            }
+        }
+    }
+
+    /// Like `tell`, but also reaches the console.
+    fn tell_sender(&self, sender: &dyn CommandSender, key: &str, args: &[Arg]) {
+        sender.send_message(self.text_sender(sender, key, args));
+    }
+
+    /// The chunk a command works on: the block at `<world> <x> <z>` when
+    /// `location` gives one, otherwise wherever the sending player stands.
+    /// The console always has to give a location.
+    fn command_target(
+        &self,
+        game: &Game,
+        sender: &dyn CommandSender,
+        location: &[String],
+        usage: &str,
+    ) -> Option<ClaimKey> {
+        let (world, x, z) = match (location, sender.as_player()) {
+            ([], Some(player)) => return Some(ClaimKey::of(player)),
+            ([world, x, z], _) => match (x.parse::<i32>(), z.parse::<i32>()) {
+                (Ok(x), Ok(z)) => (world, x, z),
+                _ => {
+                    self.tell_sender(sender, "general.usage", &[("usage", &usage)]);
+                    return None;
+                }
+            },
+            _ => {
+                self.tell_sender(sender, "general.usage", &[("usage", &usage)]);
+                return None;
+            }
+        };
+
+        match game.server.world_by_name(world) {
+            Some(world) => {
+                let key = ClaimKey::new(world.id(), claims::block_chunk(x, z));
+                self.load_claim_at(key);
+                Some(key)
+            }
+            None => {
+                self.tell_sender(sender, "admin.unknown_world", &[("world", world)]);
+                None
+            }
+        }
+    }
+
+    /// Counts a use of `action` by `player`, telling them and returning
+    /// false while they are over its rate limit. Admins are never limited.
+    fn within_rate_limit(&self, player: &Player, action: LimitedAction) -> bool {
+        if self.permissions.has(player, permissions::ADMIN) {
+            return true;
+        }
+
+        let limits = &self.config.get().rate_limits;
+        match self.rate_limits.try_use(player.name(), action, limits) {
+            Ok(()) => true,
+            Err(wait) => {
+                let seconds = wait.as_secs_f64().ceil() as u64;
+                self.tell(player, "general.rate_limited", &[("seconds", &seconds)]);
+                false
+            }
+        }
+    }
+
+    /// Checks `node`, telling the player when they are missing it.
+    fn require(&self, player: &Player, node: &str) -> bool {
+        if self.permissions.has(player, node) {
+            true
+        } else {
+            self.tell(player, "general.no_permission", &[]);
+            false
+        }
+    }
+
+    /// Holds `action` until `player` types `/claim confirm`, telling them
+    /// what it will do with the `prompt` message.
+    fn ask_confirmation(&self, player: &Player, action: PendingAction, prompt: &str, args: &[Arg]) {
+        self.confirmations.ask(player.name(), action);
+
+        let seconds = confirm::CONFIRM_TIMEOUT.as_secs();
+        let mut args = args.to_vec();
+        args.push(("seconds", &seconds));
+        self.tell(player, prompt, &args);
+    }
+
+    /// How many more chunks `player` may claim in their current world, or
+    /// `None` if they are unlimited.
+    fn claim_slots_left(&self, player: &Player) -> Option<usize> {
+        let config = self.config.get();
+        let world = player.world();
+        let limits = config.limits_for(world.name(), Dimension::of(world));
+        let limit = self.permissions.claim_limit(player, limits)?;
+
+        let owned_here = self
+            .claims_of(player.name())
+            .iter()
+            .filter(|key| key.world == world.id())
+            .count();
+        Some(limit.saturating_sub(owned_here))
+    }
+
+    fn has_reached_claim_limit(&self, player: &Player) -> bool {
+        self.claim_slots_left(player) == Some(0)
+    }
+
+    /// Claim blocks `player` has left after paying for every chunk they own.
+    fn remaining_claim_blocks(&self, player: &str) -> i64 {
+        let blocks = self.config.get().claim_blocks.clone();
+        let used = self.claims_of(player).len() as i64 * i64::from(blocks.per_chunk);
+        i64::from(blocks.starting) + self.claim_blocks.adjustment(player) - used
+    }
+
+    /// Takes `amount` unused claim blocks from `player`, as with
+    /// `ClaimBlocks::spend`.
+    fn spend_claim_blocks(&self, player: &str, amount: i64) -> Result<i64, i64> {
+        let blocks = self.config.get().claim_blocks.clone();
+        let used = self.claims_of(player).len() as i64 * i64::from(blocks.per_chunk);
+        self.claim_blocks.spend(player, amount, i64::from(blocks.starting) - used)
+    }
+
+    fn check_can_claim(&self, player: &Player, key: ClaimKey) -> Result<(), ClaimDenial> {
+        let config = self.config.get();
+
+        if !config.is_world_enabled(player.world().name(), Dimension::of(player.world())) {
+            return Err(ClaimDenial::WorldDisabled);
+        }
+        if self.is_claimed_chunk(key) {
+            return Err(ClaimDenial::AlreadyClaimed);
+        }
+        self.validate_claim(player.name(), key.world, &[key.chunk])?;
+        if self.has_reached_claim_limit(player) {
+            return Err(ClaimDenial::LimitReached);
+        }
+
+        self.check_claim_blocks(player, 1)
+    }
+
+    /// Checks `chunks` of `world` against the server's claim rules before
+    /// they are claimed for `owner`.
+    fn validate_claim(
+        &self,
+        owner: &str,
+        world: WorldId,
+        chunks: &[ChunkPosition],
+    ) -> Result<(), ClaimDenial> {
+        let config = self.config.get();
+        let world_name = self.world_name(world);
+        let dimension = self.world_dimension(world);
+        let rules = ClaimRules {
+            config: &config.validation,
+            world: &world_name,
+            border_radius: config.border_radius_in(&world_name, dimension),
+        };
+        rules.check(owner, chunks, |chunk| self.claim_owner_at(ClaimKey::new(world, chunk)))?;
+
+        if config.validation.reserve_nether && dimension == Dimension::Nether {
+            self.check_nether_reservation(owner, &config.overworld_of(&world_name), chunks)?;
+        }
+        Ok(())
+    }
+
+    /// Keeps `owner` out of Nether chunks whose portals lead back into land
+    /// someone else claimed in `overworld`, so nobody can build a portal
+    /// trap on the other side of a claim. Owners of any of that land may
+    /// still claim there, even when they share it with others.
+    fn check_nether_reservation(
+        &self,
+        owner: &str,
+        overworld: &str,
+        chunks: &[ChunkPosition],
+    ) -> Result<(), ClaimDenial> {
+        let game = self.game.as_ref();
+        let overworld = match game.and_then(|game| game.server.world_by_name(overworld)) {
+            Some(world) => world.id(),
+            None => return Ok(()),
+        };
+
+        for &chunk in chunks {
+            let owners = dimensions::overworld_chunks_of(chunk)
+                .filter_map(|linked| self.claim_owner_at(ClaimKey::new(overworld, linked)));
+            if let Some(other) = dimensions::reserved_by(owner, owners) {
+                return Err(ClaimDenial::NetherReserved { owner: other });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_claim_blocks(&self, player: &Player, chunks: usize) -> Result<(), ClaimDenial> {
+        let needed = chunks as i64 * i64::from(self.config.get().claim_blocks.per_chunk);
+        let remaining = self.remaining_claim_blocks(player.name());
+
+        if remaining < needed {
+            Err(ClaimDenial::InsufficientBlocks { needed, remaining })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn tell_denial(&self, sender: &dyn CommandSender, denial: ClaimDenial) {
+        match denial {
+            ClaimDenial::WorldDisabled => self.tell_sender(sender, "general.world_disabled", &[]),
+            ClaimDenial::AlreadyClaimed => self.tell_sender(sender, "claim.already_claimed", &[]),
+            ClaimDenial::LimitReached => self.tell_sender(sender, "claim.limit_reached", &[]),
+            ClaimDenial::InsufficientBlocks { needed, remaining } => self.tell_sender(
+                sender,
+                "claim.insufficient_blocks",
+                &[("needed", &needed), ("remaining", &remaining)],
+            ),
+            ClaimDenial::Overlap { owner } => {
+                let owner = self.owner_label(sender, &owner);
+                self.tell_sender(sender, "claim.overlap", &[("owner", &owner)])
+            }
+            ClaimDenial::TooClose { owner, buffer } => {
+                let owner = self.owner_label(sender, &owner);
+                self.tell_sender(
+                    sender,
+                    "claim.too_close",
+                    &[("owner", &owner), ("buffer", &buffer)],
+                )
+            }
+            ClaimDenial::Encloses { owner } => {
+                let owner = self.owner_label(sender, &owner);
+                self.tell_sender(sender, "claim.encloses", &[("owner", &owner)])
+            }
+            ClaimDenial::OutsideBorder { radius } => {
+                self.tell_sender(sender, "claim.outside_border", &[("radius", &radius)])
+            }
+            ClaimDenial::BannedRegion { name } => {
+                self.tell_sender(sender, "claim.banned_region", &[("region", &name)])
+            }
+            ClaimDenial::NetherReserved { owner } => {
+                let owner = self.owner_label(sender, &owner);
+                self.tell_sender(sender, "claim.nether_reserved", &[("owner", &owner)])
+            }
+        }
+    }
+
+    fn tell_error(&self, sender: &dyn CommandSender, error: ClaimError) {
+        match error {
+            ClaimError::NotClaimed => self.tell_sender(sender, "general.not_claimed", &[]),
+            ClaimError::AlreadyClaimed => self.tell_denial(sender, ClaimDenial::AlreadyClaimed),
+            ClaimError::NotOwner => self.tell_sender(sender, "general.not_owner", &[]),
+            ClaimError::InsufficientBlocks { needed, remaining } => {
+                self.tell_denial(sender, ClaimDenial::InsufficientBlocks { needed, remaining })
+            }
+            ClaimError::Denied(denial) => self.tell_denial(sender, denial),
+            ClaimError::Cancelled => self.tell_sender(sender, "general.cancelled", &[]),
+            ClaimError::IsOwner { subject } => {
+                self.tell_trust_error(sender, "trust.owner", &subject)
+            }
+            ClaimError::Banned { subject } => {
+                self.tell_trust_error(sender, "trust.banned", &subject)
+            }
+            ClaimError::NotTrusted { subject } => {
+                self.tell_trust_error(sender, "trust.not_trusted", &subject)
+            }
+            ClaimError::InvalidGroup => self.tell_sender(sender, "trust.invalid_group", &[]),
+            ClaimError::PublicManage => self.tell_sender(sender, "trust.public_manage", &[]),
+            ClaimError::GroupManage { group } => {
+                self.tell_sender(sender, "trust.group_manage", &[("group", &group)])
+            }
+        }
+    }
+
+    fn tell_trust_error(&self, sender: &dyn CommandSender, key: &str, subject: &str) {
+        let shown = self.trust_subject_label(sender, subject);
+        self.tell_sender(sender, key, &[("player", &shown)]);
+    }
+
+    /// Like `tell_error`, with the messages `/unclaim` has always used.
+    fn tell_unclaim_error(&self, player: &Player, error: ClaimError) {
+        match error {
+            ClaimError::NotOwner => self.tell(player, "claim.unclaim_denied", &[]),
+            ClaimError::Cancelled => self.tell(player, "claim.remove_failed", &[]),
+            error => self.tell_error(player, error),
+        }
+    }
+
+    /// How a claim owner is named to players.
+    fn owner_label(&self, sender: &dyn CommandSender, owner: &str) -> String {
+        if owner == claims::ADMIN_OWNER {
+            self.text_sender(sender, "claim.admin_owner", &[])
+        } else {
+            owner.to_owned()
+        }
+    }
+
+    /// Worlds that are not loaded count as the Overworld.
+    fn world_dimension(&self, world: WorldId) -> Dimension {
+        self.game
+            .as_ref()
+            .and_then(|game| game.server.world(world).map(Dimension::of))
+            .unwrap_or(Dimension::Overworld)
+    }
+
+    fn world_name(&self, world: WorldId) -> String {
+        self.game
+            .as_ref()
+            .and_then(|game| game.server.world(world).map(|world| world.name().to_owned()))
+            .unwrap_or_else(|| String::from("?"))
+    }
+
+    /// Records `action` in the audit log. `actor` is the player behind it,
+    /// if any; `position` the block it happened at.
+    fn audit(
+        &self,
+        key: ClaimKey,
+        actor: Option<&str>,
+        position: Option<(i32, i32, i32)>,
+        action: AuditAction,
+    ) {
+        self.audit_claim(key, self.claims.id_at(key), actor, position, action);
+    }
+
+    fn audit_claim(
+        &self,
+        key: ClaimKey,
+        claim: Option<ClaimId>,
+        actor: Option<&str>,
+        position: Option<(i32, i32, i32)>,
+        action: AuditAction,
+    ) {
+        let entry = AuditEntry {
+            time: AuditEntry::now(),
+            actor: actor.map(str::to_owned),
+            claim: claim.map(|id| id.0),
+            world: self.world_name(key.world),
+            chunk: (key.chunk.x, key.chunk.z),
+            position,
+            action,
+        };
+        self.audit_log.record(&self.saver, entry);
+    }
+
+    /// Passes `event` through the server's event manager so other plugins
+    /// can observe or cancel it.
+    fn fire<E: Event>(&self, mut event: E) -> E {
+        if let Some(game) = &self.game {
+            game.server.event_manager().call(&mut event);
+        }
+        event
+    }
+
+    fn trust_changed(&self, key: ClaimKey, player: &str, trusted: bool, actor: Option<&str>) {
+        self.fire(ClaimTrustChangeEvent {
+            world: key.world,
+            chunk: key.chunk,
+            player: player.to_owned(),
+            trusted,
+        });
+        self.listeners.emit(ClaimEvent::TrustChanged {
+            world: key.world,
+            chunk: key.chunk,
+            player: player.to_owned(),
+            trusted,
+        });
+        self.audit(
+            key,
+            actor,
+            None,
+            AuditAction::TrustChanged {
+                player: player.to_owned(),
+                trusted,
+            },
+        );
+    }
+
+    fn insert_claim(&self, owner: &str, key: ClaimKey) -> bool {
+        if self.is_claimed_chunk(key) {
+            return false;
+        }
+
+        if self.fire(ClaimCreateEvent::new(key.world, key.chunk, owner)).is_cancelled() {
+            return false;
+        }
+
+        if self.claims.claim(key, owner, &self.listeners).is_none() {
+            return false;
+        }
+        self.stats.record_created();
+        self.audit(key, None, None, AuditAction::ClaimCreated { owner: owner.to_owned() });
+        true
+    }
+
+    fn remove_claim(&self, key: ClaimKey, actor: Option<&str>) -> Option<Claim> {
+        let owner = self.claims.read(key, |claim| claim.owner.clone())?;
+        if self.fire(ClaimDeleteEvent::new(key.world, key.chunk, &owner)).is_cancelled() {
+            return None;
+        }
+
+        let claim = self.claims.unclaim(key, &self.listeners)?;
+        self.stats.record_deleted();
+        let action = AuditAction::ClaimDeleted { owner: claim.owner.clone() };
+        self.audit_claim(key, Some(claim.id), actor, None, action);
+        Some(claim)
+    }
+
+    /// Claims `key` for `player` if the claim rules and their limits allow.
+    fn claim_chunk(&self, player: &Player, key: ClaimKey) -> Result<(), ClaimError> {
+        self.check_can_claim(player, key)?;
+
+        if self.insert_claim(player.name(), key) {
+            Ok(())
+        } else if self.is_claimed_chunk(key) {
+            Err(ClaimError::AlreadyClaimed)
+        } else {
+            Err(ClaimError::Cancelled)
+        }
+    }
+
+    /// Claims `key` for a player in auto-claim mode. Chunks that are already
+    /// claimed are walked over; anything else that stops the claim also ends
+    /// the mode so the player is not told the same thing on every step.
+    /// Once they own land in a world, only chunks touching it are claimed,
+    /// so walking off does not scatter claims across the map.
+    fn auto_claim_chunk(&self, player: &Player, key: ClaimKey) {
+        if self.is_claimed_chunk(key) {
+            return;
+        }
+        if !self.extends_own_claim(player.name(), key) {
+            self.auto_claim.disable(player.name());
+            self.tell(player, "auto.not_adjacent", &[]);
+            return self.tell(player, "auto.stopped", &[]);
+        }
+
+        match self.claim_chunk(player, key) {
+            Ok(()) => {
+                let remaining = self.remaining_claim_blocks(player.name());
+                player.send_action_bar(self.text(
+                    player,
+                    "auto.claimed",
+                    &[("x", &key.chunk.x), ("z", &key.chunk.z), ("remaining", &remaining)],
+                ));
+            }
+            Err(ClaimError::AlreadyClaimed) | Err(ClaimError::Cancelled) => {}
+            Err(error) => {
+                self.auto_claim.disable(player.name());
+                self.tell_error(player, error);
+                self.tell(player, "auto.stopped", &[]);
+            }
+        }
+    }
+
+    /// Whether `key` shares an edge with a chunk `owner` claims, or they
+    /// claim nothing in its world yet.
+    fn extends_own_claim(&self, owner: &str, key: ClaimKey) -> bool {
+        let owns = |chunk| self.claim_owner_at(ClaimKey::new(key.world, chunk));
+        let touches = expansion::neighbours(key.chunk)
+            .iter()
+            .any(|&chunk| owns(chunk).as_deref() == Some(owner));
+        touches || self.claims_of(owner).iter().all(|claimed| claimed.world != key.world)
+    }
+
+    /// Tells the player and returns false unless they own `key`.
+    fn check_owner(&self, player: &Player, key: ClaimKey) -> bool {
+        match self.claims.read(key, |claim| claim.owner == player.name()) {
+            Some(true) => true,
+            Some(false) => {
+                self.tell(player, "general.not_owner", &[]);
+                false
+            }
+            None => {
+                self.tell(player, "general.not_claimed", &[]);
+                false
+            }
+        }
+    }
+
+    fn claim_chunks(&self, key: ClaimKey) -> Vec<ChunkPosition> {
+        self.claims.read(key, |claim| claim.chunks.clone()).unwrap_or_default()
+    }
+
+    fn show_outline(&self, player: &Player, key: ClaimKey) {
+        self.visualizer.show(player, self.claim_chunks(key));
+    }
+
+    /// Pushes the `direction` edge of the claim `player` is standing in out
+    /// by `rows` chunks.
+    fn expand_claim(&self, player: &Player, direction: Direction, rows: u32) {
+        let key = ClaimKey::of(player);
+        if !self.check_owner(player, key) {
+            return;
+        }
+        let world = player.world();
+        if !self.config.get().is_world_enabled(world.name(), Dimension::of(world)) {
+            return self.tell_denial(player, ClaimDenial::WorldDisabled);
+        }
+
+        let area = self.claim_chunks(key);
+        let new_chunks: Vec<ChunkPosition> = expansion::added(&area, direction, rows)
+            .into_iter()
+            .filter(|&chunk| {
+                !self.claims.is_owned_by(ClaimKey::new(key.world, chunk), player.name())
+            })
+            .collect();
+
+        if new_chunks.is_empty() {
+            return self.tell(player, "resize.nothing", &[]);
+        }
+        if let Err(denial) = self.validate_claim(player.name(), key.world, &new_chunks) {
+            return self.tell_denial(player, denial);
+        }
+        let new_keys: Vec<ClaimKey> =
+            new_chunks.into_iter().map(|chunk| ClaimKey::new(key.world, chunk)).collect();
+        if self.claim_slots_left(player).map_or(false, |left| new_keys.len() > left) {
+            return self.tell_denial(player, ClaimDenial::LimitReached);
+        }
+        if let Err(denial) = self.check_claim_blocks(player, new_keys.len()) {
+            return self.tell_denial(player, denial);
+        }
+
+        let added =
+            new_keys.iter().filter(|&&new_key| self.insert_claim(player.name(), new_key)).count();
+        if added == new_keys.len() {
+            self.tell(
+                player,
+                "resize.expanded",
+                &[("direction", &direction.name()), ("count", &added)],
+            );
+        } else {
+            self.tell(player, "resize.partial", &[("count", &added), ("total", &new_keys.len())]);
+        }
+    }
+
+    /// Pulls the `direction` edge of the claim `player` is standing in back
+    /// by `rows` chunks. The freed chunks' claim blocks return to the owner.
+    fn shrink_claim(&self, player: &Player, direction: Direction, rows: u32) {
+        let key = ClaimKey::of(player);
+        if !self.check_owner(player, key) {
+            return;
+        }
+
+        let area = self.claim_chunks(key);
+        let doomed = expansion::removed(&area, direction, rows);
+        if doomed.len() == area.len() {
+            return self.tell(player, "resize.whole_claim", &[]);
+        }
+
+        let removed = doomed
+            .into_iter()
+            .filter(|&chunk| {
+                self.remove_claim(ClaimKey::new(key.world, chunk), Some(player.name()))
+                    .is_some()
+            })
+            .count();
+        let refund = removed as i64 * i64::from(self.config.get().claim_blocks.per_chunk);
+        self.tell(
+            player,
+            "resize.shrunk",
+            &[("direction", &direction.name()), ("count", &removed), ("refund", &refund)],
+        );
+    }
+
+    fn show_claim(&self, player: &Player) {
+        let key = ClaimKey::of(player);
+
+        if self.is_claimed_chunk(key) {
+            self.show_outline(player, key);
+        } else {
+            self.tell(player, "claim.not_in_claim", &[]);
+        }
+    }
+
+    fn check_can_unclaim(&self, player: &Player, key: ClaimKey) -> Result<(), ClaimError> {
+        match self.claims.read(key, |claim| self.acts_for_owner(claim, player.name())) {
+            Some(true) => Ok(()),
+            Some(false) => Err(ClaimError::NotOwner),
+            None => Err(ClaimError::NotClaimed),
+        }
+    }
+
+    /// Unclaims `key` for `player` and returns the claim as it was.
+    fn unclaim_chunk(&self, player: &Player, key: ClaimKey) -> Result<Claim, ClaimError> {
+        self.check_can_unclaim(player, key)?;
+        self.remove_claim(key, Some(player.name())).ok_or(ClaimError::Cancelled)
+    }
+
+    fn abandon_all_claims(&self, player: &Player) {
+        let removed = self.purge_claims(player.name(), Some(player.name()));
+        self.tell(player, "abandon.done", &[("count", &removed)]);
+    }
+
+    fn set_entry_message(&self, player: &Player, kind: EntryMessage, text: Option<String>) {
+        let key = ClaimKey::of(player);
+
+        let updated = self.claims.update(key, |claim| {
+            if !self.can_manage(claim, player.name()) {
+                return None;
+            }
+
+            let slot = match kind {
+                EntryMessage::Greeting => &mut claim.greeting,
+                EntryMessage::Farewell => &mut claim.farewell,
+            };
+            *slot = text;
+            Some(slot.is_some())
+        });
+
+        let key = match (kind, updated) {
+            (_, None) => "general.not_claimed",
+            (_, Some(None)) => "general.not_owner",
+            (EntryMessage::Greeting, Some(Some(true))) => "greeting.updated",
+            (EntryMessage::Greeting, Some(Some(false))) => "greeting.cleared",
+            (EntryMessage::Farewell, Some(Some(true))) => "greeting.farewell_updated",
+            (EntryMessage::Farewell, Some(Some(false))) => "greeting.farewell_cleared",
+        };
+        self.tell(player, key, &[]);
+    }
+
+    fn flag_default(&self, key: ClaimKey, flag: ClaimFlag) -> bool {
+        let config = self.config.get();
+        match flag {
+            ClaimFlag::Pvp => {
+                config.pvp_allowed_in(&self.world_name(key.world), self.world_dimension(key.world))
+            }
+            ClaimFlag::MobSpawning => config.protection.mob_spawning,
+            ClaimFlag::MobGriefing => config.protection.mob_griefing,
+            ClaimFlag::PublicHarvest => config.protection.public_harvest,
+            ClaimFlag::Flight => config.perks.flight,
+            ClaimFlag::KeepInventory => config.perks.keep_inventory,
+        }
+    }
+
+    /// Whether `player` may fly at `key` with `/claim fly`: inside a claim
+    /// that trusts them by name or group, with the flight flag on. Public
+    /// trust is not enough, or the flag would let everyone fly.
+    fn may_fly_at(&self, player: &Player, key: ClaimKey) -> bool {
+        self.flag_at(key, ClaimFlag::Flight) == Some(true)
+            && self
+                .claims
+                .read(key, |claim| self.trust_level_from(claim, player.name(), false).is_some())
+                .unwrap_or(false)
+    }
+
+    fn toggle_flight(&self, player: &Player) {
+        if !self.require(player, permissions::FLY) {
+            return;
+        }
+        if self.end_flight(player) {
+            return self.tell(player, "fly.disabled", &[]);
+        }
+
+        let key = ClaimKey::of(player);
+        if !self.claims.contains(key) {
+            return self.tell(player, "general.not_claimed", &[]);
+        }
+        if !self.may_fly_at(player, key) {
+            return self.tell(player, "fly.not_allowed", &[]);
+        }
+        self.flight.start(player.name());
+        player.set_allow_flight(true);
+        self.tell(player, "fly.enabled", &[]);
+    }
+
+    /// Takes away flight given by `/claim fly`, and returns whether there
+    /// was any. Creative and spectator players keep flying.
+    fn end_flight(&self, player: &Player) -> bool {
+        if !self.flight.stop(player.name()) {
+            return false;
+        }
+        if !matches!(player.gamemode(), Gamemode::Creative | Gamemode::Spectator) {
+            player.set_flying(false);
+            player.set_allow_flight(false);
+        }
+        true
+    }
+
+    /// Takes `/claim fly` flight from everyone still using it, so none of
+    /// them keeps it once the plugin is gone.
+    fn end_all_flights(&self) {
+        let game = match &self.game {
+            Some(game) => game,
+            None => return,
+        };
+        for name in self.flight.players() {
+            match game.server.player(&name) {
+                Some(player) => {
+                    self.end_flight(player);
+                }
+                None => {
+                    self.flight.stop(&name);
+                }
+            }
+        }
+    }
+
+    /// Lets players who hold the permission keep their items where they are
+    /// trusted and keep_inventory is on.
+    fn handle_player_death(&self, event: &mut PlayerDeathEvent) {
+        let player = event.player();
+        self.end_flight(player);
+
+        let key = ClaimKey::of(player);
+        let keep = self.flag_at(key, ClaimFlag::KeepInventory) == Some(true)
+            && self.permissions.has(player, permissions::KEEP_INVENTORY)
+            && self
+                .claims
+                .read(key, |claim| self.trust_level(claim, player.name()).is_some())
+                .unwrap_or(false);
+        if keep {
+            event.set_keep_inventory(true);
+            event.set_keep_level(true);
+        }
+    }
+
+    /// Whether `flag` is on at `key`, or `None` outside claims.
+    fn flag_at(&self, key: ClaimKey, flag: ClaimFlag) -> Option<bool> {
+        self.claims.flag_at(key, flag, || self.flag_default(key, flag))
+    }
+
+    /// `/claim flag` and `/claimflag`:
+    /// `[claim] <flag> [on|off|default] [<world> <x> <z>]`. Without a claim
+    /// name or location, the claim the sender is standing in is meant. Only
+    /// players can name their claims; the console gives a location.
+    fn flag_command(&self, game: &Game, sender: &dyn CommandSender, args: &[String]) {
+        let usage = "/claimflag [claim] <flag> [on|off|default] [<world> <x> <z>]";
+
+        let (args, location) = split_location(args, 1);
+        let names_claim = args.len() > 1
+            && ClaimFlag::parse(&args[0]).is_none()
+            && ClaimFlag::parse(&args[1]).is_some();
+        let (key, args) = match (names_claim, sender.as_player()) {
+            (true, Some(player)) if location.is_empty() => {
+                match self.named_claim(player, &args[0]) {
+                    Some(key) => (key, &args[1..]),
+                    None => return,
+                }
+            }
+            (true, _) => return self.tell_sender(sender, "general.usage", &[("usage", &usage)]),
+            (false, _) => match self.command_target(game, sender, location, usage) {
+                Some(key) => (key, args),
+                None => return,
+            },
+        };
+
+        let flag = match args.first() {
+            Some(name) => match ClaimFlag::parse(name) {
+                Some(flag) => flag,
+                None => {
+                    let flags: Vec<&str> = ClaimFlag::ALL.iter().map(|flag| flag.name()).collect();
+                    return self.tell_sender(
+                        sender,
+                        "flag.unknown",
+                        &[("flag", name), ("flags", &flags.join(", "))],
+                    );
+                }
+            },
+            None => return self.tell_sender(sender, "general.usage", &[("usage", &usage)]),
+        };
+        let value = match args[1..] {
+            [] => None,
+            [ref value] if value == "default" => Some(None),
+            [ref value] => match flags::parse_value(value) {
+                Some(value) => Some(Some(value)),
+                None => return self.tell_sender(sender, "general.usage", &[("usage", &usage)]),
+            },
+            _ => return self.tell_sender(sender, "general.usage", &[("usage", &usage)]),
+        };
+
+        self.set_flag(sender, key, flag, value);
+    }
+
+    /// Shows `flag` for the claim at `key`, or sets it when `value` is
+    /// given. `Some(None)` resets it to the server default. The console may
+    /// change any claim.
+    fn set_flag(
+        &self,
+        sender: &dyn CommandSender,
+        key: ClaimKey,
+        flag: ClaimFlag,
+        value: Option<Option<bool>>,
+    ) {
+        let player = sender.as_player();
+        let outcome = self.claims.update(key, |claim| match value {
+            None => Ok(claim.flag(flag)),
+            Some(_) if !player.map_or(true, |player| self.can_manage_as(claim, player)) => {
+                Err(())
+            }
+            Some(Some(value)) => {
+                claim.flags.insert(flag, value);
+                Ok(Some(value))
+            }
+            Some(None) => {
+                claim.flags.remove(&flag);
+                Ok(None)
+            }
+        });
+        let current = match outcome {
+            Some(Ok(current)) => current,
+            Some(Err(())) => return self.tell_sender(sender, "general.not_owner", &[]),
+            None => return self.tell_sender(sender, "general.not_claimed", &[]),
+        };
+
+        let effective = current.unwrap_or_else(|| self.flag_default(key, flag));
+        let shown = if effective { "on" } else { "off" };
+        let message = match (value, current) {
+            (None, _) => "flag.value",
+            (Some(_), Some(_)) => "flag.set",
+            (Some(_), None) => "flag.cleared",
+        };
+        self.tell_sender(sender, message, &[("flag", &flag.name()), ("value", &shown)]);
+
+        if value.is_some() {
+            let action = AuditAction::FlagChanged { flag: flag.name().to_owned(), value: current };
+            self.audit(key, player.map(|player| player.name()), None, action);
+        }
+    }
+
+    /// Names the claim `player` is standing in, or clears its name.
+    fn name_claim(&self, player: &Player, name: Option<&str>) {
+        let key = ClaimKey::of(player);
+        let (id, owner) = match self.claims.read(key, |claim| (claim.id, claim.owner.clone())) {
+            Some(claim) => claim,
+            None => return self.tell(player, "general.not_claimed", &[]),
+        };
+
+        if let Some(name) = name {
+            if !claims::is_valid_name(name) {
+                let max = claims::MAX_NAME_LENGTH;
+                return self.tell(player, "name.invalid", &[("max", &max)]);
+            }
+            let taken = self.claims.named(&owner, name).and_then(|other| self.claims.id_at(other));
+            if taken.map_or(false, |other| other != id) {
+                return self.tell(player, "name.taken", &[("name", &name)]);
+            }
+        }
+
+        let updated = self.claims.update(key, |claim| {
+            if !self.acts_for_owner(claim, player.name()) {
+                return false;
+            }
+            claim.name = name.map(str::to_owned);
+            true
+        });
+
+        match (updated, name) {
+            (Some(true), Some(name)) => self.tell(player, "name.set", &[("name", &name)]),
+            (Some(true), None) => self.tell(player, "name.cleared", &[]),
+            (Some(false), _) => self.tell(player, "general.not_owner", &[]),
+            (None, _) => self.tell(player, "general.not_claimed", &[]),
+        }
+    }
+
+    /// `/claim heir`: who inherits the claim `player` is standing in if they
+    /// are purged or stay away past the expiration limit.
+    fn heir_command(&self, player: &Player, args: &[String]) {
+        let key = ClaimKey::of(player);
+
+        match (args.first().map(String::as_str), args.get(1)) {
+            (Some("add"), Some(heir)) => self.set_heir(player, key, heir, true),
+            (Some("remove"), Some(heir)) => self.set_heir(player, key, heir, false),
+            (None, None) | (Some("list"), None) => self.list_heirs(player, key),
+            _ => {
+                let usage = "/claim heir [list|add <player>|remove <player>]";
+                self.tell(player, "general.usage", &[("usage", &usage)])
+            }
+        }
+    }
+
+    /// Only the owner themselves may name heirs, and only players who have
+    /// been on the server. Group and admin claims never expire, so they
+    /// have none.
+    fn set_heir(&self, player: &Player, key: ClaimKey, heir: &str, add: bool) {
+        let known = self.expiry.known_name(heir).filter(|name| claims::is_player_name(name));
+        let outcome = self.claims.update(key, |claim| {
+            if claim.owner != player.name() {
+                return Err("general.not_owner");
+            }
+            let listed = claim.heirs.iter().position(|name| name.eq_ignore_ascii_case(heir));
+
+            match (add, listed) {
+                (true, Some(_)) => Err("heir.already"),
+                (true, None) if heir.eq_ignore_ascii_case(&claim.owner) => Err("heir.owner"),
+                (true, None) if claim.heirs.len() >= claims::MAX_HEIRS => Err("heir.too_many"),
+                (true, None) => match &known {
+                    Some(name) => {
+                        claim.heirs.push(name.clone());
+                        Ok("heir.added")
+                    }
+                    None => Err("heir.unknown_player"),
+                },
+                (false, Some(index)) => {
+                    claim.heirs.remove(index);
+                    Ok("heir.removed")
+                }
+                (false, None) => Err("heir.not_heir"),
+            }
+        });
+
+        match outcome {
+            Some(Ok(message)) | Some(Err(message)) => {
+                self.tell(player, message, &[("player", &heir), ("max", &claims::MAX_HEIRS)])
+            }
+            None => self.tell(player, "general.not_claimed", &[]),
+        }
+    }
+
+    fn list_heirs(&self, player: &Player, key: ClaimKey) {
+        match self.claims.read(key, |claim| claim.heirs.clone()) {
+            None => self.tell(player, "general.not_claimed", &[]),
+            Some(heirs) if heirs.is_empty() => self.tell(player, "heir.none", &[]),
+            Some(heirs) => self.tell(player, "heir.list", &[("heirs", &heirs.join(", "))]),
+        }
+    }
+
+    /// Hands each of `owner`'s claims to its first heir who has not been
+    /// away past the expiration limit themselves. Heirs never seen on the
+    /// server are passed over. Returns how many claims changed hands.
+    fn pass_to_heirs(&self, owner: &str) -> usize {
+        self.load_claims_of(owner);
+        let limit = self.config.get().expiration.inactivity_limit();
+        let online = |name: &str| {
+            self.game.as_ref().map_or(false, |game| game.server.player(name).is_some())
+        };
+        let is_available = |heir: &str| {
+            claims::is_player_name(heir) && (online(heir) || self.expiry.seen_within(heir, limit))
+        };
+
+        let mut inherited = 0;
+        for claim in self.claims.owned_by(owner) {
+            let (key, heir) = match (claim.keys().next(), claim.successor(&is_available)) {
+                (Some(key), Some(heir)) => (key, heir),
+                _ => continue,
+            };
+            if self.transfer_claim(key, &heir).is_none() {
+                continue;
+            }
+            self.claims.update(key, |claim| claim.heirs.retain(|name| *name != heir));
+            inherited += 1;
+
+            let game = self.game.as_ref();
+            if let Some(heir_player) = game.and_then(|game| game.server.player(&heir)) {
+                self.tell(heir_player, "heir.inherited", &[("owner", &owner)]);
+            }
+        }
+        inherited
+    }
+
+    /// A chunk of the claim `player` owns under `name`, telling them when
+    /// there is none.
+    fn named_claim(&self, player: &Player, name: &str) -> Option<ClaimKey> {
+        let key = self.claims.named(player.name(), name);
+        if key.is_none() {
+            self.tell(player, "name.unknown", &[("name", &name)]);
+        }
+        key
+    }
+
+    /// `/claim permissions`: shows the interaction matrix of the claim
+    /// `player` is standing in, or changes one cell of it.
+    fn edit_interactions(&self, player: &Player, args: &[String]) {
+        let usage = "/claim permissions [<access|container|build> <action> <allow|deny|default>]";
+        let key = ClaimKey::of(player);
+        if args.is_empty() {
+            return self.show_interactions(player, key);
+        }
+
+        let level = args.first().and_then(|level| TrustLevel::parse(level));
+        let action = args.get(1).and_then(|action| Interaction::parse(action));
+        let value = match args.get(2).map(String::as_str) {
+            Some("default") => Some(None),
+            Some(value) => flags::parse_value(value).map(Some),
+            None => None,
+        };
+        let (level, action, value) = match (level, action, value) {
+            (Some(level), Some(action), Some(value)) if interactions::LEVELS.contains(&level) => {
+                (level, action, value)
+            }
+            _ => return self.tell(player, "general.usage", &[("usage", &usage)]),
+        };
+
+        let updated = self.claims.update(key, |claim| {
+            if !self.can_manage_as(claim, player) {
+                return false;
+            }
+            let row = claim.interactions.entry(level).or_default();
+            match value {
+                Some(allowed) => {
+                    row.insert(action, allowed);
+                }
+                None => {
+                    row.remove(&action);
+                    if row.is_empty() {
+                        claim.interactions.remove(&level);
+                    }
+                }
+            }
+            true
+        });
+
+        match updated {
+            Some(true) => self.show_interactions(player, key),
+            Some(false) => self.tell(player, "general.not_owner", &[]),
+            None => self.tell(player, "general.not_claimed", &[]),
+        }
+    }
+
+    fn show_interactions(&self, player: &Player, key: ClaimKey) {
+        let matrix = match self.claims.read(key, |claim| claim.interactions.clone()) {
+            Some(matrix) => matrix,
+            None => return self.tell(player, "general.not_claimed", &[]),
+        };
+
+        self.tell(player, "permissions.header", &[]);
+        for action in Interaction::ALL.iter().copied() {
+            let allowed: Vec<&str> = interactions::LEVELS
+                .iter()
+                .copied()
+                .filter(|&level| interactions::allows(&matrix, level, action))
+                .map(TrustLevel::name)
+                .collect();
+            let levels = if allowed.is_empty() {
+                self.text(player, "permissions.managers_only", &[])
+            } else {
+                allowed.join(", ")
+            };
+            self.tell(
+                player,
+                "permissions.entry",
+                &[("action", &action.name()), ("levels", &levels)],
+            );
+        }
+    }
+
+    /// Makes where `player` stands the home of the claim they are in.
+    fn set_home(&self, player: &Player, name: Option<String>) {
+        let key = ClaimKey::of(player);
+        let position = player.position();
+
+        let updated = self.claims.update(key, |claim| {
+            if !self.acts_for_owner(claim, player.name()) {
+                return false;
+            }
+            claim.home = Some(ClaimHome { position, name: name.clone() });
+            true
+        });
+
+        match updated {
+            Some(true) => self.tell(player, "home.set", &[]),
+            Some(false) => self.tell(player, "general.not_owner", &[]),
+            None => self.tell(player, "general.not_claimed", &[]),
+        }
+    }
+
+    /// Claims with a home `player` may use, in a stable order for
+    /// `/claim home <index>`.
+    fn homes_of(&self, player: &str) -> Vec<Claim> {
+        // Homes are mostly used from far away, where their claims are not
+        // loaded.
+        self.load_indexed_claims(|claim| claim.has_home);
+        let allow_trusted = self.config.get().homes.allow_trusted;
+        let mut homes: Vec<Claim> = self
+            .claims
+            .all()
+            .into_iter()
+            .filter(|claim| claim.home.is_some())
+            .filter(|claim| {
+                self.acts_for_owner(claim, player)
+                    || (allow_trusted && self.trust_level(claim, player).is_some())
+            })
+            .collect();
+        homes.sort_by_key(|claim| claim.id);
+        homes
+    }
+
+    /// Sends `player` to a claim home, picked by its index or name when
+    /// they can use more than one.
+    fn go_home(&self, player: &Player, selector: Option<&str>) {
+        let homes = self.homes_of(player.name());
+
+        let chosen = match (selector, homes.len()) {
+            (_, 0) => return self.tell(player, "home.none", &[]),
+            (None, 1) => &homes[0],
+            (None, _) => return self.list_homes(player, &homes),
+            (Some(selector), _) => {
+                let found = match selector.parse::<usize>() {
+                    Ok(index) => homes.get(index.wrapping_sub(1)),
+                    Err(_) => homes.iter().find(|claim| {
+                        let home = claim.home.as_ref().and_then(|home| home.name.as_deref());
+                        home.into_iter()
+                            .chain(claim.name.as_deref())
+                            .any(|name| name.eq_ignore_ascii_case(selector))
+                    }),
+                };
+                match found {
+                    Some(claim) => claim,
+                    None => return self.tell(player, "home.unknown", &[("home", &selector)]),
+                }
+            }
+        };
+        let target = match &chosen.home {
+            Some(home) => home.position,
+            None => return,
+        };
+
+        if chosen.world != player.world().id() {
+            return self.tell(player, "home.other_world", &[]);
+        }
+        self.start_teleport(player, target, None);
+    }
+
+    /// Teleports `player` to `target` once the home warm-up is over, or at
+    /// once without one. `visiting` is the owner of a claim being visited.
+    fn start_teleport(&self, player: &Player, target: Position, visiting: Option<&str>) {
+        let config = self.config.get().homes.clone();
+        if let Some(left) = self.homes.cooldown_left(player.name(), config.cooldown()) {
+            return self.tell(player, "home.cooldown", &[("seconds", &(left.as_secs() + 1))]);
+        }
+
+        if config.warmup().is_zero() {
+            self.arrive(player, target, visiting);
+        } else {
+            let visiting = visiting.map(str::to_owned);
+            self.homes.start(player.name(), target, player.position(), config.warmup(), visiting);
+            self.tell(player, "home.warmup", &[("seconds", &config.warmup_seconds)]);
+        }
+    }
+
+    fn arrive(&self, player: &Player, target: Position, visiting: Option<&str>) {
+        player.teleport(target);
+        self.homes.record_use(player.name());
+        match visiting {
+            Some(owner) => self.tell(player, "visit.arrived", &[("owner", &owner)]),
+            None => self.tell(player, "home.teleported", &[]),
+        }
+    }
+
+    fn list_homes(&self, player: &Player, homes: &[Claim]) {
+        self.tell(player, "home.list_header", &[("count", &homes.len())]);
+        for (index, claim) in homes.iter().enumerate() {
+            let home = match &claim.home {
+                Some(home) => home,
+                None => continue,
+            };
+            let name = home
+                .name
+                .clone()
+                .or_else(|| claim.name.clone())
+                .unwrap_or_else(|| claim.id.to_string());
+            self.tell(
+                player,
+                "home.list_entry",
+                &[
+                    ("index", &(index + 1)),
+                    ("name", &name),
+                    ("owner", &claim.owner),
+                    ("world", &self.world_name(claim.world)),
+                    ("x", &(home.position.x.floor() as i32)),
+                    ("y", &(home.position.y.floor() as i32)),
+                    ("z", &(home.position.z.floor() as i32)),
+                ],
+            );
+        }
+    }
+
+    /// Finishes home and visit teleports whose warm-up is over.
+    fn teleport_homes(&self, game: &Game) {
+        for due in self.homes.take_due() {
+            if let Some(player) = game.server.player(&due.player) {
+                self.arrive(player, due.target, due.visiting.as_deref());
+            }
+        }
+    }
+
+    /// Opens the claim `player` is standing in to visitors, who arrive
+    /// where they stand now, or closes it again.
+    fn set_visitable(&self, player: &Player, open: bool) {
+        let key = ClaimKey::of(player);
+        let point = player.position();
+
+        let updated = self.claims.update(key, |claim| {
+            if !self.acts_for_owner(claim, player.name()) {
+                return None;
+            }
+            claim.visit_point = if open { Some(point) } else { None };
+            Some(claim.owner.clone())
+        });
+
+        match updated {
+            Some(Some(owner)) if open => self.tell(player, "visit.opened", &[("owner", &owner)]),
+            Some(Some(_)) => self.tell(player, "visit.closed", &[]),
+            Some(None) => self.tell(player, "general.not_owner", &[]),
+            None => self.tell(player, "general.not_claimed", &[]),
+        }
+    }
+
+    /// Sends `player` to one of `owner`'s claims that are open to visitors,
+    /// picked by its index or name when there is more than one.
+    fn visit(&self, player: &Player, owner: &str, selector: Option<&str>) {
+        let mut claims = self.listed_claims(owner);
+        claims.retain(|claim| claim.visit_point.is_some());
+
+        let chosen = match (selector, claims.len()) {
+            (_, 0) => return self.tell(player, "visit.none", &[("player", &owner)]),
+            (None, 1) => &claims[0],
+            (None, count) => {
+                self.tell(player, "visit.choose", &[("player", &owner), ("count", &count)]);
+                return self.list_visits(player, &claims);
+            }
+            (Some(selector), _) => {
+                let found = match selector.parse::<usize>() {
+                    Ok(index) => claims.get(index.wrapping_sub(1)),
+                    Err(_) => claims.iter().find(|claim| {
+                        let name = claim.name.as_deref();
+                        name.map_or(false, |name| name.eq_ignore_ascii_case(selector))
+                    }),
+                };
+                match found {
+                    Some(claim) => claim,
+                    None => {
+                        return self.tell(
+                            player,
+                            "visit.unknown",
+                            &[("player", &owner), ("claim", &selector)],
+                        )
+                    }
+                }
+            }
+        };
+        let target = match chosen.visit_point {
+            Some(point) => point,
+            None => return,
+        };
+
+        if chosen.world != player.world().id() {
+            return self.tell(player, "visit.other_world", &[]);
+        }
+        // Bans are only kept with the claim itself, so load it to see them.
+        self.load_stored_claims(&[chosen.id]);
+        let banned = self
+            .claims
+            .read(ClaimKey::at(chosen.world, target), |claim| claim.is_banned(player.name()))
+            .unwrap_or(false);
+        if banned {
+            return self.tell(player, "visit.banned", &[]);
+        }
+        self.start_teleport(player, target, Some(&chosen.owner));
+    }
+
+    /// Lists every claim open to visitors, for `/claim visits`.
+    fn list_all_visits(&self, player: &Player) {
+        let claims = self.visitable_claims();
+        if claims.is_empty() {
+            return self.tell(player, "visit.directory_empty", &[]);
+        }
+        self.tell(player, "visit.directory_header", &[("count", &claims.len())]);
+        self.list_visits(player, &claims);
+    }
+
+    fn list_visits(&self, player: &Player, claims: &[IndexedClaim]) {
+        for (index, claim) in claims.iter().enumerate() {
+            let point = match claim.visit_point {
+                Some(point) => point,
+                None => continue,
+            };
+            let name = claim.name.clone().unwrap_or_else(|| claim.id.to_string());
+            self.tell(
+                player,
+                "visit.list_entry",
+                &[
+                    ("index", &(index + 1)),
+                    ("name", &name),
+                    ("owner", &claim.owner),
+                    ("world", &self.world_name(claim.world)),
+                    ("x", &(point.x.floor() as i32)),
+                    ("z", &(point.z.floor() as i32)),
+                ],
+            );
+        }
+    }
+
+    /// Gives `subject` `level` trust in the claim at `key` on behalf of
+    /// `actor`, or takes their trust away for `None`. The console has no
+    /// actor and may change any claim. Returns whether they went from
+    /// untrusted to trusted or back; changing a level does not.
+    fn set_trust(
+        &self,
+        actor: Option<&Player>,
+        key: ClaimKey,
+        subject: &str,
+        level: Option<TrustLevel>,
+    ) -> Result<bool, ClaimError> {
+        let group = groups::group_of_owner(subject);
+        if group == Some("") {
+            return Err(ClaimError::InvalidGroup);
+        }
+        if level == Some(TrustLevel::Manage) {
+            if subject == trust::PUBLIC {
+                return Err(ClaimError::PublicManage);
+            }
+            if let Some(group) = group {
+                return Err(ClaimError::GroupManage { group: group.to_owned() });
+            }
+        }
+
+        let outcome = self.claims.update(key, |claim| {
+            let subject = subject.to_owned();
+            if !actor.map_or(true, |player| self.can_manage_as(claim, player)) {
+                return Err(ClaimError::NotOwner);
+            }
+            if subject == claim.owner {
+                return Err(ClaimError::IsOwner { subject });
+            }
+
+            match level {
+                Some(_) if claim.is_banned(&subject) => Err(ClaimError::Banned { subject }),
+                Some(level) => Ok(claim.trusted.insert(subject, level).is_none()),
+                None if claim.trusted.remove(&subject).is_some() => Ok(true),
+                None => Err(ClaimError::NotTrusted { subject }),
+            }
+        });
+        let changed = outcome.ok_or(ClaimError::NotClaimed)??;
+
+        if changed {
+            self.trust_changed(key, subject, level.is_some(), actor.map(|player| player.name()));
+        }
+        Ok(changed)
+    }
+
+    fn trust_subject_label(&self, sender: &dyn CommandSender, subject: &str) -> String {
+        if subject == trust::PUBLIC {
+            self.text_sender(sender, "trust.everyone", &[])
+        } else {
+            subject.to_owned()
+        }
+    }
+
+    /// Bans or unbans `target` from the claim `player` is standing in and
+    /// returns whether anything changed.
+    fn set_banned(&self, player: &Player, target: &str, banned: bool) -> bool {
+        let key = ClaimKey::of(player);
+
+        let outcome = self.claims.update(key, |claim| {
+            if !self.can_manage(claim, player.name()) {
+                return Err("general.not_owner");
+            }
+
+            let already_banned = claim.is_banned(target);
+            if banned {
+                if target == claim.owner {
+                    return Err("ban.self");
+                }
+                if already_banned {
+                    return Err("ban.already_banned");
+                }
+
+                let was_member = claim.trusted.remove(target).is_some();
+                claim.banned.push(target.to_owned());
+                Ok(was_member)
+            } else {
+                if !already_banned {
+                    return Err("ban.not_banned");
+                }
+
+                claim.banned.retain(|name| name != target);
+                Ok(false)
+            }
+        });
+
+        match outcome {
+            None => {
+                self.tell(player, "general.not_claimed", &[]);
+                false
+            }
+            Some(Err(message)) => {
+                self.tell(player, message, &[("player", &target)]);
+                false
+            }
+            Some(Ok(was_member)) => {
+                if was_member {
+                    self.trust_changed(key, target, false, Some(player.name()));
+                }
+
+                let message = if banned { "ban.added" } else { "ban.removed" };
+                self.tell(player, message, &[("player", &target)]);
+                true
+            }
+        }
+    }
+
+    /// Puts back every block untrusted players changed in the claim `player`
+    /// is standing in during the last `minutes`.
+    fn rollback(&self, player: &Player, minutes: u64) {
+        let key = ClaimKey::of(player);
+
+        let (chunks, allowed) = match self
+            .claims
+            .read(key, |claim| (claim.chunks.clone(), self.acts_for_owner(claim, player.name())))
+        {
+            Some(found) => found,
+            None => return self.tell(player, "general.not_claimed", &[]),
+        };
+        if !allowed && !self.permissions.has(player, permissions::ADMIN) {
+            return self.tell(player, "general.not_owner", &[]);
+        }
+
+        let world = player.world();
+        let world_name = world.name().to_owned();
+        let since = AuditEntry::now().saturating_sub(minutes * 60);
+        let changes = self.audit_log.query(|entry| {
+            entry.time >= since
+                && entry.world == world_name
+                && matches!(entry.action, AuditAction::BlockChanged { .. })
+                && chunks.contains(&ChunkPosition::new(entry.chunk.0, entry.chunk.1))
+        });
+        if changes.is_empty() {
+            return self.tell(player, "rollback.nothing", &[("minutes", &minutes)]);
+        }
+
+        // Newest first, so a block changed several times ends up as it was
+        // before the first change.
+        let mut restored = 0;
+        for entry in &changes {
+            if let (AuditAction::BlockChanged { before, .. }, Some((x, y, z))) =
+                (&entry.action, entry.position)
+            {
+                world.set_block_at(BlockPosition::new(x, y, z), BlockId::from_vanilla_id(*before));
+                restored += 1;
+            }
+        }
+
+        self.audit(
+            key,
+            Some(player.name()),
+            None,
+            AuditAction::RolledBack {
+                minutes,
+                blocks: restored,
+            },
+        );
+        self.tell(player, "rollback.done", &[("count", &restored), ("minutes", &minutes)]);
+    }
+
+    fn set_rental(&self, player: &Player, price: f64, duration: Duration) {
+        let key = ClaimKey::of(player);
+        if !self.check_owner(player, key) {
+            return;
+        }
+
+        let listed = self.claims.update(key, |claim| match &claim.rental {
+            Some(Rental { lease: Some(_), .. }) => false,
+            _ => {
+                claim.rental = Some(Rental {
+                    price,
+                    duration,
+                    lease: None,
+                });
+                true
+            }
+        });
+
+        if listed == Some(true) {
+            let price = self.economy.with(|economy| economy.format(price));
+            let duration = rental::format_duration(duration);
+            self.tell(player, "rent.listed", &[("price", &price), ("duration", &duration)]);
+        } else {
+            self.tell(player, "rent.already_rented", &[]);
+        }
+    }
+
+    fn cancel_rental(&self, player: &Player) {
+        let key = ClaimKey::of(player);
+        if !self.check_owner(player, key) {
+            return;
+        }
+
+        let cancelled = self.claims.update(key, |claim| match &claim.rental {
+            Some(Rental { lease: None, .. }) => {
+                claim.rental = None;
+                Ok(())
+            }
+            Some(_) => Err("rent.already_rented"),
+            None => Err("rent.not_listed"),
+        });
+
+        match cancelled {
+            Some(Ok(())) => self.tell(player, "rent.cancelled", &[]),
+            Some(Err(message)) => self.tell(player, message, &[]),
+            None => self.tell(player, "general.not_claimed", &[]),
+        }
+    }
+
+    /// Rents the claim `player` is standing in: the renter pays the owner
+    /// and is trusted until the lease runs out.
+    fn accept_rental(&self, game: &Game, player: &Player) {
+        let key = ClaimKey::of(player);
+
+        let offer = self.claims.read(key, |claim| {
+            if claim.owner == player.name() {
+                return Err("rent.own_claim");
+            }
+            if claim.is_banned(player.name()) {
+                return Err("ban.entry_denied");
+            }
+            match &claim.rental {
+                Some(Rental { lease: Some(_), .. }) => Err("rent.already_rented"),
+                Some(rental) => Ok((claim.owner.clone(), rental.price, rental.duration)),
+                None => Err("rent.not_listed"),
+            }
+        });
+        let (owner, price, duration) = match offer {
+            Some(Ok(offer)) => offer,
+            Some(Err(message)) => return self.tell(player, message, &[]),
+            None => return self.tell(player, "general.not_claimed", &[]),
+        };
+
+        let shown_price = self.economy.with(|economy| economy.format(price));
+        let paid = self.economy.with(|economy| {
+            if !economy.is_available() {
+                Err("economy.unavailable")
+            } else if economy.withdraw(player.name(), price) {
+                Ok(())
+            } else {
+                Err("economy.cannot_afford")
+            }
+        });
+        if let Err(message) = paid {
+            return self.tell(player, message, &[("price", &shown_price)]);
+        }
+
+        let leased = self.claims.update(key, |claim| {
+            let rental = match &mut claim.rental {
+                Some(rental) if rental.lease.is_none() && claim.owner == owner => rental,
+                _ => return false,
+            };
+            rental.lease = Some(Lease {
+                renter: player.name().to_owned(),
+                expires_at: SystemTime::now() + duration,
+                previous_trust: claim.trusted.get(player.name()).copied(),
+            });
+            let held =
+                claim.trusted.entry(player.name().to_owned()).or_insert(rental::RENTER_TRUST);
+            *held = (*held).max(rental::RENTER_TRUST);
+            true
+        });
+
+        if leased != Some(true) {
+            self.economy.with(|economy| economy.deposit(player.name(), price));
+            return self.tell(player, "rent.already_rented", &[]);
+        }
+
+        self.economy.with(|economy| economy.deposit(&owner, price));
+        self.trust_changed(key, player.name(), true, Some(player.name()));
+
+        let shown_duration = rental::format_duration(duration);
+        self.tell(
+            player,
+            "rent.accepted",
+            &[("price", &shown_price), ("duration", &shown_duration), ("owner", &owner)],
+        );
+        if let Some(owner_player) = game.server.player(&owner) {
+            self.tell(
+                owner_player,
+                "rent.accepted_notice",
+                &[
+                    ("player", &player.name()),
+                    ("price", &shown_price),
+                    ("duration", &shown_duration),
+                ],
+            );
+        }
+    }
+
+    fn set_sale(&self, player: &Player, price: f64) {
+        let key = ClaimKey::of(player);
+        if !self.check_owner(player, key) {
+            return;
+        }
+
+        let listed = self.claims.update(key, |claim| match &claim.rental {
+            Some(Rental { lease: Some(_), .. }) => false,
+            _ => {
+                claim.sale_price = Some(price);
+                true
+            }
+        });
+
+        if listed == Some(true) {
+            let price = self.economy.with(|economy| economy.format(price));
+            self.tell(player, "sale.listed", &[("price", &price)]);
+        } else {
+            self.tell(player, "sale.rented", &[]);
+        }
+    }
+
+    fn cancel_sale(&self, player: &Player) {
+        let key = ClaimKey::of(player);
+        if !self.check_owner(player, key) {
+            return;
+        }
+
+        match self.claims.update(key, |claim| claim.sale_price.take()) {
+            Some(Some(_)) => self.tell(player, "sale.cancelled", &[]),
+            Some(None) => self.tell(player, "sale.not_listed", &[]),
+            None => self.tell(player, "general.not_claimed", &[]),
+        }
+    }
+
+    /// Asks `player` to confirm buying the claim they are standing in at
+    /// its listed price.
+    fn offer_purchase(&self, player: &Player) {
+        let key = ClaimKey::of(player);
+        if let Some((seller, price)) = self.check_purchase(player, key, None) {
+            let shown_price = self.economy.with(|economy| economy.format(price));
+            let chunks = self.claim_chunks(key).len();
+            self.ask_confirmation(
+                player,
+                PendingAction::Buy { key, price },
+                "confirm.buy",
+                &[("seller", &seller), ("price", &shown_price), ("chunks", &chunks)],
+            );
+        }
+    }
+
+    /// The seller and price of claim `key` if `player` may buy it, telling
+    /// them why not otherwise. With `price` given, the listing must still
+    /// ask for exactly that.
+    fn check_purchase(
+        &self,
+        player: &Player,
+        key: ClaimKey,
+        price: Option<f64>,
+    ) -> Option<(String, f64)> {
+        let listing = self.claims.read(key, |claim| {
+            if claim.owner == player.name() {
+                return Err("sale.own_claim");
+            }
+            if claim.is_banned(player.name()) {
+                return Err("ban.entry_denied");
+            }
+            if matches!(claim.rental, Some(Rental { lease: Some(_), .. })) {
+                return Err("sale.rented");
+            }
+            match (claim.sale_price, price) {
+                (None, _) => Err("sale.not_listed"),
+                (Some(listed), Some(price)) if listed != price => Err("sale.changed"),
+                (Some(listed), _) => Ok((claim.owner.clone(), listed, claim.chunks.len())),
+            }
+        });
+        let (seller, price, size) = match listing {
+            Some(Ok(listing)) => listing,
+            Some(Err(message)) => {
+                self.tell(player, message, &[]);
+                return None;
+            }
+            None => {
+                self.tell(player, "general.not_claimed", &[]);
+                return None;
+            }
+        };
+
+        let denial = if self.claim_slots_left(player).map_or(false, |left| size > left) {
+            Err(ClaimDenial::LimitReached)
+        } else {
+            self.check_claim_blocks(player, size)
+        };
+        match denial {
+            Ok(()) => Some((seller, price)),
+            Err(denial) => {
+                self.tell_denial(player, denial);
+                None
+            }
+        }
+    }
+
+    /// Buys claim `key` for `player` at `price`, once they confirmed it. The
+    /// price is taken from the buyer first and only paid to the seller once
+    /// the claim is theirs, so a listing that changed in between is
+    /// refunded instead.
+    fn buy_claim(&self, game: &Game, player: &Player, key: ClaimKey, price: f64) {
+        let seller = match self.check_purchase(player, key, Some(price)) {
+            Some((seller, _)) => seller,
+            None => return,
+        };
+
+        let shown_price = self.economy.with(|economy| economy.format(price));
+        let paid = self.economy.with(|economy| {
+            if !economy.is_available() {
+                Err("economy.unavailable")
+            } else if economy.withdraw(player.name(), price) {
+                Ok(())
+            } else {
+                Err("economy.cannot_afford")
+            }
+        });
+        if let Err(message) = paid {
+            return self.tell(player, message, &[("price", &shown_price)]);
+        }
+
+        // The buyer starts with the claim as it stands, without the seller's
+        // friends, heirs or rental listing. The listing is checked in the
+        // same update that hands the claim over.
+        let sold = self.hand_over(key, Some(&seller), player.name(), |claim| {
+            let unchanged = claim.sale_price == Some(price)
+                && !matches!(claim.rental, Some(Rental { lease: Some(_), .. }));
+            if !unchanged {
+                return None;
+            }
+            claim.rental = None;
+            claim.heirs.clear();
+            Some(claim.trusted.drain().map(|(name, _)| name).collect::<Vec<_>>())
+        });
+        let untrusted = match sold {
+            Some((_, untrusted)) => untrusted,
+            None => {
+                self.economy.with(|economy| economy.deposit(player.name(), price));
+                return self.tell(player, "sale.changed", &[]);
+            }
+        };
+
+        self.economy.with(|economy| economy.deposit(&seller, price));
+        for name in &untrusted {
+            if name != player.name() {
+                self.trust_changed(key, name, false, Some(player.name()));
+            }
+        }
+        let action = AuditAction::ClaimSold { seller: seller.clone(), price };
+        self.audit(key, Some(player.name()), None, action);
+
+        self.tell(player, "sale.bought", &[("seller", &seller), ("price", &shown_price)]);
+        if let Some(seller_player) = game.server.player(&seller) {
+            self.tell(
+                seller_player,
+                "sale.sold_notice",
+                &[
+                    ("player", &player.name()),
+                    ("price", &shown_price),
+                    ("x", &key.chunk.x),
+                    ("z", &key.chunk.z),
+                ],
+            );
+        }
+    }
+
+    /// Ends leases that have run out, putting each renter's trust back to
+    /// what it was before and telling both sides if they are online.
+    fn end_expired_leases(&self, game: &Game) {
+        let now = SystemTime::now();
+        self.load_indexed_claims(|claim| claim.lease_expires.map_or(false, |at| at <= now));
+        let due: Vec<ClaimKey> = self
+            .claims
+            .all()
+            .into_iter()
+            .filter(|claim| {
+                claim
+                    .rental
+                    .as_ref()
+                    .and_then(|rental| rental.lease.as_ref())
+                    .map_or(false, |lease| lease.expires_at <= now)
+            })
+            .filter_map(|claim| claim.keys().next())
+            .collect();
+
+        for key in due {
+            let ended = self.claims.update(key, |claim| {
+                let lease = claim.rental.as_mut()?.lease.take()?;
+                match lease.previous_trust {
+                    Some(level) => claim.trusted.insert(lease.renter.clone(), level),
+                    None => claim.trusted.remove(&lease.renter),
+                };
+                Some((claim.owner.clone(), lease))
+            });
+            let (owner, lease) = match ended.flatten() {
+                Some(ended) => ended,
+                None => continue,
+            };
+
+            if lease.previous_trust.is_none() {
+                self.trust_changed(key, &lease.renter, false, None);
+            }
+            if let Some(renter) = game.server.player(&lease.renter) {
+                self.tell(renter, "rent.ended", &[("owner", &owner)]);
+            }
+            if let Some(owner_player) = game.server.player(&owner) {
+                self.tell(owner_player, "rent.ended_notice", &[("player", &lease.renter)]);
+            }
+        }
+    }
+
+    /// Charges upkeep on claims that have fallen due. Claims whose owner
+    /// cannot pay get a grace period and are unclaimed once it runs out.
+    fn collect_upkeep(&self, game: &Game) {
+        let config = self.config.get().upkeep.clone();
+        if !config.enabled {
+            return;
+        }
+
+        let now = SystemTime::now();
+        self.load_indexed_claims(|claim| {
+            !config.is_exempt(&claim.owner) && claim.upkeep_due.map_or(true, |due| due <= now)
+        });
+        for claim in self.claims.all() {
+            let key = match claim.keys().next() {
+                Some(key) if !config.is_exempt(&claim.owner) => key,
+                _ => continue,
+            };
+            let state = match claim.upkeep {
+                Some(state) if state.paid_until <= now => state,
+                Some(_) => continue,
+                None => {
+                    let state = UpkeepState::starting_now();
+                    self.claims.update(key, |claim| claim.upkeep = Some(state));
+                    continue;
+                }
+            };
+
+            let cost = config.per_chunk_per_day * claim.chunks.len() as f64;
+            if self.charge_upkeep(&claim.owner, cost, config.charge) {
+                self.claims.update(key, |claim| {
+                    if let Some(state) = &mut claim.upkeep {
+                        state.paid(now);
+                    }
+                });
+                continue;
+            }
+
+            match state.overdue_since {
+                None => {
+                    self.claims.update(key, |claim| {
+                        if let Some(state) = &mut claim.upkeep {
+                            state.overdue_since = Some(now);
+                        }
+                    });
+                    if let Some(owner) = game.server.player(&claim.owner) {
+                        self.tell_overdue(owner, &claim, now, now);
+                    }
+                }
+                Some(since) if now >= since + config.grace() => {
+                    self.unclaim_unpaid(game, &claim);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn charge_upkeep(&self, owner: &str, cost: f64, charge: UpkeepCharge) -> bool {
+        match charge {
+            UpkeepCharge::ClaimBlocks => {
+                let blocks = cost.ceil() as i64;
+                if self.remaining_claim_blocks(owner) < blocks {
+                    return false;
+                }
+                self.claim_blocks.adjust(owner, -blocks);
+                true
+            }
+            UpkeepCharge::Currency => self
+                .economy
+                .with(|economy| economy.is_available() && economy.withdraw(owner, cost)),
+        }
+    }
+
+    fn format_upkeep(&self, player: &Player, cost: f64, charge: UpkeepCharge) -> String {
+        match charge {
+            UpkeepCharge::ClaimBlocks => {
+                let blocks = cost.ceil() as i64;
+                self.text(player, "upkeep.claim_blocks", &[("count", &blocks)])
+            }
+            UpkeepCharge::Currency => self.economy.with(|economy| economy.format(cost)),
+        }
+    }
+
+    fn tell_overdue(&self, owner: &Player, claim: &Claim, since: SystemTime, now: SystemTime) {
+        let config = self.config.get().upkeep.clone();
+        let cost = config.per_chunk_per_day * claim.chunks.len() as f64;
+        let days = upkeep::grace_days_left(since, config.grace(), now);
+        let first = claim.chunks[0];
+
+        self.tell(
+            owner,
+            "upkeep.overdue",
+            &[
+                ("cost", &self.format_upkeep(owner, cost, config.charge)),
+                ("chunks", &claim.chunks.len()),
+                ("x", &first.x),
+                ("z", &first.z),
+                ("days", &days),
+            ],
+        );
+    }
+
+    fn unclaim_unpaid(&self, game: &Game, claim: &Claim) {
+        let removed = claim.keys().filter(|&key| self.remove_claim(key, None).is_some()).count();
+        if removed == 0 {
+            return;
+        }
+
+        let first = claim.chunks[0];
+        match game.server.player(&claim.owner) {
+            Some(owner) => {
+                self.tell(owner, "upkeep.unclaimed", &[("x", &first.x), ("z", &first.z)])
+            }
+            None => self.upkeep.record_lost(&claim.owner, (first.x, first.z)),
+        }
+    }
+
+    /// Tells an owner who just joined about claims with unpaid upkeep and
+    /// any they lost while away.
+    fn tell_upkeep_notices(&self, player: &Player) {
+        for (x, z) in self.upkeep.take_lost(player.name()) {
+            self.tell(player, "upkeep.unclaimed", &[("x", &x), ("z", &z)]);
+        }
+
+        if !self.config.get().upkeep.enabled {
+            return;
+        }
+        let now = SystemTime::now();
+        self.load_indexed_claims(|claim| claim.owner == player.name() && claim.upkeep_overdue);
+        for claim in self.claims.owned_by(player.name()) {
+            if let Some(since) = claim.upkeep.and_then(|state| state.overdue_since) {
+                self.tell_overdue(player, &claim, since, now);
+            }
+        }
+    }
+
+    /// Claims the chunks covered by each imported claim for its owner and
+    /// copies its trust. Chunks someone already holds are left alone.
+    fn import_claims(&self, game: &Game, imported: &[ImportedClaim], report: &mut ImportReport) {
+        for claim in imported {
+            let world = match game.server.world_by_name(&claim.world) {
+                Some(world) => world.id(),
+                None => {
+                    report.unknown_worlds += 1;
+                    continue;
+                }
+            };
+
+            let mut first_key = None;
+            for (x, z) in claim.chunks() {
+                let key = ClaimKey::new(world, ChunkPosition::new(x, z));
+
+                if self.claims.is_owned_by(key, &claim.owner) {
+                    first_key.get_or_insert(key);
+                    continue;
+                }
+                match self.validate_claim(&claim.owner, world, &[key.chunk]) {
+                    Ok(()) | Err(ClaimDenial::Overlap { .. }) => {}
+                    Err(_) => {
+                        report.rejected_chunks += 1;
+                        continue;
+                    }
+                }
+                if self.insert_claim(&claim.owner, key) {
+                    first_key.get_or_insert(key);
+                    report.chunks += 1;
+                } else {
+                    report.taken_chunks += 1;
+                }
+            }
+
+            let key = match first_key {
+                Some(key) => key,
+                None => continue,
+            };
+            report.claims += 1;
+
+            let added: Vec<String> = self
+                .claims
+                .update(key, |target| {
+                    let mut added = Vec::new();
+                    for (player, level) in &claim.trusted {
+                        if *player == target.owner {
+                            continue;
+                        }
+                        let held = target.trusted.entry(player.clone()).or_insert_with(|| {
+                            added.push(player.clone());
+                            *level
+                        });
+                        *held = (*held).max(*level);
+                    }
+                    added
+                })
+                .unwrap_or_default();
+            for player in added {
+                self.trust_changed(key, &player, true, None);
+            }
+        }
+    }
+
+    /// Reads every saved claim back. With lazy loading on, claims are only
+    /// indexed here and are loaded as their chunks load.
+    fn load_claims(&self, game: &Game) -> std::io::Result<usize> {
+        let stored = self.storage.driver().load_claims()?;
+        let highest_id = stored.iter().map(|claim| claim.id).max().unwrap_or(0);
+
+        let claims: Vec<Claim> = stored
+            .iter()
+            .filter_map(|claim| match game.server.world_by_name(&claim.world) {
+                Some(world) => Some(claim.to_claim(world.id())),
+                None => {
+                    log::warn!("Not loading claim #{} in unknown world {}", claim.id, claim.world);
+                    None
+                }
+            })
+            .collect();
+
+        if !self.config.get().storage.lazy_loading {
+            return Ok(self.claims.restore(claims, highest_id));
+        }
+        self.residency.index(&claims);
+        self.claims.restore(Vec::new(), highest_id);
+        Ok(claims.len())
+    }
+
+    /// Reads back last-seen times and claim block adjustments.
+    fn load_players(&self) -> std::io::Result<()> {
+        let players = self.storage.driver().load_players()?;
+        self.expiry.restore(
+            players
+                .iter()
+                .filter_map(|player| Some((player.name.clone(), player.last_seen()?))),
+        );
+        self.claim_blocks.restore(
+            players
+                .iter()
+                .filter(|player| player.claim_blocks != 0)
+                .map(|player| (player.name.clone(), player.claim_blocks)),
+        );
+        Ok(())
+    }
+
+    /// Everything kept about players, merged into one record per name.
+    fn player_records(&self) -> Vec<StoredPlayer> {
+        let last_seen = self.expiry.last_seen();
+        let adjustments = self.claim_blocks.adjustments();
+        let mut names: Vec<&String> = last_seen.keys().chain(adjustments.keys()).collect();
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .map(|name| {
+                let blocks = adjustments.get(name).copied().unwrap_or(0);
+                StoredPlayer::new(name, last_seen.get(name).copied(), blocks)
+            })
+            .collect()
+    }
+
+    /// Counts the chunks that were loaded before the plugin was enabled.
+    fn load_resident_claims(&self, game: &Game) {
+        if !self.residency.is_enabled() {
+            return;
+        }
+        for world in game.server.worlds() {
+            for chunk in world.loaded_chunks() {
+                self.chunk_loaded(ClaimKey::new(world.id(), chunk));
+            }
+        }
+    }
+
+    fn chunk_loaded(&self, key: ClaimKey) {
+        let ids = self.residency.chunk_loaded(key);
+        self.load_stored_claims(&ids);
+    }
+
+    /// Reads the claims `ids` back from storage, unless memory already has
+    /// the latest word on them.
+    fn load_stored_claims(&self, ids: &[ClaimId]) {
+        let ids: Vec<u64> =
+            ids.iter().filter(|&&id| !self.claims.holds(id)).map(|id| id.0).collect();
+        let game = match &self.game {
+            Some(game) if !ids.is_empty() => game,
+            _ => return,
+        };
+
+        let stored = match self.storage.driver().load_claims_by_id(&ids) {
+            Ok(stored) => stored,
+            Err(err) => {
+                log::warn!("Could not load {} LandClaiming claim(s): {}", ids.len(), err);
+                return;
+            }
+        };
+        let claims = stored
+            .iter()
+            .filter_map(|claim| {
+                let world = game.server.world_by_name(&claim.world)?;
+                Some(claim.to_claim(world.id()))
+            })
+            .collect();
+        for id in self.claims.admit(claims) {
+            log::warn!("Not loading claim #{}, which overlaps a claim already loaded", id.0);
+        }
+    }
+
+    /// Drops claims around chunks that have stayed unloaded. They stay in
+    /// storage and in the index. Claims whose latest change has not been
+    /// written yet are kept until it has.
+    fn unload_idle_claims(&self) {
+        if !self.residency.is_enabled() {
+            return;
+        }
+        let after = self.config.get().storage.unload_after();
+        let mut idle = self.residency.evictable(after, self.claims.ids());
+        idle.retain(|&id| self.saver.is_written(id));
+        if !idle.is_empty() {
+            self.claims.evict(&idle);
+        }
+    }
+
+    /// Every claim of `owner`, including those not loaded right now.
+    fn listed_claims(&self, owner: &str) -> Vec<IndexedClaim> {
+        let loaded = self.claims.owned_by(owner).iter().map(IndexedClaim::of).collect();
+        self.with_unloaded(loaded, || self.residency.owned_by(owner))
+    }
+
+    /// Every claim, including those not loaded right now.
+    fn all_listed_claims(&self) -> Vec<IndexedClaim> {
+        let loaded = self.claims.all().iter().map(IndexedClaim::of).collect();
+        self.with_unloaded(loaded, || self.residency.all())
+    }
+
+    /// Every claim open to visitors, loaded or not, by owner.
+    fn visitable_claims(&self) -> Vec<IndexedClaim> {
+        let loaded = self
+            .claims
+            .all()
+            .iter()
+            .filter(|claim| claim.visit_point.is_some())
+            .map(IndexedClaim::of)
+            .collect();
+        let mut listed = self.with_unloaded(loaded, || self.residency.visitable());
+        listed.sort_by(|a, b| a.owner.cmp(&b.owner).then(a.id.cmp(&b.id)));
+        listed
+    }
+
+    /// `loaded`, along with what `indexed` finds of the claims not loaded
+    /// when lazy loading is on, by id.
+    fn with_unloaded(
+        &self,
+        loaded: Vec<IndexedClaim>,
+        indexed: impl FnOnce() -> Vec<IndexedClaim>,
+    ) -> Vec<IndexedClaim> {
+        if !self.residency.is_enabled() {
+            return loaded;
+        }
+
+        let mut listed = indexed();
+        listed.retain(|indexed| !self.claims.holds(indexed.id));
+        listed.extend(loaded);
+        listed.sort_by_key(|claim| claim.id);
+        listed
+    }
+
+    /// Tab-completion for a command taking `syntax`, from what `sender`
+    /// has typed so far.
+    fn complete(
+        &self,
+        game: &Game,
+        sender: &dyn CommandSender,
+        syntax: &Syntax,
+        args: &[String],
+    ) -> Vec<String> {
+        syntax.complete(args, |arg| self.suggest(game, sender, arg))
+    }
+
+    fn suggest(&self, game: &Game, sender: &dyn CommandSender, arg: ArgKind) -> Vec<String> {
+        let online = || game.server.players().map(|player| player.name().to_owned());
+        match arg {
+            ArgKind::Player => online().collect(),
+            ArgKind::TrustSubject => {
+                let groups = self.groups.names().into_iter().map(|name| format!("@{}", name));
+                online().chain(std::iter::once(trust::PUBLIC.to_owned())).chain(groups).collect()
+            }
+            ArgKind::ClaimName => match sender.as_player() {
+                Some(player) => self
+                    .listed_claims(player.name())
+                    .into_iter()
+                    .filter_map(|claim| claim.name)
+                    .collect(),
+                None => Vec::new(),
+            },
+            ArgKind::Group => self.groups.names(),
+            ArgKind::Either(first, second) => {
+                let mut names = self.suggest(game, sender, *first);
+                names.extend(self.suggest(game, sender, *second));
+                names
+            }
+            _ => arg.fixed_suggestions(),
+        }
+    }
+
+    /// Registers the jobs that keep claims maintained while the server runs.
+    fn schedule_maintenance(&self) {
+        let schedule = |task: Task<Self>| self.maintenance.register(self, task);
+
+        schedule(Task::new("expire_claims", |_| expiration::CHECK_INTERVAL, |plugin: &Self| {
+            plugin.with_game(|game| plugin.expire_inactive_claims(game))
+        }));
+        schedule(Task::new("end_leases", |_| rental::CHECK_INTERVAL, |plugin: &Self| {
+            plugin.with_game(|game| plugin.end_expired_leases(game))
+        }));
+        schedule(Task::new("collect_upkeep", |_| upkeep::CHECK_INTERVAL, |plugin: &Self| {
+            plugin.with_game(|game| plugin.collect_upkeep(game))
+        }));
+        schedule(Task::new("refresh_webmap", |_| webmap::REFRESH_INTERVAL, |plugin: &Self| {
+            plugin.with_game(|game| plugin.refresh_webmap(game))
+        }));
+        schedule(Task::new(
+            "save_claims",
+            |plugin: &Self| plugin.config.get().storage.flush_interval(),
+            Self::submit_claim_changes,
+        ));
+        schedule(Task::new(
+            "send_chunk_notices",
+            |_| notifier::CHUNK_NOTICE_INTERVAL,
+            Self::send_chunk_notices,
+        ));
+        schedule(Task::new(
+            "unload_idle_claims",
+            |_| residency::EVICTION_INTERVAL,
+            Self::unload_idle_claims,
+        ));
+    }
+
+    fn with_game(&self, job: impl FnOnce(&Game)) {
+        if let Some(game) = &self.game {
+            job(game);
+        }
+    }
+
+    /// Hands claims and players changed since the last save to the
+    /// background writer.
+    fn submit_claim_changes(&self) {
+        let (changed, removed) = self.claims.take_changes();
+        self.residency.update(&changed, &removed);
+        let players_changed = self.expiry.take_changed() | self.claim_blocks.take_changed();
+        self.saver.submit(ClaimBatch {
+            changed: changed
+                .iter()
+                .map(|claim| StoredClaim::new(claim, &self.world_name(claim.world)))
+                .collect(),
+            removed: removed.into_iter().map(|id| id.0).collect(),
+            players: players_changed.then(|| self.player_records()),
+            audit: Vec::new(),
+        });
+    }
+
+    /// Writes every claim to `path`, loading those only in storage first.
+    fn export_claims(&self, path: &Path) -> Result<usize, SnapshotError> {
+        self.load_indexed_claims(|_| true);
+        let mut claims = self.claims.all();
+        claims.sort_by_key(|claim| claim.id);
+        let stored: Vec<StoredClaim> = claims
+            .iter()
+            .map(|claim| StoredClaim::new(claim, &self.world_name(claim.world)))
+            .collect();
+
+        snapshot::write(path, &ClaimSnapshot::new(stored))?;
+        Ok(claims.len())
+    }
+
+    /// Replaces every claim with those in the snapshot at `path`, returning
+    /// how many were restored and how many were skipped for being in worlds
+    /// this server does not have. Each claim deleted or restored is
+    /// audited under `actor`.
+    fn restore_claims(
+        &self,
+        game: &Game,
+        path: &Path,
+        actor: Option<&str>,
+    ) -> Result<(usize, usize), SnapshotError> {
+        let snapshot = snapshot::read(path)?;
+
+        let mut skipped = 0;
+        let claims = snapshot
+            .claims
+            .iter()
+            .filter_map(|claim| match game.server.world_by_name(&claim.world) {
+                Some(world) => Some(claim.to_claim(world.id())),
+                None => {
+                    skipped += 1;
+                    None
+                }
+            })
+            .collect();
+
+        // Claims only in storage have to go too, so load them first.
+        self.load_indexed_claims(|_| true);
+
+        let before = self.claims.all();
+        let restored = self.claims.replace_all(claims, &self.listeners);
+        let after = self.claims.all();
+        let unchanged = |claim: &Claim, others: &[Claim]| {
+            others.iter().any(|other| other.id == claim.id && other.owner == claim.owner)
+        };
+        for claim in before.iter().filter(|claim| !unchanged(claim, &after)) {
+            if let Some(key) = claim.keys().next() {
+                let action = AuditAction::ClaimDeleted { owner: claim.owner.clone() };
+                self.audit_claim(key, Some(claim.id), actor, None, action);
+            }
+        }
+        for claim in after.iter().filter(|claim| !unchanged(claim, &before)) {
+            if let Some(key) = claim.keys().next() {
+                let action = AuditAction::ClaimCreated { owner: claim.owner.clone() };
+                self.audit_claim(key, Some(claim.id), actor, None, action);
+            }
+        }
+        Ok((restored, skipped))
+    }
+
+    fn tell_restored(
+        &self,
+        sender: &dyn CommandSender,
+        path: &Path,
+        result: Result<(usize, usize), SnapshotError>,
+    ) {
+        match result {
+            Ok((restored, skipped)) => self.tell_sender(
+                sender,
+                "admin.restored",
+                &[("count", &restored), ("skipped", &skipped), ("file", &path.display())],
+            ),
+            Err(err) => self.tell_sender(sender, "admin.snapshot_failed", &[("error", &err)]),
+        }
+    }
+
+    fn refresh_webmap(&self, game: &Game) {
+        let config = self.config.get();
+        self.webmap.refresh(game, &config.webmap, || self.all_listed_claims());
+    }
+
+    /// Removes every claim `owner` has, for an admin who confirmed it.
+    /// Claims with an available heir go to them instead.
+    fn admin_purge(&self, admin: &Player, owner: &str) {
+        if !self.require(admin, permissions::ADMIN) {
+            return;
+        }
+
+        let inherited = self.pass_to_heirs(owner);
+        let removed = self.purge_claims(owner, Some(admin.name()));
+        self.tell(
+            admin,
+            "admin.purged",
+            &[("count", &removed), ("player", &owner), ("inherited", &inherited)],
+        );
+    }
+
+    /// `admin` is `None` when the console deletes the claim.
+    fn admin_delete_claim(&self, key: ClaimKey, admin: Option<&str>) -> Option<Claim> {
+        self.remove_claim(key, admin)
+    }
+
+    /// Makes every unclaimed chunk within `radius` chunks of `center` part of
+    /// an admin claim: nobody may build there and PvP, mob spawning and mob
+    /// griefing start off. Returns how many chunks were claimed and how
+    /// many were skipped because someone already held them.
+    fn define_admin_claim(&self, center: ClaimKey, radius: u32) -> (usize, usize) {
+        let radius = radius as i32;
+        let (mut added, mut taken) = (0, 0);
+
+        for x in center.chunk.x - radius..=center.chunk.x + radius {
+            for z in center.chunk.z - radius..=center.chunk.z + radius {
+                let key = ClaimKey::new(center.world, ChunkPosition::new(x, z));
+                if self.claims.is_owned_by(key, claims::ADMIN_OWNER) {
+                    continue;
+                }
+                if !self.insert_claim(claims::ADMIN_OWNER, key) {
+                    taken += 1;
+                    continue;
+                }
+                added += 1;
+                self.claims.update(key, |claim| {
+                    for flag in ClaimFlag::ALL.iter().copied() {
+                        claim.flags.entry(flag).or_insert(false);
+                    }
+                });
+            }
+        }
+        (added, taken)
+    }
+
+    fn transfer_claim(&self, key: ClaimKey, new_owner: &str) -> Option<String> {
+        self.hand_over(key, None, new_owner, |_| Some(())).map(|(previous, ())| previous)
+    }
+
+    /// Like `transfer_claim`, but only while `owner` still owns the claim.
+    /// The owner is checked in the same update that hands it over, so a
+    /// claim that changed hands since an offer was made is never taken.
+    fn transfer_claim_from(&self, key: ClaimKey, owner: &str, new_owner: &str) -> Option<String> {
+        self.hand_over(key, Some(owner), new_owner, |_| Some(())).map(|(previous, ())| previous)
+    }
+
+    /// Hands the claim at `key` to `new_owner` if it still belongs to
+    /// `owner`, when given, and `prepare` agrees. `prepare` runs in the
+    /// same update, so whatever it checks cannot change before the claim
+    /// changes hands. Returns the previous owner and what `prepare` gave.
+    fn hand_over<T>(
+        &self,
+        key: ClaimKey,
+        owner: Option<&str>,
+        new_owner: &str,
+        prepare: impl FnOnce(&mut Claim) -> Option<T>,
+    ) -> Option<(String, T)> {
+        // The new owner may already use the name for one of their claims.
+        let name_taken = self
+            .claims
+            .read(key, |claim| claim.name.clone())
+            .flatten()
+            .map_or(false, |name| self.claims.named(new_owner, &name).is_some());
+
+        let (previous_owner, prepared) = self.claims.update(key, |claim| {
+            if owner.map_or(false, |owner| claim.owner != owner) {
+                return None;
+            }
+            let prepared = prepare(claim)?;
+            claim.trusted.remove(new_owner);
+            claim.sale_price = None;
+            if name_taken {
+                claim.name = None;
+            }
+            Some((std::mem::replace(&mut claim.owner, new_owner.to_owned()), prepared))
+        })??;
+
+        self.trust_changed(key, &previous_owner, false, None);
+        self.trust_changed(key, new_owner, true, None);
+        Some((previous_owner, prepared))
+    }
+
+    /// Folds claim `from` into claim `into` for staff, such as after two
+    /// towns merge. Returns the absorbed claim as it was.
+    fn merge_claims(
+        &self,
+        into: ClaimId,
+        from: ClaimId,
+        admin: Option<&str>,
+    ) -> Result<Claim, ReshapeError> {
+        let absorbed = self.claims.merge_claims(into, from)?;
+        let key = self.claims.key_of(into).ok_or(ReshapeError::UnknownClaim)?;
+
+        let action = AuditAction::ClaimsMerged {
+            absorbed: from.0,
+            owner: absorbed.owner.clone(),
+        };
+        self.audit_claim(key, Some(into), admin, None, action);
+        let owner = self.claims.read(key, |claim| claim.owner.clone()).unwrap_or_default();
+        if owner != absorbed.owner {
+            // The absorbed chunks changed hands.
+            for chunk in &absorbed.chunks {
+                let (world, chunk) = (absorbed.world, *chunk);
+                let previous = absorbed.owner.clone();
+                self.listeners.emit(ClaimEvent::Deleted { world, chunk, owner: previous });
+                self.listeners.emit(ClaimEvent::Created { world, chunk, owner: owner.clone() });
+            }
+            self.trust_changed(key, &absorbed.owner, true, admin);
+        }
+        self.webmap.dirty_flag().store(true, std::sync::atomic::Ordering::SeqCst);
+        Ok(absorbed)
+    }
+
+    /// Carves `chunks` out of claim `id` for staff, such as to settle a
+    /// contested area. Returns the new claim's id.
+    fn split_claim(
+        &self,
+        id: ClaimId,
+        chunks: &[ChunkPosition],
+        admin: Option<&str>,
+    ) -> Result<ClaimId, ReshapeError> {
+        let key = self.claims.key_of(id).ok_or(ReshapeError::UnknownClaim)?;
+        let count = self
+            .claims
+            .read(key, |claim| claim.chunks.iter().filter(|chunk| chunks.contains(chunk)).count())
+            .unwrap_or(0);
+        let piece = self.claims.split_claim(id, chunks)?;
+
+        let key = self.claims.key_of(id).unwrap_or(key);
+        let action = AuditAction::ClaimSplit { piece: piece.0, chunks: count };
+        self.audit_claim(key, Some(id), admin, None, action);
+        self.webmap.dirty_flag().store(true, std::sync::atomic::Ordering::SeqCst);
+        Ok(piece)
+    }
+
+    /// Every chunk `owner` claims, counting claims not loaded right now.
+    fn claims_of(&self, owner: &str) -> Vec<ClaimKey> {
+        let mut keys = self.claims.keys_owned_by(owner);
+        if self.residency.is_enabled() {
+            for claim in self.residency.owned_by(owner) {
+                if !self.claims.holds(claim.id) {
+                    keys.extend(claim.keys());
+                }
+            }
+        }
+        keys
+    }
+
+    /// Everyone who owns a claim, counting claims not loaded right now.
+    fn claim_owners(&self) -> Vec<String> {
+        let mut owners = self.claims.owners();
+        if self.residency.is_enabled() {
+            let indexed = self.residency.all().into_iter();
+            owners.extend(
+                indexed.filter(|claim| !self.claims.holds(claim.id)).map(|claim| claim.owner),
+            );
+            owners.sort();
+            owners.dedup();
+        }
+        owners
+    }
+
+    /// Loads every claim of `owner` that is only in storage right now, for
+    /// changes that have to reach all of them.
+    fn load_claims_of(&self, owner: &str) {
+        self.load_indexed_claims(|claim| claim.owner == owner);
+    }
+
+    /// Loads the claims `filter` picks from the index that are only in
+    /// storage right now.
+    fn load_indexed_claims(&self, filter: impl Fn(&IndexedClaim) -> bool) {
+        if self.residency.is_enabled() {
+            let ids: Vec<ClaimId> =
+                self.residency.matching(filter).iter().map(|claim| claim.id).collect();
+            self.load_stored_claims(&ids);
+        }
+    }
+
+    /// Loads the claim covering `key` if it is only in storage, for
+    /// commands aimed at a chunk far from where anyone is.
+    fn load_claim_at(&self, key: ClaimKey) {
+        if let Some(claim) = self.residency.claim_at(key) {
+            self.load_stored_claims(&[claim.id]);
+        }
+    }
+
+    fn purge_claims(&self, owner: &str, actor: Option<&str>) -> usize {
+        self.load_claims_of(owner);
+        let removed = self
+            .claims_of(owner)
+            .into_iter()
+            .filter(|&key| self.remove_claim(key, actor).is_some())
+            .count();
+        self.expiry.forget(owner);
+        removed
+    }
+
+    fn expire_inactive_claims(&self, game: &Game) {
+        let expiration = self.config.get().expiration.clone();
+        let mut owners = self.claim_owners();
+        owners.retain(|owner| owner != claims::ADMIN_OWNER);
+        let inactive =
+            self.expiry.inactive_owners(&owners, expiration.inactivity_limit(), |name| {
+                game.server.player(name).is_some()
+            });
+
+        for owner in inactive {
+            self.pass_to_heirs(&owner);
+            if self.claims_of(&owner).is_empty() {
+                self.expiry.forget(&owner);
+                continue;
+            }
+            match expiration.action {
+                ExpirationAction::Unclaim => {
+                    self.purge_claims(&owner, None);
+                }
+                ExpirationAction::FlagForReview => self.expiry.flag(&owner),
+            }
+        }
+    }
+}
+
+/// How far back `/claim rollback` may reach. Older changes may also have
+/// been pushed out of the in-memory audit log.
+const MAX_ROLLBACK_MINUTES: u64 = 7 * 24 * 60;
+
+struct ClaimCommand {
+    plugin: Arc<LandClaiming>,
+    transfers: PendingTransfers,
+}
+
+impl ClaimCommand {
+    fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self {
+            plugin,
+            transfers: PendingTransfers::default(),
+        }
+    }
+
+    fn claim(&self, player: &Player) {
+        if !self.plugin.require(player, permissions::CLAIM)
+            || !self.plugin.within_rate_limit(player, LimitedAction::Claim)
+        {
+            return;
+        }
+
+        let player_key = ClaimKey::of(player);
+
+        match self.plugin.claim_chunk(player, player_key) {
+            Ok(()) => {
+                self.plugin.tell(player, "claim.created", &[]);
+                self.plugin.show_outline(player, player_key);
+            }
+            Err(ClaimError::Cancelled) => self.plugin.tell(player, "claim.create_failed", &[]),
+            Err(error) => self.plugin.tell_error(player, error),
+        }
+    }
+
+    fn toggle_auto_claim(&self, player: &Player) {
+        if !self.plugin.require(player, permissions::CLAIM) {
+            return;
+        }
+
+        if self.plugin.auto_claim.toggle(player.name()) {
+            let remaining = self.plugin.remaining_claim_blocks(player.name());
+            self.plugin.tell(player, "auto.enabled", &[("remaining", &remaining)]);
+        } else {
+            self.plugin.tell(player, "auto.disabled", &[]);
+        }
+    }
+
+    fn offer_transfer(&self, ctx: &CommandContext, player: &Player, args: &[String]) {
+        let (key, recipient) = match args {
+            [recipient] => (ClaimKey::of(player), recipient),
+            [name, recipient] => match self.plugin.named_claim(player, name) {
+                Some(key) => (key, recipient),
+                None => return,
+            },
+            _ => {
+                return self.plugin.tell(
+                    player,
+                    "general.usage",
+                    &[("usage", &"/claim transfer [claim] <player>")],
+                )
+            }
+        };
+
+        if self.transfer_recipient(ctx, player, key, recipient).is_some() {
+            let action = PendingAction::Transfer { key, recipient: recipient.clone() };
+            self.plugin.ask_confirmation(
+                player,
+                action,
+                "confirm.transfer",
+                &[("player", recipient), ("chunks", &self.plugin.claim_chunks(key).len())],
+            );
+        }
+    }
+
+    /// The online recipient of a transfer of `key`, telling the player why
+    /// there is none.
+    fn transfer_recipient<'a>(
+        &self,
+        ctx: &'a CommandContext,
+        player: &Player,
+        key: ClaimKey,
+        recipient: &str,
+    ) -> Option<&'a Player> {
+        match self.plugin.claims.read(key, |claim| claim.owner == player.name()) {
+            Some(true) => {}
+            Some(false) => {
+                self.plugin.tell(player, "general.not_owner", &[]);
+                return None;
+            }
+            None => {
+                self.plugin.tell(player, "general.not_claimed", &[]);
+                return None;
+            }
+        }
+
+        let recipient_player = ctx.game.server.player(recipient);
+        if recipient_player.is_none() {
+            self.plugin.tell(player, "general.player_offline", &[("player", &recipient)]);
+        }
+        recipient_player
+    }
+
+    fn send_transfer_offer(
+        &self,
+        ctx: &CommandContext,
+        player: &Player,
+        key: ClaimKey,
+        recipient: &str,
+    ) {
+        let recipient_player = match self.transfer_recipient(ctx, player, key, recipient) {
+            Some(recipient_player) => recipient_player,
+            None => return,
+        };
+
+        self.transfers.offer(recipient, key, player.name());
+        self.plugin.tell(player, "transfer.offered", &[("player", &recipient)]);
+        self.plugin.tell(
+            recipient_player,
+            "transfer.incoming",
+            &[("player", &player.name()), ("x", &key.chunk.x), ("z", &key.chunk.z)],
+        );
+    }
+
+    fn confirm(&self, ctx: &CommandContext, player: &Player) {
+        let action = match self.plugin.confirmations.take(player.name()) {
+            Some(action) => action,
+            None => return self.plugin.tell(player, "confirm.none", &[]),
+        };
+
+        match action {
+            PendingAction::Unclaim(key) => match self.plugin.unclaim_chunk(player, key) {
+                Ok(_) => self.plugin.tell(player, "claim.removed", &[]),
+                Err(error) => self.plugin.tell_unclaim_error(player, error),
+            },
+            PendingAction::AbandonAll => self.plugin.abandon_all_claims(player),
+            PendingAction::Transfer { key, recipient } => {
+                self.send_transfer_offer(ctx, player, key, &recipient)
+            }
+            PendingAction::Buy { key, price } => {
+                self.plugin.buy_claim(&ctx.game, player, key, price)
+            }
+            PendingAction::Purge { owner } => self.plugin.admin_purge(player, &owner),
+            PendingAction::RestoreClaims { path } => {
+                if self.plugin.require(player, permissions::ADMIN) {
+                    let result = self.plugin.restore_claims(&ctx.game, &path, Some(player.name()));
+                    self.plugin.tell_restored(player, &path, result);
+                }
+            }
+        }
+    }
+
+    fn resize(&self, player: &Player, args: &[String], grow: bool) {
+        let (node, usage) = if grow {
+            (permissions::CLAIM, "/claim expand <north|east|south|west> <chunks>")
+        } else {
+            (permissions::UNCLAIM, "/claim shrink <north|east|south|west> <chunks>")
+        };
+        if !self.plugin.require(player, node) {
+            return;
+        }
+
+        let mut reader = ArgReader::new(args);
+        let (direction, rows) = match (reader.direction(), reader.count::<u32>()) {
+            (Some(direction), Some(rows)) => (direction, rows),
+            _ => return self.plugin.tell(player, "general.usage", &[("usage", &usage)]),
+        };
+
+        if rows > expansion::MAX_ROWS {
+            return self.plugin.tell(player, "resize.too_far", &[("max", &expansion::MAX_ROWS)]);
+        }
+
+        if grow {
+            self.plugin.expand_claim(player, direction, rows);
+        } else {
+            self.plugin.shrink_claim(player, direction, rows);
+        }
+    }
+
+    fn rollback(&self, player: &Player, args: &[String]) {
+        let minutes = match ArgReader::new(args).count::<u64>() {
+            Some(minutes) => minutes,
+            None => {
+                return self.plugin.tell(
+                    player,
+                    "general.usage",
+                    &[("usage", &"/claim rollback <minutes>")],
+                )
+            }
+        };
+
+        if minutes > MAX_ROLLBACK_MINUTES {
+            return self.plugin.tell(player, "rollback.too_far", &[("max", &MAX_ROLLBACK_MINUTES)]);
+        }
+        self.plugin.rollback(player, minutes);
+    }
+
+    fn rent(&self, ctx: &CommandContext, player: &Player, args: &[String]) {
+        let mut reader = ArgReader::new(args);
+        match reader.word() {
+            Some("set") => {
+                let price = reader.price();
+                let duration = reader.word().and_then(rental::parse_duration);
+
+                match (price, duration) {
+                    (Some(price), Some(duration)) => {
+                        self.plugin.set_rental(player, price, duration)
+                    }
+                    _ => self.plugin.tell(
+                        player,
+                        "general.usage",
+                        &[("usage", &"/claim rent set <price> <30m|12h|7d|2w>")],
+                    ),
+                }
+            }
+            Some("cancel") => self.plugin.cancel_rental(player),
+            Some("accept") => self.plugin.accept_rental(&ctx.game, player),
+            _ => self.plugin.tell(
+                player,
+                "general.usage",
+                &[("usage", &"/claim rent <set <price> <duration>|cancel|accept>")],
+            ),
+        }
+    }
+
+    fn sell(&self, player: &Player, args: &[String]) {
+        let price = ArgReader::new(args).price();
+
+        match (args.first().map(String::as_str), price) {
+            (Some("cancel"), _) => self.plugin.cancel_sale(player),
+            (_, Some(price)) => self.plugin.set_sale(player, price),
+            _ => self.plugin.tell(
+                player,
+                "general.usage",
+                &[("usage", &"/claim sell <price|cancel>")],
+            ),
+        }
+    }
+
+    fn set_home(&self, player: &Player, name: Option<&String>) {
+        if let Some(name) = name {
+            let valid = name.chars().count() <= homes::MAX_NAME_LENGTH
+                && name.parse::<usize>().is_err();
+            if !valid {
+                let max = homes::MAX_NAME_LENGTH;
+                return self.plugin.tell(player, "home.invalid_name", &[("max", &max)]);
+            }
+        }
+        self.plugin.set_home(player, name.cloned());
+    }
+
+    fn home(&self, player: &Player, selector: Option<&String>) {
+        if self.plugin.require(player, permissions::HOME) {
+            self.plugin.go_home(player, selector.map(String::as_str));
+        }
+    }
+
+    fn set_visit(&self, player: &Player, state: Option<&String>) {
+        match state.map(String::as_str) {
+            Some("on") => self.plugin.set_visitable(player, true),
+            Some("off") => self.plugin.set_visitable(player, false),
+            _ => {
+                let usage = "/claim setvisit <on|off>";
+                self.plugin.tell(player, "general.usage", &[("usage", &usage)])
+            }
+        }
+    }
+
+    fn visit(&self, player: &Player, args: &[String]) {
+        if !self.plugin.require(player, permissions::VISIT) {
+            return;
+        }
+        match args {
+            [owner] => self.plugin.visit(player, owner, None),
+            [owner, selector] => self.plugin.visit(player, owner, Some(selector)),
+            _ => self.plugin.tell(
+                player,
+                "general.usage",
+                &[("usage", &"/claim visit <player> [number|name]")],
+            ),
+        }
+    }
+
+    fn set_entry_message(&self, player: &Player, kind: EntryMessage, words: &[String]) {
+        let text = words.join(" ");
+
+        if text.chars().count() > greetings::MAX_MESSAGE_LENGTH {
+            self.plugin.tell(
+                player,
+                "greeting.too_long",
+                &[("max", &greetings::MAX_MESSAGE_LENGTH)],
+            );
+            return;
+        }
+
+        let text = if text.is_empty() { None } else { Some(text) };
+        self.plugin.set_entry_message(player, kind, text);
+    }
+
+    fn accept_transfer(&self, ctx: &CommandContext, player: &Player) {
+        let offer = match self.transfers.take(player.name()) {
+            Some(offer) => offer,
+            None => return self.plugin.tell(player, "transfer.none_pending", &[]),
+        };
+
+        if !self.plugin.claims.is_owned_by(offer.key, &offer.from) {
+            return self.plugin.tell(player, "transfer.unavailable", &[]);
+        }
+
+        let size = self.plugin.claim_chunks(offer.key).len();
+        if self.plugin.claim_slots_left(player).map_or(false, |left| size > left) {
+            return self.plugin.tell_denial(player, ClaimDenial::LimitReached);
+        }
+
+        if let Err(denial) = self.plugin.check_claim_blocks(player, size) {
+            return self.plugin.tell_denial(player, denial);
+        }
+
+        if self.plugin.transfer_claim_from(offer.key, &offer.from, player.name()).is_none() {
+            return self.plugin.tell(player, "transfer.unavailable", &[]);
+        }
+        self.plugin.tell(
+            player,
+            "transfer.accepted",
+            &[("x", &offer.key.chunk.x), ("z", &offer.key.chunk.z)],
+        );
+        if let Some(previous_owner) = ctx.game.server.player(&offer.from) {
+            self.plugin.tell(
+                previous_owner,
+                "transfer.accepted_notice",
+                &[("player", &player.name())],
+            );
+        }
+    }
+}
+
+impl Command for ClaimCommand {
+    fn execute(&self, ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        match args.first().map(String::as_str) {
+            Some("show") => self.plugin.show_claim(player),
+            Some("auto") => self.toggle_auto_claim(player),
+            Some("hud") => self.plugin.toggle_hud(player),
+            Some("fly") => self.plugin.toggle_flight(player),
+            Some("expand") => self.resize(player, &args[1..], true),
+            Some("shrink") => self.resize(player, &args[1..], false),
+            Some("transfer") => self.offer_transfer(ctx, player, &args[1..]),
+            Some("accept") => self.accept_transfer(ctx, player),
+            Some("confirm") => self.confirm(ctx, player),
+            Some("rent") => self.rent(ctx, player, &args[1..]),
+            Some("sell") => self.sell(player, &args[1..]),
+            Some("buy") => self.plugin.offer_purchase(player),
+            Some("rollback") => self.rollback(player, &args[1..]),
+            Some("flag") => self.plugin.flag_command(&ctx.game, player, &args[1..]),
+            Some("name") => self.plugin.name_claim(player, args.get(1).map(String::as_str)),
+            Some("heir") => self.plugin.heir_command(player, &args[1..]),
+            Some("permissions") => self.plugin.edit_interactions(player, &args[1..]),
+            Some("sethome") => self.set_home(player, args.get(1)),
+            Some("home") => self.home(player, args.get(1)),
+            Some("setvisit") => self.set_visit(player, args.get(1)),
+            Some("visit") => self.visit(player, &args[1..]),
+            Some("visits") => {
+                if self.plugin.require(player, permissions::VISIT) {
+                    self.plugin.list_all_visits(player);
+                }
+            }
+            Some("setgreeting") => {
+                self.set_entry_message(player, EntryMessage::Greeting, &args[1..])
+            }
+            Some("setfarewell") => {
+                self.set_entry_message(player, EntryMessage::Farewell, &args[1..])
+            }
+            None => self.claim(player),
+            Some(_) => self.plugin.tell(player, "general.usage", &[("usage", &self.get_usage())]),
+        }
+    }
+
+    fn tab_complete(
+        &self,
+        ctx: &mut CommandContext,
+        sender: &dyn CommandSender,
+        args: Vec<String>,
+    ) -> Vec<String> {
+        const SYNTAX: Syntax = Syntax::Subcommands(&[
+            ("show", &[]),
+            ("auto", &[]),
+            ("hud", &[]),
+            ("fly", &[]),
+            ("expand", &[ArgKind::Direction, ArgKind::Count]),
+            ("shrink", &[ArgKind::Direction, ArgKind::Count]),
+            (
+                "transfer",
+                &[ArgKind::Either(&ArgKind::ClaimName, &ArgKind::Player), ArgKind::Player],
+            ),
+            ("accept", &[]),
+            ("confirm", &[]),
+            ("rent", &[ArgKind::Choice(&["set", "cancel", "accept"])]),
+            ("sell", &[ArgKind::Choice(&["cancel"])]),
+            ("buy", &[]),
+            ("rollback", &[ArgKind::Count]),
+            (
+                "flag",
+                &[
+                    ArgKind::Either(&ArgKind::ClaimName, &ArgKind::Flag),
+                    ArgKind::Either(&ArgKind::Flag, &ArgKind::FlagValue),
+                    ArgKind::FlagValue,
+                ],
+            ),
+            ("name", &[ArgKind::Text]),
+            ("heir", &[ArgKind::Choice(&["list", "add", "remove"]), ArgKind::Player]),
+            (
+                "permissions",
+                &[
+                    ArgKind::TrustLevel,
+                    ArgKind::Interaction,
+                    ArgKind::Choice(&["allow", "deny", "default"]),
+                ],
+            ),
+            ("sethome", &[ArgKind::Text]),
+            ("home", &[ArgKind::Text]),
+            ("setvisit", &[ArgKind::Choice(&["on", "off"])]),
+            ("visit", &[ArgKind::Player, ArgKind::Text]),
+            ("visits", &[]),
+            ("setgreeting", &[]),
+            ("setfarewell", &[]),
+        ]);
+        self.plugin.complete(&ctx.game, sender, &SYNTAX, &args)
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claim")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claim [show|auto|hud|fly|expand <direction> <chunks>|shrink <direction> <chunks>|transfer [claim] <player>|accept|confirm|rent <set|cancel|accept>|sell <price|cancel>|buy|rollback <minutes>|flag [claim] <flag> [on|off|default]|name [name]|heir [list|add <player>|remove <player>]|permissions [<level> <action> <allow|deny|default>]|sethome [name]|home [index|name]|setvisit <on|off>|visit <player> [number|name]|visits|setgreeting [message]|setfarewell [message]]")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Claims the current chunk, or manages the claim you are standing in.")
+    }
+}
+
+struct UnclaimCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl UnclaimCommand {
+    fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for UnclaimCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, _args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        if !self.plugin.require(player, permissions::UNCLAIM)
+            || !self.plugin.within_rate_limit(player, LimitedAction::Unclaim)
+        {
+            return;
+        }
+
+        let player_key = ClaimKey::of(player);
+
+        match self.plugin.check_can_unclaim(player, player_key) {
+            Ok(()) => self.plugin.ask_confirmation(
+                player,
+                PendingAction::Unclaim(player_key),
+                "confirm.unclaim",
+                &[("x", &player_key.chunk.x), ("z", &player_key.chunk.z)],
+            ),
+            Err(error) => self.plugin.tell_unclaim_error(player, error),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        String::from("unclaim")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/unclaim")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Unclaims the current chunk.")
+    }
+}
+
+struct AbandonAllClaimsCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl AbandonAllClaimsCommand {
+    fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for AbandonAllClaimsCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, _args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        if !self.plugin.require(player, permissions::UNCLAIM) {
+            return;
+        }
+
+        let owned = self.plugin.claims_of(player.name()).len();
+        if owned == 0 {
+            self.plugin.tell(player, "abandon.no_claims", &[]);
+            return;
+        }
+
+        self.plugin.ask_confirmation(
+            player,
+            PendingAction::AbandonAll,
+            "confirm.abandon",
+            &[("count", &owned)],
+        );
+    }
+
+    fn get_name(&self) -> String {
+        String::from("abandonallclaims")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/abandonallclaims")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Abandons every chunk you have claimed.")
     }
 }
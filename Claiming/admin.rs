@@ -0,0 +1,868 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use feather_core::world::ChunkPosition;
+use feather_server::{
+    command::{Command, CommandContext, CommandSender},
+    player::Player,
+    world::WorldId,
+};
+
+use crate::args::{ArgKind, ArgReader, Syntax};
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::claims::{self, ClaimId, ClaimKey, ReshapeError};
+use crate::confirm::PendingAction;
+use crate::migration;
+use crate::permissions;
+use crate::snapshot::{self, SnapshotError};
+use crate::LandClaiming;
+
+/// Audit entries shown per page of `/claimadmin log`.
+const LOG_PAGE_SIZE: usize = 8;
+
+/// The furthest `/claimadmin defineadminclaim` reaches from the admin's
+/// chunk.
+const MAX_ADMIN_CLAIM_RADIUS: u32 = 32;
+
+/// The most chunks a selection for `/claimadmin split` may cover.
+const MAX_SELECTION_CHUNKS: usize = 4096;
+
+/// Staff members who currently ignore claim protections.
+#[derive(Default)]
+pub struct AdminBypass {
+    enabled: Mutex<HashSet<String>>,
+}
+
+impl AdminBypass {
+    pub fn is_bypassing(&self, player: &Player) -> bool {
+        self.enabled.lock().unwrap().contains(player.name())
+    }
+
+    /// Flips bypass mode for `player` and returns whether it is now on.
+    pub fn toggle(&self, player: &Player) -> bool {
+        let mut enabled = self.enabled.lock().unwrap();
+
+        if enabled.remove(player.name()) {
+            false
+        } else {
+            enabled.insert(player.name().to_owned());
+            true
+        }
+    }
+}
+
+/// Opposite corner chunks picked with `/claimadmin select`.
+#[derive(Clone, Copy)]
+struct Selection {
+    world: WorldId,
+    first: ChunkPosition,
+    second: Option<ChunkPosition>,
+}
+
+impl Selection {
+    fn chunks(&self) -> Vec<ChunkPosition> {
+        let second = self.second.unwrap_or(self.first);
+        let (min_x, max_x) = (self.first.x.min(second.x), self.first.x.max(second.x));
+        let (min_z, max_z) = (self.first.z.min(second.z), self.first.z.max(second.z));
+
+        (min_x..=max_x)
+            .flat_map(|x| (min_z..=max_z).map(move |z| ChunkPosition::new(x, z)))
+            .collect()
+    }
+
+    fn size(&self) -> usize {
+        let second = self.second.unwrap_or(self.first);
+        let side = |a: i32, b: i32| (i64::from(a) - i64::from(b)).unsigned_abs() as usize + 1;
+        side(self.first.x, second.x).saturating_mul(side(self.first.z, second.z))
+    }
+}
+
+pub struct ClaimAdminCommand {
+    plugin: Arc<LandClaiming>,
+    selections: Mutex<HashMap<String, Selection>>,
+}
+
+impl ClaimAdminCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self {
+            plugin,
+            selections: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn target(
+        &self,
+        ctx: &CommandContext,
+        sender: &dyn CommandSender,
+        location: &[String],
+        usage: &str,
+    ) -> Option<ClaimKey> {
+        self.plugin.command_target(&ctx.game, sender, location, usage)
+    }
+
+    fn delete(&self, ctx: &CommandContext, sender: &dyn CommandSender, location: &[String]) {
+        let key = match self.target(ctx, sender, location, "/claimadmin delete [<world> <x> <z>]") {
+            Some(key) => key,
+            None => return,
+        };
+
+        if !self.plugin.is_claimed_chunk(key) {
+            return self.plugin.tell_sender(sender, "general.not_claimed", &[]);
+        }
+
+        let admin = sender.as_player().map(Player::name);
+        match self.plugin.admin_delete_claim(key, admin) {
+            Some(claim) => {
+                self.plugin.tell_sender(sender, "admin.deleted", &[("owner", &claim.owner)])
+            }
+            None => self.plugin.tell_sender(sender, "admin.delete_cancelled", &[]),
+        }
+    }
+
+    fn define_admin_claim(
+        &self,
+        ctx: &CommandContext,
+        sender: &dyn CommandSender,
+        args: &[String],
+    ) {
+        let radius = match args.first().map(|radius| radius.parse::<u32>()) {
+            None => 0,
+            Some(Ok(radius)) if radius <= MAX_ADMIN_CLAIM_RADIUS => radius,
+            Some(_) => {
+                return self.plugin.tell_sender(
+                    sender,
+                    "admin.admin_claim_radius",
+                    &[("max", &MAX_ADMIN_CLAIM_RADIUS)],
+                )
+            }
+        };
+        let usage = "/claimadmin defineadminclaim [radius] [<world> <x> <z>]";
+        let center = match self.target(ctx, sender, args.get(1..).unwrap_or(&[]), usage) {
+            Some(center) => center,
+            None => return,
+        };
+
+        let (added, taken) = self.plugin.define_admin_claim(center, radius);
+        if added == 0 {
+            return self.plugin.tell_sender(sender, "admin.admin_claim_none", &[]);
+        }
+        self.plugin.tell_sender(
+            sender,
+            "admin.admin_claim_defined",
+            &[("count", &added), ("taken", &taken)],
+        );
+    }
+
+    fn transfer(&self, ctx: &CommandContext, sender: &dyn CommandSender, args: &[String]) {
+        let usage = "/claimadmin transfer <newowner> [<world> <x> <z>]";
+        let new_owner = match args.first() {
+            Some(new_owner) => new_owner,
+            None => return self.usage(sender, usage),
+        };
+        let key = match self.target(ctx, sender, &args[1..], usage) {
+            Some(key) => key,
+            None => return,
+        };
+
+        match self.plugin.transfer_claim(key, new_owner) {
+            Some(previous_owner) => self.plugin.tell_sender(
+                sender,
+                "admin.transferred",
+                &[("from", &previous_owner), ("to", new_owner)],
+            ),
+            None => self.plugin.tell_sender(sender, "general.not_claimed", &[]),
+        }
+    }
+
+    /// Folds one claim into another it touches, for example after two towns
+    /// merge. The first claim keeps its owner, name and settings.
+    fn merge(&self, sender: &dyn CommandSender, args: &[String]) {
+        let mut reader = ArgReader::new(args);
+        let (into, from) = match (reader.claim_id(), reader.claim_id()) {
+            (Some(into), Some(from)) => (into, from),
+            _ => return self.usage(sender, "/claimadmin merge <claimA> <claimB>"),
+        };
+        for &id in &[into, from] {
+            if self.plugin.claims.key_of(id).is_none() {
+                return self.plugin.tell_sender(sender, "admin.unknown_claim", &[("id", &id)]);
+            }
+        }
+
+        let admin = sender.as_player().map(Player::name);
+        match self.plugin.merge_claims(into, from, admin) {
+            Ok(absorbed) => self.plugin.tell_sender(
+                sender,
+                "admin.merged",
+                &[("from", &from), ("owner", &absorbed.owner), ("into", &into)],
+            ),
+            Err(error) => self.tell_reshape_error(sender, error, into, from),
+        }
+    }
+
+    /// Marks the chunk the admin stands in as a corner of the area to
+    /// split. Every other use starts a new selection.
+    fn select(&self, player: &Player) {
+        let key = ClaimKey::of(player);
+        let mut selections = self.selections.lock().unwrap();
+
+        let selection = match selections.get(player.name()) {
+            Some(&selection) if selection.second.is_none() && selection.world == key.world => {
+                Selection {
+                    second: Some(key.chunk),
+                    ..selection
+                }
+            }
+            _ => Selection {
+                world: key.world,
+                first: key.chunk,
+                second: None,
+            },
+        };
+        selections.insert(player.name().to_owned(), selection);
+        drop(selections);
+
+        match selection.second {
+            None => self.plugin.tell(
+                player,
+                "admin.select_first",
+                &[("x", &key.chunk.x), ("z", &key.chunk.z)],
+            ),
+            Some(second) => self.plugin.tell(
+                player,
+                "admin.select_second",
+                &[
+                    ("count", &selection.size()),
+                    ("x1", &selection.first.x),
+                    ("z1", &selection.first.z),
+                    ("x2", &second.x),
+                    ("z2", &second.z),
+                ],
+            ),
+        }
+    }
+
+    /// Carves the selected chunks out of the one claim they cover into a
+    /// claim of their own, to settle a contested area.
+    fn split(&self, sender: &dyn CommandSender, player: &Player) {
+        let selection = self.selections.lock().unwrap().get(player.name()).copied();
+        let selection = match selection {
+            Some(selection) if selection.second.is_some() => selection,
+            _ => return self.plugin.tell(player, "admin.select_none", &[]),
+        };
+        if selection.size() > MAX_SELECTION_CHUNKS {
+            return self.plugin.tell(
+                player,
+                "admin.select_too_large",
+                &[("max", &MAX_SELECTION_CHUNKS)],
+            );
+        }
+
+        let chunks = selection.chunks();
+        let mut ids: Vec<ClaimId> = chunks
+            .iter()
+            .filter_map(|&chunk| self.plugin.claims.id_at(ClaimKey::new(selection.world, chunk)))
+            .collect();
+        ids.sort();
+        ids.dedup();
+        let id = match ids[..] {
+            [] => return self.plugin.tell(player, "admin.split_unclaimed", &[]),
+            [id] => id,
+            _ => {
+                let claims: Vec<String> = ids.iter().map(ClaimId::to_string).collect();
+                return self.plugin.tell(
+                    player,
+                    "admin.split_several",
+                    &[("claims", &claims.join(", "))],
+                );
+            }
+        };
+
+        match self.plugin.split_claim(id, &chunks, Some(player.name())) {
+            Ok(piece) => {
+                self.selections.lock().unwrap().remove(player.name());
+                self.plugin.tell(player, "admin.split", &[("from", &id), ("piece", &piece)]);
+            }
+            Err(error) => self.tell_reshape_error(sender, error, id, id),
+        }
+    }
+
+    fn tell_reshape_error(
+        &self,
+        sender: &dyn CommandSender,
+        error: ReshapeError,
+        first: ClaimId,
+        second: ClaimId,
+    ) {
+        match error {
+            ReshapeError::UnknownClaim => {
+                self.plugin.tell_sender(sender, "admin.unknown_claim", &[("id", &first)])
+            }
+            ReshapeError::SameClaim => self.plugin.tell_sender(sender, "admin.merge_same", &[]),
+            ReshapeError::NotAdjacent => self.plugin.tell_sender(
+                sender,
+                "admin.merge_not_adjacent",
+                &[("into", &first), ("from", &second)],
+            ),
+            ReshapeError::NothingToSplit => {
+                self.plugin.tell_sender(sender, "admin.split_whole", &[("id", &first)])
+            }
+        }
+    }
+
+    /// Who holds the chunk at a location, for scripts as much as for staff.
+    fn info(&self, ctx: &CommandContext, sender: &dyn CommandSender, location: &[String]) {
+        let key = match self.target(ctx, sender, location, "/claimadmin info [<world> <x> <z>]") {
+            Some(key) => key,
+            None => return,
+        };
+        let claim = match self.plugin.claims.get(key) {
+            Some(claim) => claim,
+            None => return self.plugin.tell_sender(sender, "general.not_claimed", &[]),
+        };
+
+        let owner = if claim.is_admin_claim() {
+            self.plugin.text_sender(sender, "claim.admin_owner", &[])
+        } else {
+            claim.owner.clone()
+        };
+        self.plugin.tell_sender(
+            sender,
+            "admin.info",
+            &[
+                ("id", &claim.id),
+                ("name", &claim.name.as_deref().unwrap_or("-")),
+                ("world", &self.plugin.world_name(key.world)),
+                ("owner", &owner),
+                ("chunks", &claim.chunks.len()),
+                ("trusted", &claim.trusted.len()),
+            ],
+        );
+    }
+
+    fn list(&self, ctx: &CommandContext, sender: &dyn CommandSender, owner: Option<&String>) {
+        let owner = match owner {
+            Some(owner) => owner,
+            None => return self.usage(sender, "/claimadmin list <player>"),
+        };
+        let claims = self.plugin.listed_claims(owner);
+
+        if claims.is_empty() {
+            return self.plugin.tell_sender(sender, "admin.no_claims", &[("player", owner)]);
+        }
+
+        self.plugin.tell_sender(
+            sender,
+            "admin.list_header",
+            &[("player", owner), ("count", &claims.len())],
+        );
+        for claim in claims {
+            let world = ctx
+                .game
+                .server
+                .world(claim.world)
+                .map_or_else(|| String::from("?"), |world| world.name().to_owned());
+            let first = claim.first_chunk;
+            self.plugin.tell_sender(
+                sender,
+                "admin.list_entry",
+                &[
+                    ("id", &claim.id),
+                    ("world", &world),
+                    ("chunks", &claim.chunk_count),
+                    ("x", &first.x),
+                    ("z", &first.z),
+                    ("block_x", &(first.x * claims::CHUNK_SIZE)),
+                    ("block_z", &(first.z * claims::CHUNK_SIZE)),
+                ],
+            );
+        }
+    }
+
+    fn show_stats(&self, sender: &dyn CommandSender) {
+        let stats = self.plugin.stats();
+        let hit_rate = stats
+            .cache_hit_rate()
+            .map_or_else(|| String::from("-"), |rate| format!("{:.1}", rate * 100.0));
+        let mean = stats
+            .mean_lookup()
+            .map_or_else(|| String::from("-"), |mean| mean.as_micros().to_string());
+
+        self.plugin.tell_sender(sender, "admin.stats_header", &[]);
+        self.plugin.tell_sender(
+            sender,
+            "admin.stats_claims",
+            &[("claims", &stats.claims), ("chunks", &stats.chunks)],
+        );
+        self.plugin.tell_sender(
+            sender,
+            "admin.stats_changes",
+            &[
+                ("created", &stats.created),
+                ("created_hour", &stats.created_last_hour),
+                ("deleted", &stats.deleted),
+                ("deleted_hour", &stats.deleted_last_hour),
+            ],
+        );
+        self.plugin.tell_sender(
+            sender,
+            "admin.stats_denied",
+            &[("denied", &stats.denied_interactions)],
+        );
+        self.plugin.tell_sender(
+            sender,
+            "admin.stats_lookups",
+            &[("lookups", &stats.lookups()), ("hit_rate", &hit_rate), ("mean", &mean)],
+        );
+        for task in self.plugin.maintenance.reports() {
+            let mean = task
+                .metrics
+                .mean()
+                .map_or_else(|| String::from("-"), |mean| mean.as_millis().to_string());
+            self.plugin.tell_sender(
+                sender,
+                "admin.stats_task",
+                &[
+                    ("task", &task.name),
+                    ("runs", &task.metrics.runs),
+                    ("mean", &mean),
+                    ("longest", &task.metrics.longest.as_millis()),
+                    ("next", &task.next_in.as_secs()),
+                ],
+            );
+        }
+    }
+
+    /// Players have to confirm a purge; the console is trusted to mean it.
+    fn purge(&self, sender: &dyn CommandSender, owner: Option<&String>) {
+        let owner = match owner {
+            Some(owner) => owner,
+            None => return self.usage(sender, "/claimadmin purge <player>"),
+        };
+        let owned = self.plugin.claims_of(owner).len();
+        if owned == 0 {
+            return self.plugin.tell_sender(sender, "admin.no_claims", &[("player", owner)]);
+        }
+
+        match sender.as_player() {
+            Some(player) => self.plugin.ask_confirmation(
+                player,
+                PendingAction::Purge { owner: owner.clone() },
+                "confirm.purge",
+                &[("player", owner), ("count", &owned)],
+            ),
+            None => {
+                let inherited = self.plugin.pass_to_heirs(owner);
+                let removed = self.plugin.purge_claims(owner, None);
+                self.plugin.tell_sender(
+                    sender,
+                    "admin.purged",
+                    &[("count", &removed), ("player", owner), ("inherited", &inherited)],
+                );
+            }
+        }
+    }
+
+    fn list_expired(&self, sender: &dyn CommandSender) {
+        let flagged = self.plugin.expiry.flagged();
+
+        if flagged.is_empty() {
+            return self.plugin.tell_sender(sender, "admin.expired_none", &[]);
+        }
+
+        self.plugin.tell_sender(sender, "admin.expired_header", &[("count", &flagged.len())]);
+        for owner in flagged {
+            let count = self.plugin.claims_of(&owner).len();
+            self.plugin.tell_sender(
+                sender,
+                "admin.expired_entry",
+                &[("player", &owner), ("count", &count)],
+            );
+        }
+        self.plugin.tell_sender(sender, "admin.expired_hint", &[]);
+    }
+
+    /// Writes every claim to a snapshot file in the data directory. Works
+    /// from the console too.
+    fn export(&self, sender: &dyn CommandSender, file: Option<&String>) {
+        let path = match file.map(|file| self.snapshot_path(file)) {
+            Some(Ok(path)) => path,
+            Some(Err(err)) => {
+                return self.plugin.tell_sender(sender, "admin.snapshot_failed", &[("error", &err)])
+            }
+            None => {
+                let usage = "/claimadmin export <file>";
+                return self.plugin.tell_sender(sender, "general.usage", &[("usage", &usage)]);
+            }
+        };
+
+        match self.plugin.export_claims(&path) {
+            Ok(count) => self.plugin.tell_sender(
+                sender,
+                "admin.exported",
+                &[("count", &count), ("file", &path.display())],
+            ),
+            Err(err) => {
+                self.plugin.tell_sender(sender, "admin.snapshot_failed", &[("error", &err)])
+            }
+        }
+    }
+
+    /// Imports a snapshot written by `export`, or another plugin's claims.
+    /// Also runs from the console, since imports are usually done before
+    /// players join.
+    fn import(&self, ctx: &CommandContext, sender: &dyn CommandSender, args: &[String]) {
+        let data_dir = match (args.first(), args.get(1)) {
+            (Some(source), Some(path)) if source == "griefprevention" => PathBuf::from(path),
+            (Some(file), None) => {
+                return match self.snapshot_path(file) {
+                    Ok(path) => self.import_snapshot(ctx, sender, path),
+                    Err(err) => {
+                        self.plugin.tell_sender(sender, "admin.snapshot_failed", &[("error", &err)])
+                    }
+                }
+            }
+            _ => {
+                let usage = "/claimadmin import <file|griefprevention <path>>";
+                return self.plugin.tell_sender(sender, "general.usage", &[("usage", &usage)]);
+            }
+        };
+
+        let read = migration::read_user_cache(Path::new(migration::USER_CACHE_PATH))
+            .and_then(|names| migration::read_griefprevention(&data_dir, &names));
+        let (claims, mut report) = match read {
+            Ok(read) => read,
+            Err(err) => {
+                return self.plugin.tell_sender(sender, "admin.import_failed", &[("error", &err)])
+            }
+        };
+
+        self.plugin.import_claims(&ctx.game, &claims, &mut report);
+        self.plugin.tell_sender(
+            sender,
+            "admin.imported",
+            &[("claims", &report.claims), ("chunks", &report.chunks)],
+        );
+        self.plugin.tell_sender(
+            sender,
+            "admin.import_skipped",
+            &[
+                ("taken", &report.taken_chunks),
+                ("rejected", &report.rejected_chunks),
+                ("worlds", &report.unknown_worlds),
+                ("owners", &report.unknown_owners),
+                ("admin", &report.admin_claims),
+                ("subdivisions", &report.subdivisions),
+                ("unreadable", &report.unreadable_files),
+            ],
+        );
+    }
+
+    /// Where the snapshot `file` lives, in its own folder of the storage
+    /// directory so it can never replace the live claims file.
+    fn snapshot_path(&self, file: &str) -> Result<PathBuf, SnapshotError> {
+        let dir = Path::new(&self.plugin.config.get().storage.directory).join("snapshots");
+        snapshot::resolve(&dir, file)
+    }
+
+    /// Replaces every claim with a snapshot's. Players have to confirm it
+    /// first; the console is trusted to mean it.
+    fn import_snapshot(&self, ctx: &CommandContext, sender: &dyn CommandSender, path: PathBuf) {
+        let snapshot = match snapshot::read(&path) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                return self.plugin.tell_sender(sender, "admin.snapshot_failed", &[("error", &err)])
+            }
+        };
+
+        match sender.as_player() {
+            Some(player) => {
+                let count = snapshot.claims.len();
+                let current = self.plugin.all_listed_claims().len();
+                let file = path.display().to_string();
+                self.plugin.ask_confirmation(
+                    player,
+                    PendingAction::RestoreClaims { path },
+                    "confirm.restore",
+                    &[("current", &current), ("count", &count), ("file", &file)],
+                );
+            }
+            None => {
+                let result = self.plugin.restore_claims(&ctx.game, &path, None);
+                self.plugin.tell_restored(sender, &path, result);
+            }
+        }
+    }
+
+    /// Recent audit entries about a player, or about the claim at a
+    /// location: `here` for where the admin stands, or `<world> <x> <z>`.
+    fn log(&self, ctx: &CommandContext, sender: &dyn CommandSender, args: &[String]) {
+        let usage = "/claimadmin log <player|here|<world> <x> <z>> [page]";
+        let (location, page) = match args {
+            [] => return self.usage(sender, usage),
+            [here, page @ ..] if here == "here" => (Some(&[][..]), page),
+            [_, x, z, page @ ..] if x.parse::<i32>().is_ok() && z.parse::<i32>().is_ok() => {
+                (Some(&args[..3]), page)
+            }
+            [_, page @ ..] => (None, page),
+        };
+        let page = page.first().and_then(|page| page.parse::<usize>().ok()).unwrap_or(1).max(1);
+
+        let (target, entries) = match location {
+            Some(location) => {
+                let key = match self.target(ctx, sender, location, usage) {
+                    Some(key) => key,
+                    None => return,
+                };
+                let world = self.plugin.world_name(key.world);
+                let claim = self.plugin.claims.id_at(key).map(|id| id.0);
+                let chunk = (key.chunk.x, key.chunk.z);
+
+                let entries = self.plugin.audit_log.query(|entry| {
+                    entry.world == world
+                        && (entry.chunk == chunk || (claim.is_some() && entry.claim == claim))
+                });
+                (format!("{} {}, {}", world, chunk.0, chunk.1), entries)
+            }
+            None => {
+                let target = &args[0];
+                (target.clone(), self.plugin.audit_log.query(|entry| entry.involves(target)))
+            }
+        };
+
+        if entries.is_empty() {
+            return self.plugin.tell_sender(sender, "admin.log_empty", &[]);
+        }
+
+        let pages = (entries.len() + LOG_PAGE_SIZE - 1) / LOG_PAGE_SIZE;
+        let page = page.min(pages);
+        self.plugin.tell_sender(
+            sender,
+            "admin.log_header",
+            &[("target", &target), ("page", &page), ("pages", &pages)],
+        );
+        for entry in entries.iter().skip((page - 1) * LOG_PAGE_SIZE).take(LOG_PAGE_SIZE) {
+            self.plugin.tell_sender(
+                sender,
+                "admin.log_entry",
+                &[
+                    ("age", &audit::format_age(entry.time)),
+                    ("action", &self.describe(sender, entry)),
+                    ("world", &entry.world),
+                    ("x", &entry.chunk.0),
+                    ("z", &entry.chunk.1),
+                ],
+            );
+        }
+    }
+
+    fn describe(&self, sender: &dyn CommandSender, entry: &AuditEntry) -> String {
+        let actor = entry.actor.as_deref().unwrap_or("-");
+
+        match &entry.action {
+            AuditAction::ClaimCreated { owner } => {
+                self.plugin.text_sender(sender, "audit.created", &[("owner", owner)])
+            }
+            AuditAction::ClaimDeleted { owner } => self.plugin.text_sender(
+                sender,
+                "audit.deleted",
+                &[("owner", owner), ("actor", &actor)],
+            ),
+            AuditAction::TrustChanged { player: trusted_player, trusted: true } => {
+                self.plugin.text_sender(
+                    sender,
+                    "audit.trusted",
+                    &[("player", trusted_player), ("actor", &actor)],
+                )
+            }
+            AuditAction::TrustChanged { player: trusted_player, trusted: false } => {
+                self.plugin.text_sender(
+                    sender,
+                    "audit.untrusted",
+                    &[("player", trusted_player), ("actor", &actor)],
+                )
+            }
+            AuditAction::BlockChanged { .. } => {
+                let (x, y, z) = entry.position.unwrap_or_default();
+                self.plugin.text_sender(
+                    sender,
+                    "audit.block_changed",
+                    &[("actor", &actor), ("x", &x), ("y", &y), ("z", &z)],
+                )
+            }
+            AuditAction::RolledBack { minutes, blocks } => self.plugin.text_sender(
+                sender,
+                "audit.rolled_back",
+                &[("actor", &actor), ("minutes", minutes), ("count", blocks)],
+            ),
+            AuditAction::FlagChanged { flag, value: Some(value) } => {
+                let value = if *value { "on" } else { "off" };
+                self.plugin.text_sender(
+                    sender,
+                    "audit.flag_set",
+                    &[("actor", &actor), ("flag", flag), ("value", &value)],
+                )
+            }
+            AuditAction::FlagChanged { flag, value: None } => self.plugin.text_sender(
+                sender,
+                "audit.flag_cleared",
+                &[("actor", &actor), ("flag", flag)],
+            ),
+            AuditAction::ClaimSold { seller, price } => {
+                let price = self.plugin.economy.with(|economy| economy.format(*price));
+                self.plugin.text_sender(
+                    sender,
+                    "audit.sold",
+                    &[("actor", &actor), ("seller", seller), ("price", &price)],
+                )
+            }
+            AuditAction::ClaimsMerged { absorbed, owner } => self.plugin.text_sender(
+                sender,
+                "audit.merged",
+                &[("actor", &actor), ("absorbed", &ClaimId(*absorbed)), ("owner", owner)],
+            ),
+            AuditAction::ClaimSplit { piece, chunks } => self.plugin.text_sender(
+                sender,
+                "audit.split",
+                &[("actor", &actor), ("count", chunks), ("piece", &ClaimId(*piece))],
+            ),
+            AuditAction::InteractDenied { owner } => {
+                let (x, y, z) = entry.position.unwrap_or_default();
+                self.plugin.text_sender(
+                    sender,
+                    "audit.interact_denied",
+                    &[("actor", &actor), ("owner", owner), ("x", &x), ("y", &y), ("z", &z)],
+                )
+            }
+        }
+    }
+
+    fn usage(&self, sender: &dyn CommandSender, usage: &str) {
+        self.plugin.tell_sender(sender, "general.usage", &[("usage", &usage)]);
+    }
+
+    fn toggle_bypass(&self, player: &Player) {
+        if !self.plugin.require(player, permissions::ADMIN_BYPASS) {
+            return;
+        }
+
+        let message = if self.plugin.admin_bypass.toggle(player) {
+            "admin.bypass_on"
+        } else {
+            "admin.bypass_off"
+        };
+        self.plugin.tell(player, message, &[]);
+    }
+}
+
+impl Command for ClaimAdminCommand {
+    fn execute(&self, ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        if let Some(player) = sender.as_player() {
+            if !self.plugin.require(player, permissions::ADMIN) {
+                return;
+            }
+        }
+
+        match args.first().map(String::as_str) {
+            Some("delete") => self.delete(ctx, sender, &args[1..]),
+            Some("defineadminclaim") => self.define_admin_claim(ctx, sender, &args[1..]),
+            Some("transfer") => self.transfer(ctx, sender, &args[1..]),
+            Some("merge") => self.merge(sender, &args[1..]),
+            Some("select") => match sender.as_player() {
+                Some(player) => self.select(player),
+                None => self.plugin.tell_sender(sender, "general.players_only", &[]),
+            },
+            Some("split") => match sender.as_player() {
+                Some(player) => self.split(sender, player),
+                None => self.plugin.tell_sender(sender, "general.players_only", &[]),
+            },
+            Some("info") => self.info(ctx, sender, &args[1..]),
+            Some("list") => self.list(ctx, sender, args.get(1)),
+            Some("purge") => self.purge(sender, args.get(1)),
+            Some("expired") => self.list_expired(sender),
+            Some("stats") => self.show_stats(sender),
+            Some("log") => self.log(ctx, sender, &args[1..]),
+            Some("import") => self.import(ctx, sender, &args[1..]),
+            Some("export") => self.export(sender, args.get(1)),
+            Some("bypass") => match sender.as_player() {
+                Some(player) => self.toggle_bypass(player),
+                None => self.plugin.tell_sender(sender, "general.players_only", &[]),
+            },
+            _ => self.usage(sender, &self.get_usage()),
+        }
+    }
+
+    fn tab_complete(
+        &self,
+        ctx: &mut CommandContext,
+        sender: &dyn CommandSender,
+        args: Vec<String>,
+    ) -> Vec<String> {
+        const SYNTAX: Syntax = Syntax::Subcommands(&[
+            ("delete", &[]),
+            ("defineadminclaim", &[ArgKind::Count]),
+            ("transfer", &[ArgKind::Player]),
+            ("merge", &[ArgKind::Text, ArgKind::Text]),
+            ("select", &[]),
+            ("split", &[]),
+            ("info", &[]),
+            ("list", &[ArgKind::Player]),
+            ("purge", &[ArgKind::Player]),
+            ("expired", &[]),
+            ("stats", &[]),
+            ("bypass", &[]),
+            ("log", &[ArgKind::Either(&ArgKind::Player, &ArgKind::Choice(&["here"]))]),
+            ("import", &[ArgKind::Choice(&["griefprevention"])]),
+            ("export", &[ArgKind::Text]),
+        ]);
+        self.plugin.complete(&ctx.game, sender, &SYNTAX, &args)
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claimadmin")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claimadmin <delete [<world> <x> <z>]|defineadminclaim [radius] [<world> <x> <z>]|transfer <newowner> [<world> <x> <z>]|merge <claimA> <claimB>|select|split|info [<world> <x> <z>]|list <player>|purge <player>|expired|stats|bypass|log <player|here|<world> <x> <z>> [page]|export <file>|import <file|griefprevention <path>>>")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Manages any player's claims.")
+    }
+}
+
+pub struct ClaimReloadCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl ClaimReloadCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for ClaimReloadCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, _args: Vec<String>) {
+        if let Some(player) = sender.as_player() {
+            if !self.plugin.require(player, permissions::RELOAD) {
+                return;
+            }
+        }
+
+        match self.plugin.config.load().and_then(|()| self.plugin.messages.load()) {
+            Ok(()) => self.plugin.tell_sender(sender, "admin.reloaded", &[]),
+            Err(err) => self.plugin.tell_sender(sender, "admin.reload_failed", &[("error", &err)]),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claimreload")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claimreload")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Reloads the LandClaiming configuration and message files.")
+    }
+}
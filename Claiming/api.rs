@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+use feather_core::world::ChunkPosition;
+use feather_core::Position;
+use feather_server::world::WorldId;
+
+use crate::claims::{ClaimId, ClaimKey};
+use crate::economy::EconomyProvider;
+use crate::stats::StatsSnapshot;
+use crate::trust::TrustLevel;
+use crate::LandClaiming;
+
+/// A read-only copy of a claim handed out to other plugins. `chunk` is the
+/// chunk that was asked about; `chunks` is every chunk of the claim.
+#[derive(Clone, Debug)]
+pub struct ClaimInfo {
+    pub id: ClaimId,
+    pub world: WorldId,
+    pub chunk: ChunkPosition,
+    pub chunks: Vec<ChunkPosition>,
+    pub owner: String,
+    pub name: Option<String>,
+    /// Trusted players, with `*` for public trust and `@<group>` for trust
+    /// given to a group.
+    pub members: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ClaimEvent {
+    Created {
+        world: WorldId,
+        chunk: ChunkPosition,
+        owner: String,
+    },
+    Deleted {
+        world: WorldId,
+        chunk: ChunkPosition,
+        owner: String,
+    },
+    TrustChanged {
+        world: WorldId,
+        chunk: ChunkPosition,
+        player: String,
+        trusted: bool,
+    },
+}
+
+/// Somewhere claim changes are reported to.
+pub trait EventSink {
+    fn emit(&self, event: ClaimEvent);
+}
+
+type Listener = Box<dyn Fn(&ClaimEvent) + Send + Sync>;
+
+#[derive(Default)]
+pub struct ClaimListeners {
+    listeners: Mutex<Vec<Listener>>,
+}
+
+impl ClaimListeners {
+    pub fn subscribe(&self, listener: impl Fn(&ClaimEvent) + Send + Sync + 'static) {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
+}
+
+impl EventSink for ClaimListeners {
+    fn emit(&self, event: ClaimEvent) {
+        for listener in self.listeners.lock().unwrap().iter() {
+            listener(&event);
+        }
+    }
+}
+
+/// The supported way for other plugins to work with claims. Registered in
+/// the plugin registry on enable; fetch it with
+/// `game.server.plugin_registry().get::<LandClaimingApi>()`.
+#[derive(Clone)]
+pub struct LandClaimingApi {
+    plugin: Arc<LandClaiming>,
+}
+
+impl LandClaimingApi {
+    pub(crate) fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+
+    pub fn get_claim_at(&self, world: WorldId, position: Position) -> Option<ClaimInfo> {
+        let key = ClaimKey::at(world, position);
+        self.plugin.claims.read(key, |claim| claim.info(key))
+    }
+
+    /// Whether `player` may build at `position`. Unclaimed land is open to
+    /// everyone.
+    pub fn is_trusted(&self, player: &str, world: WorldId, position: Position) -> bool {
+        self.plugin
+            .claims
+            .read(ClaimKey::at(world, position), |claim| {
+                self.plugin.trust_level(claim, player) >= Some(TrustLevel::Build)
+            })
+            .unwrap_or(true)
+    }
+
+    /// Claims `chunk` for `owner`, returning false if it is already taken or
+    /// breaks the server's claim rules, such as the buffer between owners
+    /// or a banned region. The chunk joins any of the owner's claims it
+    /// touches. Claim limits are not applied; callers are expected to do
+    /// their own checks.
+    pub fn create_claim(&self, owner: &str, world: WorldId, chunk: ChunkPosition) -> bool {
+        self.plugin.validate_claim(owner, world, &[chunk]).is_ok()
+            && self.plugin.insert_claim(owner, ClaimKey::new(world, chunk))
+    }
+
+    /// One entry per claim, not per chunk.
+    pub fn claims_of(&self, owner: &str) -> Vec<ClaimInfo> {
+        self.plugin.load_claims_of(owner);
+        self.plugin
+            .claims
+            .owned_by(owner)
+            .iter()
+            .filter_map(|claim| claim.keys().next().map(|key| claim.info(key)))
+            .collect()
+    }
+
+    /// Counters for dashboards and metrics exporters. `to_prometheus` on the
+    /// snapshot renders them for a Prometheus scrape.
+    pub fn stats(&self) -> StatsSnapshot {
+        self.plugin.stats()
+    }
+
+    /// Routes `/buyclaimblocks` and `/sellclaimblocks` through `provider`.
+    pub fn set_economy_provider(&self, provider: impl EconomyProvider + 'static) {
+        self.plugin.economy.set_provider(provider);
+    }
+
+    pub fn subscribe(&self, listener: impl Fn(&ClaimEvent) + Send + Sync + 'static) {
+        self.plugin.listeners.subscribe(listener);
+    }
+}
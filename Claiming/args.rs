@@ -0,0 +1,235 @@
+use std::str::FromStr;
+
+use crate::claims::ClaimId;
+use crate::expansion::Direction;
+use crate::flags::ClaimFlag;
+use crate::interactions::Interaction;
+use crate::trust::TrustLevel;
+
+/// A kind of command argument, for reading it and for suggesting values
+/// while it is being typed.
+#[derive(Clone, Copy, Debug)]
+pub enum ArgKind {
+    /// Online players are suggested.
+    Player,
+    /// A player, `public` or `@<group>`.
+    TrustSubject,
+    /// One of the sender's claim names.
+    ClaimName,
+    Group,
+    Flag,
+    /// `on`, `off` or `default`.
+    FlagValue,
+    TrustLevel,
+    Interaction,
+    Direction,
+    /// A whole number above zero. Nothing is suggested.
+    Count,
+    /// One of a fixed set of words.
+    Choice(&'static [&'static str]),
+    /// Anything at all. Nothing is suggested.
+    Text,
+    /// Where either of two arguments may be given, such as an optional
+    /// claim name before a player.
+    Either(&'static ArgKind, &'static ArgKind),
+}
+
+impl ArgKind {
+    /// What can be suggested without looking at the server. Players, claim
+    /// names and groups are left to the caller.
+    pub fn fixed_suggestions(self) -> Vec<String> {
+        let names: Vec<&str> = match self {
+            ArgKind::Flag => ClaimFlag::ALL.iter().map(|flag| flag.name()).collect(),
+            ArgKind::FlagValue => vec!["on", "off", "default"],
+            ArgKind::TrustLevel => TrustLevel::ALL.iter().map(|level| level.name()).collect(),
+            ArgKind::Interaction => Interaction::ALL.iter().map(|action| action.name()).collect(),
+            ArgKind::Direction => Direction::ALL.iter().map(|direction| direction.name()).collect(),
+            ArgKind::Choice(choices) => choices.to_vec(),
+            ArgKind::Either(first, second) => {
+                let mut names = first.fixed_suggestions();
+                names.extend(second.fixed_suggestions());
+                return names;
+            }
+            ArgKind::Player
+            | ArgKind::TrustSubject
+            | ArgKind::ClaimName
+            | ArgKind::Group
+            | ArgKind::Count
+            | ArgKind::Text => Vec::new(),
+        };
+        names.into_iter().map(str::to_owned).collect()
+    }
+}
+
+/// What a command takes after its name.
+pub enum Syntax {
+    Args(&'static [ArgKind]),
+    /// A sub-command word, then the arguments of that sub-command.
+    Subcommands(&'static [(&'static str, &'static [ArgKind])]),
+}
+
+impl Syntax {
+    /// Suggestions for the last of `args`, the one being typed. `suggest`
+    /// lists the values of an argument; only those starting with what has
+    /// been typed so far are kept.
+    pub fn complete(
+        &self,
+        args: &[String],
+        suggest: impl Fn(ArgKind) -> Vec<String>,
+    ) -> Vec<String> {
+        let typed = args.last().map_or("", String::as_str);
+        let position = args.len().saturating_sub(1);
+
+        let candidates = match self {
+            Syntax::Args(list) => list.get(position).map_or_else(Vec::new, |&arg| suggest(arg)),
+            Syntax::Subcommands(subcommands) if position == 0 => {
+                subcommands.iter().map(|(name, _)| (*name).to_owned()).collect()
+            }
+            Syntax::Subcommands(subcommands) => subcommands
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&args[0]))
+                .and_then(|(_, list)| list.get(position - 1))
+                .map_or_else(Vec::new, |&arg| suggest(arg)),
+        };
+        matching(candidates, typed)
+    }
+}
+
+/// `candidates` starting with `typed`, ignoring case, sorted.
+pub fn matching(candidates: Vec<String>, typed: &str) -> Vec<String> {
+    let typed = typed.to_lowercase();
+    let mut matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&typed))
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches
+}
+
+/// Splits a trailing `<world> <x> <z>` off `args`, for commands the console
+/// can run on a claim it names by location. One is only split off when at
+/// least `before` arguments come ahead of it and the last two are whole
+/// numbers.
+pub fn split_location(args: &[String], before: usize) -> (&[String], &[String]) {
+    let at = match args.len().checked_sub(3) {
+        Some(at) if at >= before => at,
+        _ => return (args, &[]),
+    };
+    let numbers = args[at + 1..].iter().all(|arg| arg.parse::<i32>().is_ok());
+    if numbers {
+        args.split_at(at)
+    } else {
+        (args, &[])
+    }
+}
+
+/// Reads typed arguments off the front of a command's arguments. Each read
+/// moves on by one argument, whether or not it could be read.
+pub struct ArgReader<'a> {
+    args: &'a [String],
+}
+
+impl<'a> ArgReader<'a> {
+    pub fn new(args: &'a [String]) -> Self {
+        Self { args }
+    }
+
+    pub fn word(&mut self) -> Option<&'a str> {
+        let (first, rest) = self.args.split_first()?;
+        self.args = rest;
+        Some(first)
+    }
+
+    /// A number above zero.
+    pub fn count<T: FromStr + PartialOrd + Default>(&mut self) -> Option<T> {
+        self.word()
+            .and_then(|word| word.parse::<T>().ok())
+            .filter(|count| *count > T::default())
+    }
+
+    /// An amount of money above zero. Words like `inf` parse as numbers
+    /// too, but are no price.
+    pub fn price(&mut self) -> Option<f64> {
+        self.count::<f64>().filter(|price| price.is_finite())
+    }
+
+    pub fn flag(&mut self) -> Option<ClaimFlag> {
+        self.word().and_then(ClaimFlag::parse)
+    }
+
+    pub fn trust_level(&mut self) -> Option<TrustLevel> {
+        self.word().and_then(TrustLevel::parse)
+    }
+
+    pub fn direction(&mut self) -> Option<Direction> {
+        self.word().and_then(Direction::parse)
+    }
+
+    pub fn claim_id(&mut self) -> Option<ClaimId> {
+        self.word().and_then(ClaimId::parse)
+    }
+
+    /// Whatever has not been read yet.
+    pub fn rest(&self) -> &'a [String] {
+        self.args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| (*word).to_owned()).collect()
+    }
+
+    #[test]
+    fn completes_the_argument_being_typed() {
+        let syntax = Syntax::Args(&[ArgKind::Flag, ArgKind::FlagValue]);
+        let none = |arg: ArgKind| arg.fixed_suggestions();
+
+        assert_eq!(syntax.complete(&args(&["mob"]), none), ["mob_griefing", "mob_spawning"]);
+        assert_eq!(syntax.complete(&args(&["pvp", "o"]), none), ["off", "on"]);
+        assert!(syntax.complete(&args(&["pvp", "on", ""]), none).is_empty());
+    }
+
+    #[test]
+    fn completes_sub_commands_then_their_arguments() {
+        let syntax = Syntax::Subcommands(&[("expand", &[ArgKind::Direction]), ("show", &[])]);
+        let none = |arg: ArgKind| arg.fixed_suggestions();
+
+        assert_eq!(syntax.complete(&args(&[""]), none), ["expand", "show"]);
+        assert_eq!(syntax.complete(&args(&["EXPAND", "s"]), none), ["south"]);
+        assert!(syntax.complete(&args(&["unknown", ""]), none).is_empty());
+    }
+
+    #[test]
+    fn reads_typed_arguments_in_order() {
+        let words = args(&["north", "0", "build", "x"]);
+        let mut reader = ArgReader::new(&words);
+
+        assert_eq!(reader.direction(), Some(Direction::North));
+        assert_eq!(reader.count::<u32>(), None);
+        assert_eq!(reader.trust_level(), Some(TrustLevel::Build));
+        assert_eq!(reader.rest(), ["x"]);
+
+        let prices = args(&["12.5", "inf", "NaN"]);
+        let mut reader = ArgReader::new(&prices);
+        assert_eq!(reader.price(), Some(12.5));
+        assert_eq!(reader.price(), None);
+        assert_eq!(reader.price(), None);
+    }
+
+    #[test]
+    fn splits_off_a_trailing_location() {
+        let words = args(&["alice", "build", "world", "-40", "120"]);
+        let (rest, location) = split_location(&words, 1);
+        assert_eq!((rest, location), (&words[..2], &words[2..]));
+
+        let (rest, location) = split_location(&words[2..], 1);
+        assert_eq!((rest.len(), location.len()), (3, 0));
+        let words = args(&["alice", "build", "world"]);
+        assert!(split_location(&words, 1).1.is_empty());
+    }
+}
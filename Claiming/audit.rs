@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::ClaimSaver;
+use crate::storage::Storage;
+
+/// How many entries are kept in memory for `/claimadmin log`.
+const RECENT_CAPACITY: usize = 10_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditAction {
+    ClaimCreated { owner: String },
+    ClaimDeleted { owner: String },
+    TrustChanged { player: String, trusted: bool },
+    InteractDenied { owner: String },
+    /// A block changed by a player without build trust. Blocks are stored
+    /// as vanilla state ids.
+    BlockChanged { before: u16, after: u16 },
+    RolledBack { minutes: u64, blocks: usize },
+    /// `value` is `None` when the flag went back to the server default.
+    FlagChanged { flag: String, value: Option<bool> },
+    /// The claim changed hands through `/claim buy`. The buyer is the actor.
+    ClaimSold { seller: String, price: f64 },
+    /// Staff folded the claim `absorbed`, held by `owner`, into this one.
+    ClaimsMerged { absorbed: u64, owner: String },
+    /// Staff carved `chunks` chunks out of this claim into `piece`.
+    ClaimSplit { piece: u64, chunks: usize },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    /// Who did it, when a player did.
+    pub actor: Option<String>,
+    pub claim: Option<u64>,
+    pub world: String,
+    pub chunk: (i32, i32),
+    /// The exact block, for actions that happened at one.
+    pub position: Option<(i32, i32, i32)>,
+    #[serde(flatten)]
+    pub action: AuditAction,
+}
+
+impl AuditEntry {
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs())
+    }
+
+    /// Whether `player` did this or it was done to or about them.
+    pub fn involves(&self, player: &str) -> bool {
+        let subject = match &self.action {
+            AuditAction::ClaimCreated { owner }
+            | AuditAction::ClaimDeleted { owner }
+            | AuditAction::InteractDenied { owner } => Some(owner),
+            AuditAction::TrustChanged { player, .. } => Some(player),
+            AuditAction::ClaimSold { seller, .. } => Some(seller),
+            AuditAction::ClaimsMerged { owner, .. } => Some(owner),
+            AuditAction::BlockChanged { .. }
+            | AuditAction::RolledBack { .. }
+            | AuditAction::FlagChanged { .. }
+            | AuditAction::ClaimSplit { .. } => None,
+        };
+        self.actor.as_deref() == Some(player) || subject.map_or(false, |subject| subject == player)
+    }
+}
+
+/// A record of claim activity for settling disputes. Every entry goes to
+/// the storage backend; the most recent ones are also kept in memory to
+/// answer queries.
+#[derive(Default)]
+pub struct ClaimAuditLog {
+    recent: Mutex<VecDeque<AuditEntry>>,
+}
+
+impl ClaimAuditLog {
+    pub fn load(&self, storage: &Storage) -> std::io::Result<()> {
+        let entries = storage.driver().load_audit(RECENT_CAPACITY)?;
+        *self.recent.lock().unwrap() = entries.into();
+        Ok(())
+    }
+
+    /// Keeps `entry` for queries and hands it to `saver` to be written.
+    pub fn record(&self, saver: &ClaimSaver, entry: AuditEntry) {
+        saver.append_audit(entry.clone());
+
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+
+    /// Matching entries, newest first.
+    pub fn query(&self, matches: impl Fn(&AuditEntry) -> bool) -> Vec<AuditEntry> {
+        self.recent
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|entry| matches(entry))
+            .cloned()
+            .collect()
+    }
+}
+
+/// A rough age such as `5m` or `3d`.
+pub fn format_age(time: u64) -> String {
+    let secs = AuditEntry::now().saturating_sub(time);
+
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86_399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
@@ -0,0 +1,30 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Players who claim every unclaimed chunk they walk into.
+#[derive(Default)]
+pub struct AutoClaim {
+    enabled: Mutex<HashSet<String>>,
+}
+
+impl AutoClaim {
+    pub fn is_enabled(&self, player: &str) -> bool {
+        self.enabled.lock().unwrap().contains(player)
+    }
+
+    /// Flips auto-claim mode for `player` and returns whether it is now on.
+    pub fn toggle(&self, player: &str) -> bool {
+        let mut enabled = self.enabled.lock().unwrap();
+
+        if enabled.remove(player) {
+            false
+        } else {
+            enabled.insert(player.to_owned());
+            true
+        }
+    }
+
+    pub fn disable(&self, player: &str) {
+        self.enabled.lock().unwrap().remove(player);
+    }
+}
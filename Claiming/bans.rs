@@ -0,0 +1,149 @@
+use std::sync::Arc;
+use feather_core::world::ChunkPosition;
+use feather_core::Position;
+use feather_server::command::{Command, CommandContext, CommandSender};
+
+use crate::args::{ArgKind, Syntax};
+use crate::claims::{ClaimKey, CHUNK_SIZE};
+use crate::LandClaiming;
+
+/// How far outside the border a banned player is placed when ejected.
+const EJECT_MARGIN: f64 = 0.5;
+
+/// The closest point just outside `chunk` to `position`, keeping height.
+pub fn outside_of(chunk: ChunkPosition, position: Position) -> Position {
+    let min_x = f64::from(chunk.x * CHUNK_SIZE);
+    let min_z = f64::from(chunk.z * CHUNK_SIZE);
+    let max_x = min_x + f64::from(CHUNK_SIZE);
+    let max_z = min_z + f64::from(CHUNK_SIZE);
+
+    let to_west = position.x - min_x;
+    let to_east = max_x - position.x;
+    let to_north = position.z - min_z;
+    let to_south = max_z - position.z;
+    let nearest = to_west.min(to_east).min(to_north).min(to_south);
+
+    let mut outside = position;
+    if nearest == to_west {
+        outside.x = min_x - EJECT_MARGIN;
+    } else if nearest == to_east {
+        outside.x = max_x + EJECT_MARGIN;
+    } else if nearest == to_north {
+        outside.z = min_z - EJECT_MARGIN;
+    } else {
+        outside.z = max_z + EJECT_MARGIN;
+    }
+    outside
+}
+
+pub struct ClaimBanCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl ClaimBanCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for ClaimBanCommand {
+    fn execute(&self, ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        let target = match args.first() {
+            Some(target) => target,
+            None => {
+                return self.plugin.tell(
+                    player,
+                    "general.usage",
+                    &[("usage", &self.get_usage())],
+                )
+            }
+        };
+
+        let key = ClaimKey::of(player);
+        if !self.plugin.set_banned(player, target, true) {
+            return;
+        }
+
+        if let Some(target_player) = ctx.game.server.player(target) {
+            if ClaimKey::of(target_player) == key {
+                target_player.teleport(outside_of(key.chunk, target_player.position()));
+                self.plugin.tell(target_player, "ban.notice", &[]);
+            }
+        }
+    }
+
+    fn tab_complete(
+        &self,
+        ctx: &mut CommandContext,
+        sender: &dyn CommandSender,
+        args: Vec<String>,
+    ) -> Vec<String> {
+        const SYNTAX: Syntax = Syntax::Args(&[ArgKind::Player]);
+        self.plugin.complete(&ctx.game, sender, &SYNTAX, &args)
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claimban")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claimban <player>")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Bans a player from entering the claim you are standing in.")
+    }
+}
+
+pub struct ClaimUnbanCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl ClaimUnbanCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for ClaimUnbanCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        match args.first() {
+            Some(target) => {
+                self.plugin.set_banned(player, target, false);
+            }
+            None => self.plugin.tell(player, "general.usage", &[("usage", &self.get_usage())]),
+        }
+    }
+
+    fn tab_complete(
+        &self,
+        ctx: &mut CommandContext,
+        sender: &dyn CommandSender,
+        args: Vec<String>,
+    ) -> Vec<String> {
+        const SYNTAX: Syntax = Syntax::Args(&[ArgKind::Player]);
+        self.plugin.complete(&ctx.game, sender, &SYNTAX, &args)
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claimunban")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claimunban <player>")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Lets a banned player enter the claim you are standing in again.")
+    }
+}
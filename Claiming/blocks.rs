@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Claim blocks each player has gained or spent on top of the configured
+/// starting amount, for example by buying or selling them. The blocks in
+/// use are never stored; they follow from the chunks a player owns.
+#[derive(Default)]
+pub struct ClaimBlocks {
+    adjustments: Mutex<HashMap<String, i64>>,
+    changed: AtomicBool,
+}
+
+impl ClaimBlocks {
+    pub fn adjustment(&self, player: &str) -> i64 {
+        self.adjustments.lock().unwrap().get(player).copied().unwrap_or(0)
+    }
+
+    pub fn adjust(&self, player: &str, amount: i64) {
+        *self.adjustments.lock().unwrap().entry(player.to_owned()).or_insert(0) += amount;
+        self.changed.store(true, Ordering::Relaxed);
+    }
+
+    /// Takes `amount` blocks from `player` if `base` plus their adjustment
+    /// leaves that many, checking and taking under one lock so two sales
+    /// cannot both spend the same blocks. Returns what is left afterwards,
+    /// or what they have when it is not enough.
+    pub fn spend(&self, player: &str, amount: i64, base: i64) -> Result<i64, i64> {
+        let mut adjustments = self.adjustments.lock().unwrap();
+        let remaining = base + adjustments.get(player).copied().unwrap_or(0);
+        if remaining < amount {
+            return Err(remaining);
+        }
+        *adjustments.entry(player.to_owned()).or_insert(0) -= amount;
+        self.changed.store(true, Ordering::Relaxed);
+        Ok(remaining - amount)
+    }
+
+    /// Puts back the adjustments loaded from storage.
+    pub fn restore(&self, adjustments: impl IntoIterator<Item = (String, i64)>) {
+        self.adjustments.lock().unwrap().extend(adjustments);
+    }
+
+    pub fn adjustments(&self) -> HashMap<String, i64> {
+        self.adjustments.lock().unwrap().clone()
+    }
+
+    /// Whether any adjustment changed since the previous call.
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spending_never_goes_below_zero() {
+        let blocks = ClaimBlocks::default();
+        blocks.adjust("alice", 50);
+
+        assert_eq!(blocks.spend("alice", 80, 40), Ok(10));
+        assert_eq!(blocks.spend("alice", 20, 40), Err(10));
+        assert_eq!(blocks.adjustment("alice"), -30);
+    }
+}
@@ -0,0 +1,984 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{RwLock, RwLockWriteGuard};
+use feather_core::world::ChunkPosition;
+use feather_core::Position;
+use feather_server::{util::BlockPosition, world::WorldId};
+
+use crate::api::{ClaimEvent, ClaimInfo, EventSink};
+use crate::expansion;
+use crate::flags::{ClaimFlag, ClaimFlags};
+use crate::homes::ClaimHome;
+use crate::interactions::InteractionMatrix;
+use crate::player::PlayerRef;
+use crate::rental::Rental;
+use crate::upkeep::UpkeepState;
+use crate::trust::TrustLevel;
+
+/// Blocks along each side of a chunk.
+pub const CHUNK_SIZE: i32 = 16;
+
+/// The chunk containing `position`. Coordinates are floored first, so a
+/// player at x = -0.5 is in chunk -1 rather than chunk 0.
+pub fn chunk_of(position: Position) -> ChunkPosition {
+    block_chunk(position.x.floor() as i32, position.z.floor() as i32)
+}
+
+/// The block containing `position`.
+pub fn block_of(position: Position) -> BlockPosition {
+    let (x, y, z) = (position.x.floor(), position.y.floor(), position.z.floor());
+    BlockPosition::new(x as i32, y as i32, z as i32)
+}
+
+/// The chunk containing the block at `x`, `z`.
+pub fn block_chunk(x: i32, z: i32) -> ChunkPosition {
+    ChunkPosition::new(x.div_euclid(CHUNK_SIZE), z.div_euclid(CHUNK_SIZE))
+}
+
+/// Admin claims, such as the area around spawn, belong to no player and
+/// store this as their owner. Player names cannot contain `*`.
+pub const ADMIN_OWNER: &str = "*admin";
+
+/// The longest name an owner may give a claim.
+pub const MAX_NAME_LENGTH: usize = 24;
+
+/// Most heirs one claim may have.
+pub const MAX_HEIRS: usize = 5;
+
+/// Whether `name` could be a player's, rather than standing for everyone,
+/// the admin owner or a group. Player names are up to 16 letters, digits
+/// and underscores.
+pub fn is_player_name(name: &str) -> bool {
+    (1..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Claim names are single words so they can be typed as command arguments,
+/// and never just a number so they cannot be mistaken for a list index.
+pub fn is_valid_name(name: &str) -> bool {
+    (1..=MAX_NAME_LENGTH).contains(&name.chars().count())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        && name.parse::<u64>().is_err()
+}
+
+/// Identifies a claimed chunk. Chunks at the same coordinates in different
+/// worlds are separate claims.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClaimKey {
+    pub world: WorldId,
+    pub chunk: ChunkPosition,
+}
+
+impl ClaimKey {
+    pub fn new(world: WorldId, chunk: ChunkPosition) -> Self {
+        Self { world, chunk }
+    }
+
+    pub fn at(world: WorldId, position: Position) -> Self {
+        Self::new(world, chunk_of(position))
+    }
+
+    pub fn at_block(world: WorldId, block: BlockPosition) -> Self {
+        Self::new(world, block_chunk(block.x, block.z))
+    }
+
+    /// The chunk `player` is standing in.
+    pub fn of(player: &impl PlayerRef) -> Self {
+        Self::at(player.world(), player.position())
+    }
+}
+
+/// Why a player may not claim a chunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClaimDenial {
+    WorldDisabled,
+    AlreadyClaimed,
+    LimitReached,
+    InsufficientBlocks { needed: i64, remaining: i64 },
+    /// Part of the area belongs to someone else.
+    Overlap { owner: String },
+    /// The area comes within `buffer` chunks of another owner's claim.
+    TooClose { owner: String, buffer: u32 },
+    /// The area would close off another owner's claim from the rest of the
+    /// world.
+    Encloses { owner: String },
+    OutsideBorder { radius: u32 },
+    BannedRegion { name: String },
+    /// The Nether chunk's portals lead into another owner's Overworld claim.
+    NetherReserved { owner: String },
+}
+
+/// Why a claim operation failed. Operations only report it; the commands
+/// decide what to tell the player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClaimError {
+    NotClaimed,
+    AlreadyClaimed,
+    /// The player does not own the claim, or may not act for its owner.
+    NotOwner,
+    InsufficientBlocks { needed: i64, remaining: i64 },
+    /// Any other claim rule or limit the chunk would break.
+    Denied(ClaimDenial),
+    /// Another plugin cancelled the change.
+    Cancelled,
+    /// Owners always have full trust in their own claims.
+    IsOwner { subject: String },
+    /// Banned players have to be unbanned before they can be trusted.
+    Banned { subject: String },
+    NotTrusted { subject: String },
+    /// A group trust subject with no group name after the `@`.
+    InvalidGroup,
+    /// Public trust only goes up to build.
+    PublicManage,
+    /// Group trust only goes up to build too, since a group may take in
+    /// anyone, or be one every player holds.
+    GroupManage { group: String },
+}
+
+impl From<ClaimDenial> for ClaimError {
+    fn from(denial: ClaimDenial) -> Self {
+        match denial {
+            ClaimDenial::AlreadyClaimed => ClaimError::AlreadyClaimed,
+            ClaimDenial::InsufficientBlocks { needed, remaining } => {
+                ClaimError::InsufficientBlocks { needed, remaining }
+            }
+            denial => ClaimError::Denied(denial),
+        }
+    }
+}
+
+/// Identifies one logical claim: a connected group of chunks sharing an
+/// owner, member list and settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClaimId(pub u64);
+
+impl ClaimId {
+    /// Reads an id as shown to players, such as `#12`. The `#` may be left
+    /// out.
+    pub fn parse(text: &str) -> Option<Self> {
+        text.strip_prefix('#').unwrap_or(text).parse().ok().map(ClaimId)
+    }
+}
+
+impl fmt::Display for ClaimId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(Clone)]
+pub struct Claim {
+    pub id: ClaimId,
+    pub world: WorldId,
+    pub chunks: Vec<ChunkPosition>,
+    pub owner: String,
+    /// Unique among the owner's claims, ignoring case.
+    pub name: Option<String>,
+    /// Everyone but the owner who has been trusted, and how far.
+    pub trusted: HashMap<String, TrustLevel>,
+    pub greeting: Option<String>,
+    pub farewell: Option<String>,
+    pub banned: Vec<String>,
+    pub rental: Option<Rental>,
+    /// What the owner is asking, while the claim is listed with
+    /// `/claim sell`.
+    pub sale_price: Option<f64>,
+    pub flags: ClaimFlags,
+    pub interactions: InteractionMatrix,
+    pub home: Option<ClaimHome>,
+    /// Where visitors arrive. Set while the claim is open to
+    /// `/claim visit`.
+    pub visit_point: Option<Position>,
+    pub upkeep: Option<UpkeepState>,
+    /// Who takes the claim over, first come first, if the owner is purged
+    /// or stays away past the expiration limit.
+    pub heirs: Vec<String>,
+}
+
+/// Why staff could not merge two claims or split one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReshapeError {
+    /// No such claim is loaded.
+    UnknownClaim,
+    SameClaim,
+    /// The claims are in different worlds or do not touch.
+    NotAdjacent,
+    /// The selection covers none of the claim, or all of it.
+    NothingToSplit,
+}
+
+impl Claim {
+    fn new(id: ClaimId, owner: &str, world: WorldId) -> Self {
+        Self {
+            id,
+            world,
+            chunks: Vec::new(),
+            owner: owner.to_owned(),
+            name: None,
+            trusted: HashMap::new(),
+            greeting: None,
+            farewell: None,
+            banned: Vec::new(),
+            rental: None,
+            sale_price: None,
+            flags: ClaimFlags::new(),
+            interactions: InteractionMatrix::new(),
+            home: None,
+            visit_point: None,
+            upkeep: None,
+            heirs: Vec::new(),
+        }
+    }
+
+    pub fn is_admin_claim(&self) -> bool {
+        self.owner == ADMIN_OWNER
+    }
+
+    pub fn trust_of(&self, player: &str) -> Option<TrustLevel> {
+        if self.owner == player {
+            Some(TrustLevel::Manage)
+        } else {
+            self.trusted.get(player).copied()
+        }
+    }
+
+    /// Like `trust_of`, but also counting trust given to subjects other
+    /// than `player` that `covers` says include them, such as public trust.
+    /// Banned players get nothing from those, and they never give more than
+    /// build trust.
+    pub fn trust_with(&self, player: &str, covers: impl Fn(&str) -> bool) -> Option<TrustLevel> {
+        let shared = if self.is_banned(player) {
+            None
+        } else {
+            self.trusted
+                .iter()
+                .filter(|(subject, _)| covers(subject))
+                .map(|(_, level)| (*level).min(TrustLevel::Build))
+                .max()
+        };
+        self.trust_of(player).max(shared)
+    }
+
+    pub fn has_trust(&self, player: &str, level: TrustLevel) -> bool {
+        self.trust_of(player).map_or(false, |held| held >= level)
+    }
+
+    /// Whether `player` holds any trust at all.
+    pub fn is_member(&self, player: &str) -> bool {
+        self.trust_of(player).is_some()
+    }
+
+    /// The owner followed by every trusted player, sorted by name.
+    pub fn members(&self) -> Vec<String> {
+        let mut trusted: Vec<String> = self.trusted.keys().cloned().collect();
+        trusted.sort();
+        trusted.insert(0, self.owner.clone());
+        trusted
+    }
+
+    pub fn is_banned(&self, player: &str) -> bool {
+        self.banned.iter().any(|banned| banned == player)
+    }
+
+    /// The first heir `is_available` accepts. Heirs banned from the claim
+    /// are passed over.
+    pub fn successor(&self, is_available: impl Fn(&str) -> bool) -> Option<String> {
+        self.heirs
+            .iter()
+            .find(|heir| **heir != self.owner && !self.is_banned(heir) && is_available(heir))
+            .cloned()
+    }
+
+    /// The value set for `flag` in this claim, if any.
+    pub fn flag(&self, flag: ClaimFlag) -> Option<bool> {
+        self.flags.get(&flag).copied()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = ClaimKey> + '_ {
+        self.chunks.iter().map(move |&chunk| ClaimKey::new(self.world, chunk))
+    }
+
+    /// Folds `other` into this claim. Trust and bans are combined, keeping
+    /// the higher level where a player is trusted in both; where both claims
+    /// have a name, greeting, farewell, rental, sale price, home, visit
+    /// point, flag or interaction set, this claim's wins.
+    fn absorb(&mut self, other: Claim) {
+        self.chunks.extend(other.chunks);
+        for (player, level) in other.trusted {
+            let held = self.trusted.entry(player).or_insert(level);
+            *held = (*held).max(level);
+        }
+        for banned in other.banned {
+            if !self.banned.contains(&banned) && !self.is_member(&banned) {
+                self.banned.push(banned);
+            }
+        }
+        self.name = self.name.take().or(other.name);
+        self.greeting = self.greeting.take().or(other.greeting);
+        self.farewell = self.farewell.take().or(other.farewell);
+        self.rental = self.rental.take().or(other.rental);
+        self.sale_price = self.sale_price.take().or(other.sale_price);
+        self.home = self.home.take().or(other.home);
+        self.visit_point = self.visit_point.take().or(other.visit_point);
+        self.upkeep = self.upkeep.take().or(other.upkeep);
+        for heir in other.heirs {
+            if !self.heirs.contains(&heir) && self.heirs.len() < MAX_HEIRS {
+                self.heirs.push(heir);
+            }
+        }
+        for (flag, value) in other.flags {
+            self.flags.entry(flag).or_insert(value);
+        }
+        for (level, row) in other.interactions {
+            let own = self.interactions.entry(level).or_default();
+            for (action, allowed) in row {
+                own.entry(action).or_insert(allowed);
+            }
+        }
+    }
+
+    /// Clears the home and visit point if their chunks are no longer part
+    /// of the claim.
+    fn drop_stray_points(&mut self) {
+        let inside = self.home.as_ref().map_or(true, |home| {
+            self.chunks.contains(&chunk_of(home.position))
+        });
+        if !inside {
+            self.home = None;
+        }
+        let inside = self.visit_point.map_or(true, |point| {
+            self.chunks.contains(&chunk_of(point))
+        });
+        if !inside {
+            self.visit_point = None;
+        }
+    }
+
+    pub fn info(&self, key: ClaimKey) -> ClaimInfo {
+        ClaimInfo {
+            id: self.id,
+            world: key.world,
+            chunk: key.chunk,
+            chunks: self.chunks.clone(),
+            owner: self.owner.clone(),
+            name: self.name.clone(),
+            members: self.members(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Store {
+    claims: HashMap<ClaimId, Claim>,
+    chunks: HashMap<ClaimKey, ClaimId>,
+    next_id: u64,
+    /// Claims changed or removed since storage last caught up.
+    changed: HashSet<ClaimId>,
+    removed: HashSet<ClaimId>,
+}
+
+impl Store {
+    fn mark_changed(&mut self, id: ClaimId) {
+        self.removed.remove(&id);
+        self.changed.insert(id);
+    }
+
+    fn mark_removed(&mut self, id: ClaimId) {
+        self.changed.remove(&id);
+        self.removed.insert(id);
+    }
+
+    fn restored(claims: Vec<Claim>, highest_id: u64) -> Self {
+        let mut store = Store::default();
+        store.next_id = claims.iter().map(|claim| claim.id.0).fold(highest_id, u64::max);
+
+        for mut claim in claims {
+            if store.claims.contains_key(&claim.id) {
+                continue;
+            }
+            let world = claim.world;
+            let total = claim.chunks.len();
+            claim.chunks.retain(|&chunk| !store.chunks.contains_key(&ClaimKey::new(world, chunk)));
+            if claim.chunks.is_empty() {
+                continue;
+            }
+
+            let (id, overlapped) = (claim.id, claim.chunks.len() < total);
+            for key in claim.keys() {
+                store.chunks.insert(key, id);
+            }
+            store.claims.insert(id, claim);
+            if overlapped {
+                store.split_disconnected(id);
+            }
+        }
+        store
+    }
+
+    fn allocate_id(&mut self) -> ClaimId {
+        self.next_id += 1;
+        ClaimId(self.next_id)
+    }
+
+    fn claim_at(&self, key: ClaimKey) -> Option<&Claim> {
+        self.chunks.get(&key).and_then(|id| self.claims.get(id))
+    }
+
+    fn claim_at_mut(&mut self, key: ClaimKey) -> Option<&mut Claim> {
+        let id = *self.chunks.get(&key)?;
+        self.claims.get_mut(&id)
+    }
+
+    /// Moves every chunk of `from` into `into`.
+    fn merge(&mut self, into: ClaimId, from: ClaimId) {
+        let absorbed = match self.claims.remove(&from) {
+            Some(absorbed) => absorbed,
+            None => return,
+        };
+        self.mark_removed(from);
+        self.mark_changed(into);
+
+        for key in absorbed.keys() {
+            self.chunks.insert(key, into);
+        }
+        if let Some(claim) = self.claims.get_mut(&into) {
+            claim.absorb(absorbed);
+        }
+    }
+
+    /// Moves `chunks` of `id` into a new claim with copies of its settings,
+    /// then splits both wherever they no longer hang together.
+    fn carve(&mut self, id: ClaimId, chunks: &[ChunkPosition]) -> Result<ClaimId, ReshapeError> {
+        let claim = self.claims.get(&id).ok_or(ReshapeError::UnknownClaim)?;
+        let (carved, kept): (Vec<ChunkPosition>, Vec<ChunkPosition>) =
+            claim.chunks.iter().copied().partition(|chunk| chunks.contains(chunk));
+        if carved.is_empty() || kept.is_empty() {
+            return Err(ReshapeError::NothingToSplit);
+        }
+
+        let mut piece = claim.clone();
+        let new_id = self.allocate_id();
+        piece.id = new_id;
+        piece.chunks = carved;
+        piece.name = None;
+        piece.sale_price = None;
+        piece.drop_stray_points();
+        if let Some(claim) = self.claims.get_mut(&id) {
+            claim.chunks = kept;
+            claim.sale_price = None;
+            claim.drop_stray_points();
+        }
+
+        for key in piece.keys() {
+            self.chunks.insert(key, new_id);
+        }
+        self.claims.insert(new_id, piece);
+        self.mark_changed(id);
+        self.mark_changed(new_id);
+        self.split_disconnected(id);
+        self.split_disconnected(new_id);
+        Ok(new_id)
+    }
+
+    /// Splits `id` into one claim per connected group of chunks. The group
+    /// listed first keeps the id; the others get copies of its settings.
+    fn split_disconnected(&mut self, id: ClaimId) {
+        let claim = match self.claims.get(&id) {
+            Some(claim) => claim.clone(),
+            None => return,
+        };
+
+        let mut unassigned: HashSet<ChunkPosition> = claim.chunks.iter().copied().collect();
+        let mut groups = Vec::new();
+        for &start in &claim.chunks {
+            if !unassigned.contains(&start) {
+                continue;
+            }
+            let group = expansion::connected(start, |chunk| unassigned.contains(&chunk));
+            for chunk in &group {
+                unassigned.remove(chunk);
+            }
+            groups.push(group);
+        }
+
+        self.mark_changed(id);
+        // A price was asked for the whole claim, so no piece stays for sale.
+        let is_split = groups.len() > 1;
+        let mut groups = groups.into_iter();
+        if let (Some(kept), Some(claim)) = (groups.next(), self.claims.get_mut(&id)) {
+            claim.chunks = kept;
+            claim.drop_stray_points();
+            if is_split {
+                claim.sale_price = None;
+            }
+        }
+        for group in groups {
+            let new_id = self.allocate_id();
+            let mut piece = claim.clone();
+            piece.id = new_id;
+            piece.chunks = group;
+            // Only the piece keeping the id keeps the name, so it stays unique.
+            piece.name = None;
+            piece.sale_price = None;
+            piece.drop_stray_points();
+
+            for key in piece.keys() {
+                self.chunks.insert(key, new_id);
+            }
+            self.claims.insert(new_id, piece);
+            self.mark_changed(new_id);
+        }
+    }
+}
+
+/// The single shared store of claims. Every access goes through the lock,
+/// so event handlers and commands running on different threads always see
+/// and modify the same state.
+///
+/// Each claimed chunk belongs to exactly one claim. Claiming a chunk next to
+/// one of your own claims grows that claim, merging it with any other of
+/// your claims it now touches, so members and settings always cover the
+/// whole connected area.
+///
+/// Closures passed to `read` and `update` run while the lock is held and
+/// must not call back into the manager or fire events.
+#[derive(Default)]
+pub struct ClaimManager {
+    store: RwLock<Store>,
+    /// Bumped whenever any claim may have changed.
+    revision: AtomicU64,
+}
+
+impl ClaimManager {
+    /// Locks the store for a change. The revision moves on while the lock
+    /// is held, so anyone who reads the new revision also sees the change.
+    fn write(&self) -> RwLockWriteGuard<'_, Store> {
+        let store = self.store.write().unwrap();
+        self.revision.fetch_add(1, Ordering::AcqRel);
+        store
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Acquire)
+    }
+
+    pub fn get(&self, key: ClaimKey) -> Option<Claim> {
+        self.store.read().unwrap().claim_at(key).cloned()
+    }
+
+    pub fn id_at(&self, key: ClaimKey) -> Option<ClaimId> {
+        self.store.read().unwrap().chunks.get(&key).copied()
+    }
+
+    /// Runs `f` on the claim covering `key`.
+    pub fn read<R>(&self, key: ClaimKey, f: impl FnOnce(&Claim) -> R) -> Option<R> {
+        self.store.read().unwrap().claim_at(key).map(f)
+    }
+
+    /// Runs `f` on the claim covering `key`. Changes apply to every chunk of
+    /// the claim; `f` must not change its chunks or id.
+    pub fn update<R>(&self, key: ClaimKey, f: impl FnOnce(&mut Claim) -> R) -> Option<R> {
+        let mut store = self.write();
+        let id = *store.chunks.get(&key)?;
+        store.mark_changed(id);
+        store.claims.get_mut(&id).map(f)
+    }
+
+    /// Whether `flag` is on at `key`, asking `default` when the claim has
+    /// not set it. `None` outside claims.
+    pub fn flag_at(
+        &self,
+        key: ClaimKey,
+        flag: ClaimFlag,
+        default: impl FnOnce() -> bool,
+    ) -> Option<bool> {
+        let set = self.read(key, |claim| claim.flag(flag))?;
+        Some(set.unwrap_or_else(default))
+    }
+
+    pub fn contains(&self, key: ClaimKey) -> bool {
+        self.store.read().unwrap().chunks.contains_key(&key)
+    }
+
+    pub fn is_owned_by(&self, key: ClaimKey, owner: &str) -> bool {
+        self.read(key, |claim| claim.owner == owner).unwrap_or(false)
+    }
+
+    /// Claims `key` for `owner` unless the chunk was claimed in the meantime,
+    /// and returns the claim it became part of.
+    pub fn insert_if_vacant(&self, key: ClaimKey, owner: &str) -> Option<ClaimId> {
+        let mut store = self.write();
+
+        if store.chunks.contains_key(&key) {
+            return None;
+        }
+
+        let mut touching: Vec<ClaimId> = expansion::neighbours(key.chunk)
+            .iter()
+            .filter_map(|&chunk| store.claim_at(ClaimKey::new(key.world, chunk)))
+            .filter(|claim| claim.owner == owner)
+            .map(|claim| claim.id)
+            .collect();
+        touching.sort();
+        touching.dedup();
+
+        let id = match touching.first() {
+            Some(&oldest) => {
+                for &other in &touching[1..] {
+                    store.merge(oldest, other);
+                }
+                oldest
+            }
+            None => {
+                let id = store.allocate_id();
+                store.claims.insert(id, Claim::new(id, owner, key.world));
+                id
+            }
+        };
+
+        store.chunks.insert(key, id);
+        if let Some(claim) = store.claims.get_mut(&id) {
+            claim.chunks.push(key.chunk);
+        }
+        store.mark_changed(id);
+        Some(id)
+    }
+
+    /// `insert_if_vacant`, telling `events` when the chunk was claimed.
+    pub fn claim(&self, key: ClaimKey, owner: &str, events: &dyn EventSink) -> Option<ClaimId> {
+        let id = self.insert_if_vacant(key, owner)?;
+        events.emit(ClaimEvent::Created {
+            world: key.world,
+            chunk: key.chunk,
+            owner: owner.to_owned(),
+        });
+        Some(id)
+    }
+
+    /// `remove`, telling `events` when a chunk was unclaimed.
+    pub fn unclaim(&self, key: ClaimKey, events: &dyn EventSink) -> Option<Claim> {
+        let claim = self.remove(key)?;
+        events.emit(ClaimEvent::Deleted {
+            world: key.world,
+            chunk: key.chunk,
+            owner: claim.owner.clone(),
+        });
+        Some(claim)
+    }
+
+    /// Unclaims a single chunk and returns the claim as it was beforehand.
+    /// A claim left with no chunks is gone; one cut in two becomes two
+    /// claims.
+    pub fn remove(&self, key: ClaimKey) -> Option<Claim> {
+        let mut store = self.write();
+
+        let id = store.chunks.remove(&key)?;
+        let claim = store.claims.get_mut(&id)?;
+        let before = claim.clone();
+
+        claim.chunks.retain(|&chunk| chunk != key.chunk);
+        claim.drop_stray_points();
+        if claim.chunks.is_empty() {
+            store.claims.remove(&id);
+            store.mark_removed(id);
+        } else {
+            store.split_disconnected(id);
+        }
+        Some(before)
+    }
+
+    /// A chunk of the claim with id `id`, if it is loaded.
+    pub fn key_of(&self, id: ClaimId) -> Option<ClaimKey> {
+        self.store.read().unwrap().claims.get(&id).and_then(|claim| claim.keys().next())
+    }
+
+    /// Folds the claim `from` into `into`, which has to touch it, and
+    /// returns `from` as it was. When the owners differ, the owner of `from`
+    /// keeps manage trust over the whole claim and its name is dropped,
+    /// since it need not be unique among the new owner's claims.
+    pub fn merge_claims(&self, into: ClaimId, from: ClaimId) -> Result<Claim, ReshapeError> {
+        let mut store = self.write();
+        if into == from {
+            return Err(ReshapeError::SameClaim);
+        }
+        let (target, absorbed) = match (store.claims.get(&into), store.claims.get(&from)) {
+            (Some(target), Some(absorbed)) => (target, absorbed.clone()),
+            _ => return Err(ReshapeError::UnknownClaim),
+        };
+        let touches = target.world == absorbed.world
+            && absorbed.chunks.iter().any(|&chunk| {
+                let neighbours = expansion::neighbours(chunk);
+                neighbours.iter().any(|neighbour| target.chunks.contains(neighbour))
+            });
+        if !touches {
+            return Err(ReshapeError::NotAdjacent);
+        }
+
+        let owner = target.owner.clone();
+        if let Some(claim) = store.claims.get_mut(&from) {
+            // What the old owner arranged for their land does not carry
+            // over to someone else's claim.
+            if claim.owner != owner {
+                claim.trusted.remove(&owner);
+                claim.banned.retain(|banned| *banned != owner);
+                claim.trusted.insert(claim.owner.clone(), TrustLevel::Manage);
+                claim.name = None;
+                claim.heirs.clear();
+                claim.rental = None;
+                claim.sale_price = None;
+            }
+        }
+        store.merge(into, from);
+        if let Some(claim) = store.claims.get_mut(&into) {
+            // The price was asked for one of the parts only.
+            claim.sale_price = None;
+        }
+        Ok(absorbed)
+    }
+
+    /// Carves `chunks` out of the claim `id` into a claim of their own with
+    /// the same owner, members and flags, and returns its id. Chunks that
+    /// are not part of the claim are ignored.
+    pub fn split_claim(
+        &self,
+        id: ClaimId,
+        chunks: &[ChunkPosition],
+    ) -> Result<ClaimId, ReshapeError> {
+        self.write().carve(id, chunks)
+    }
+
+    /// A chunk of the claim `owner` named `name`.
+    pub fn named(&self, owner: &str, name: &str) -> Option<ClaimKey> {
+        self.store
+            .read()
+            .unwrap()
+            .claims
+            .values()
+            .filter(|claim| claim.owner == owner)
+            .find(|claim| claim.name.as_deref().map_or(false, |own| own.eq_ignore_ascii_case(name)))
+            .and_then(|claim| claim.keys().next())
+    }
+
+    pub fn keys_owned_by(&self, owner: &str) -> Vec<ClaimKey> {
+        self.store
+            .read()
+            .unwrap()
+            .claims
+            .values()
+            .filter(|claim| claim.owner == owner)
+            .flat_map(|claim| claim.keys().collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn owned_by(&self, owner: &str) -> Vec<Claim> {
+        let mut owned: Vec<Claim> = self
+            .store
+            .read()
+            .unwrap()
+            .claims
+            .values()
+            .filter(|claim| claim.owner == owner)
+            .cloned()
+            .collect();
+        owned.sort_by_key(|claim| claim.id);
+        owned
+    }
+
+    /// A copy of every claim.
+    pub fn all(&self) -> Vec<Claim> {
+        self.store.read().unwrap().claims.values().cloned().collect()
+    }
+
+    /// Copies of the claims changed since the last call, and the ids of
+    /// those that no longer exist.
+    pub fn take_changes(&self) -> (Vec<Claim>, Vec<ClaimId>) {
+        let mut store = self.store.write().unwrap();
+
+        let changed: Vec<ClaimId> = store.changed.drain().collect();
+        let removed = store.removed.drain().collect();
+        let changed = changed
+            .iter()
+            .filter_map(|id| store.claims.get(id))
+            .cloned()
+            .collect();
+        (changed, removed)
+    }
+
+    /// Replaces every claim with `claims`, as read back from storage. Claims
+    /// overlapping one restored earlier lose the overlapping chunks. New ids
+    /// start above `highest_id`, so ids of stored claims that could not be
+    /// restored are never reused.
+    pub fn restore(&self, claims: Vec<Claim>, highest_id: u64) -> usize {
+        let store = Store::restored(claims, highest_id);
+        let count = store.claims.len();
+        *self.write() = store;
+        count
+    }
+
+    /// Like `restore`, but for claims that did not come from storage:
+    /// every claim is saved again and those that are gone are deleted.
+    /// `events` hears of every chunk that changed hands.
+    pub fn replace_all(&self, claims: Vec<Claim>, events: &dyn EventSink) -> usize {
+        let (count, changes) = self.swap_all(claims);
+        for event in changes {
+            events.emit(event);
+        }
+        count
+    }
+
+    fn swap_all(&self, claims: Vec<Claim>) -> (usize, Vec<ClaimEvent>) {
+        let mut store = self.write();
+
+        let mut replacement = Store::restored(claims, store.next_id);
+        replacement.changed = replacement.claims.keys().copied().collect();
+        replacement.removed = store
+            .claims
+            .keys()
+            .chain(&store.removed)
+            .filter(|id| !replacement.claims.contains_key(id))
+            .copied()
+            .collect();
+
+        let owner_at = |store: &Store, key: &ClaimKey| {
+            store.chunks.get(key).and_then(|id| store.claims.get(id)).map(|claim| &claim.owner)
+        };
+        let mut changes = Vec::new();
+        for key in store.chunks.keys() {
+            let (before, after) = (owner_at(&*store, key), owner_at(&replacement, key));
+            if let Some(owner) = before.filter(|&owner| after != Some(owner)) {
+                let (world, chunk, owner) = (key.world, key.chunk, owner.clone());
+                changes.push(ClaimEvent::Deleted { world, chunk, owner });
+            }
+        }
+        for key in replacement.chunks.keys() {
+            let (before, after) = (owner_at(&*store, key), owner_at(&replacement, key));
+            if let Some(owner) = after.filter(|&owner| before != Some(owner)) {
+                let (world, chunk, owner) = (key.world, key.chunk, owner.clone());
+                changes.push(ClaimEvent::Created { world, chunk, owner });
+            }
+        }
+
+        let count = replacement.claims.len();
+        *store = replacement;
+        (count, changes)
+    }
+
+    /// Adds claims read back from storage for chunks that just loaded,
+    /// without marking them changed. Claims already held are left out, and
+    /// so are claims overlapping one that is; their ids are returned.
+    pub fn admit(&self, claims: Vec<Claim>) -> Vec<ClaimId> {
+        let mut store = self.write();
+
+        let mut overlapping = Vec::new();
+        for claim in claims {
+            if store.claims.contains_key(&claim.id) || store.removed.contains(&claim.id) {
+                continue;
+            }
+            if claim.keys().any(|key| store.chunks.contains_key(&key)) {
+                overlapping.push(claim.id);
+                continue;
+            }
+            for key in claim.keys() {
+                store.chunks.insert(key, claim.id);
+            }
+            store.next_id = store.next_id.max(claim.id.0);
+            store.claims.insert(claim.id, claim);
+        }
+        overlapping
+    }
+
+    /// Whether memory has the latest word on claim `id`: it is loaded, or
+    /// it was deleted since the changes were last taken.
+    pub fn holds(&self, id: ClaimId) -> bool {
+        let store = self.store.read().unwrap();
+        store.claims.contains_key(&id) || store.removed.contains(&id)
+    }
+
+    /// Whether claim `id` changed since the changes were last taken.
+    pub fn is_unsaved(&self, id: ClaimId) -> bool {
+        self.store.read().unwrap().changed.contains(&id)
+    }
+
+    pub fn ids(&self) -> Vec<ClaimId> {
+        let mut ids: Vec<ClaimId> = self.store.read().unwrap().claims.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Drops `ids` from memory, leaving them in storage. Claims with changes
+    /// not yet handed to storage are kept. Returns how many were dropped.
+    pub fn evict(&self, ids: &[ClaimId]) -> usize {
+        let mut store = self.write();
+
+        let mut evicted = 0;
+        for id in ids {
+            if store.changed.contains(id) {
+                continue;
+            }
+            if let Some(claim) = store.claims.remove(id) {
+                for key in claim.keys() {
+                    store.chunks.remove(&key);
+                }
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+
+    /// How many claims there are and how many chunks they cover.
+    pub fn counts(&self) -> (usize, usize) {
+        let store = self.store.read().unwrap();
+        (store.claims.len(), store.chunks.len())
+    }
+
+    pub fn owners(&self) -> Vec<String> {
+        let mut owners: Vec<String> = self
+            .store
+            .read()
+            .unwrap()
+            .claims
+            .values()
+            .map(|claim| claim.owner.clone())
+            .collect();
+        owners.sort();
+        owners.dedup();
+        owners
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f64, z: f64) -> Position {
+        Position {
+            x,
+            y: 64.0,
+            z,
+            ..Position::default()
+        }
+    }
+
+    #[test]
+    fn positive_coordinates() {
+        assert_eq!(chunk_of(at(0.0, 0.0)), ChunkPosition::new(0, 0));
+        assert_eq!(chunk_of(at(15.9, 15.9)), ChunkPosition::new(0, 0));
+        assert_eq!(chunk_of(at(16.0, 31.5)), ChunkPosition::new(1, 1));
+        assert_eq!(chunk_of(at(100.0, 260.0)), ChunkPosition::new(6, 16));
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        assert_eq!(chunk_of(at(-0.5, -0.5)), ChunkPosition::new(-1, -1));
+        assert_eq!(chunk_of(at(-16.0, -16.0)), ChunkPosition::new(-1, -1));
+        assert_eq!(chunk_of(at(-16.1, -17.0)), ChunkPosition::new(-2, -2));
+        assert_eq!(chunk_of(at(-100.0, 5.0)), ChunkPosition::new(-7, 0));
+    }
+
+    #[test]
+    fn block_coordinates() {
+        assert_eq!(block_chunk(15, 16), ChunkPosition::new(0, 1));
+        assert_eq!(block_chunk(-1, -16), ChunkPosition::new(-1, -1));
+        assert_eq!(block_chunk(-17, -32), ChunkPosition::new(-2, -2));
+    }
+}
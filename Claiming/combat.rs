@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use feather_server::{entity::Entity, player::Player};
+
+/// The player to blame for damage dealt by `damager`: the player
+/// themselves, whoever shot a projectile, or whoever threw a splash or
+/// lingering potion.
+pub fn responsible_player(damager: &Entity) -> Option<&Player> {
+    damager.as_player().or_else(|| damager.source().and_then(Entity::as_player))
+}
+
+/// Pairs of players who have recently been in a fight that was allowed.
+/// While tagged they can keep fighting each other even inside claims that
+/// forbid PvP, so nobody can escape a fight by stepping over a claim
+/// border. The tag only covers the two of them, not anyone else either
+/// one meets there.
+#[derive(Default)]
+pub struct CombatTags {
+    tagged_until: Mutex<HashMap<(String, String), Instant>>,
+}
+
+/// The same key whichever side struck first.
+fn pair(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_owned(), b.to_owned())
+    } else {
+        (b.to_owned(), a.to_owned())
+    }
+}
+
+impl CombatTags {
+    pub fn tag(&self, attacker: &str, victim: &str, grace: Duration) {
+        let now = Instant::now();
+        let mut tagged_until = self.tagged_until.lock().unwrap();
+        tagged_until.retain(|_, until| *until > now);
+        tagged_until.insert(pair(attacker, victim), now + grace);
+    }
+
+    pub fn are_fighting(&self, attacker: &str, victim: &str) -> bool {
+        self.tagged_until
+            .lock()
+            .unwrap()
+            .get(&pair(attacker, victim))
+            .map_or(false, |&until| until > Instant::now())
+    }
+
+    pub fn forget(&self, player: &str) {
+        self.tagged_until.lock().unwrap().retain(|(a, b), _| a != player && b != player);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_only_cover_the_two_who_fought() {
+        let tags = CombatTags::default();
+        tags.tag("alice", "bob", Duration::from_secs(30));
+
+        assert!(tags.are_fighting("bob", "alice"));
+        assert!(!tags.are_fighting("alice", "carol"));
+        assert!(!tags.are_fighting("carol", "bob"));
+        tags.forget("bob");
+        assert!(!tags.are_fighting("alice", "bob"));
+    }
+}
@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::claims;
+use crate::dimensions::Dimension;
+use crate::expiration::ExpirationAction;
+use crate::greetings::MessageDisplay;
+use crate::groups;
+use crate::hud::HudDisplay;
+use crate::notifier::NoticeKind;
+use crate::upkeep::UpkeepCharge;
+
+pub const CONFIG_PATH: &str = "plugins/LandClaiming/config.toml";
+
+/// Written to `CONFIG_PATH` the first time the plugin starts.
+const DEFAULT_CONFIG: &str = include_str!("config.toml");
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub worlds: Vec<String>,
+    pub world_overrides: HashMap<String, WorldOverride>,
+    pub dimensions: HashMap<Dimension, WorldOverride>,
+    pub limits: LimitsConfig,
+    pub claim_blocks: ClaimBlocksConfig,
+    pub economy: EconomyConfig,
+    pub protection: ProtectionConfig,
+    pub rate_limits: RateLimitsConfig,
+    pub messages: MessagesConfig,
+    pub storage: StorageConfig,
+    pub expiration: ExpirationConfig,
+    pub homes: HomesConfig,
+    pub notifications: NotificationsConfig,
+    pub perks: PerksConfig,
+    pub hud: HudConfig,
+    pub upkeep: UpkeepConfig,
+    pub validation: ValidationConfig,
+    pub webmap: WebMapConfig,
+}
+
+impl Config {
+    /// The overrides that apply in `world`, the world's own before those of
+    /// its dimension.
+    fn overrides(&self, world: &str, dimension: Dimension) -> impl Iterator<Item = &WorldOverride> {
+        self.world_overrides.get(world).into_iter().chain(self.dimensions.get(&dimension))
+    }
+
+    pub fn is_world_enabled(&self, world: &str, dimension: Dimension) -> bool {
+        let listed = self.worlds.is_empty() || self.worlds.iter().any(|enabled| enabled == world);
+        let claiming = self.overrides(world, dimension).find_map(|overrides| overrides.claiming);
+        listed && claiming != Some(false)
+    }
+
+    pub fn limits_for(&self, world: &str, dimension: Dimension) -> &LimitsConfig {
+        self.overrides(world, dimension)
+            .find_map(|overrides| overrides.limits.as_ref())
+            .unwrap_or(&self.limits)
+    }
+
+    pub fn pvp_allowed_in(&self, world: &str, dimension: Dimension) -> bool {
+        self.overrides(world, dimension)
+            .find_map(|overrides| overrides.pvp)
+            .unwrap_or(self.protection.pvp)
+    }
+
+    /// `None` if claims in `world` may reach any distance from its centre.
+    pub fn border_radius_in(&self, world: &str, dimension: Dimension) -> Option<u32> {
+        let radius = self
+            .overrides(world, dimension)
+            .find_map(|overrides| overrides.border_radius)
+            .unwrap_or(self.validation.border_radius);
+        Some(radius).filter(|&radius| radius > 0)
+    }
+
+    /// The Overworld whose portals lead into Nether world `nether`: its
+    /// `portal_link`, or else its name without the `_nether` suffix.
+    pub fn overworld_of(&self, nether: &str) -> String {
+        self.world_overrides
+            .get(nether)
+            .and_then(|overrides| overrides.portal_link.clone())
+            .unwrap_or_else(|| nether.trim_end_matches("_nether").to_owned())
+    }
+}
+
+/// Settings that replace the top-level ones inside a single world, or in
+/// every world of a dimension.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WorldOverride {
+    /// Set to false to turn claiming off.
+    pub claiming: Option<bool>,
+    pub limits: Option<LimitsConfig>,
+    pub pvp: Option<bool>,
+    pub border_radius: Option<u32>,
+    /// For a Nether world, the Overworld its portals lead to.
+    pub portal_link: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    pub default_claims: usize,
+    pub tiers: Vec<usize>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            default_claims: 8,
+            tiers: vec![16, 32, 64, 128],
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct ClaimBlocksConfig {
+    pub starting: u32,
+    pub per_chunk: u32,
+}
+
+impl Default for ClaimBlocksConfig {
+    fn default() -> Self {
+        Self {
+            starting: 2048,
+            per_chunk: 256,
+        }
+    }
+}
+
+/// Prices are per claim block. Zero turns buying or selling off.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct EconomyConfig {
+    pub buy_price: f64,
+    pub sell_price: f64,
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        Self {
+            buy_price: 1.0,
+            sell_price: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct ProtectionConfig {
+    pub block_interact: bool,
+    pub entry_bans: bool,
+    pub pvp: bool,
+    pub combat_tag_seconds: u64,
+    pub mob_spawning: bool,
+    pub mob_griefing: bool,
+    pub public_harvest: bool,
+}
+
+impl Default for ProtectionConfig {
+    fn default() -> Self {
+        Self {
+            block_interact: true,
+            entry_bans: true,
+            pvp: false,
+            combat_tag_seconds: 15,
+            mob_spawning: true,
+            mob_griefing: false,
+            public_harvest: false,
+        }
+    }
+}
+
+impl ProtectionConfig {
+    pub fn combat_tag(&self) -> Duration {
+        Duration::from_secs(self.combat_tag_seconds)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitsConfig {
+    pub window_seconds: u64,
+    pub claims: u32,
+    pub unclaims: u32,
+    pub trust: u32,
+    pub entry_message_seconds: u64,
+}
+
+impl Default for RateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            window_seconds: 10,
+            claims: 5,
+            unclaims: 5,
+            trust: 10,
+            entry_message_seconds: 10,
+        }
+    }
+}
+
+impl RateLimitsConfig {
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_seconds)
+    }
+
+    pub fn entry_message_quiet(&self) -> Duration {
+        Duration::from_secs(self.entry_message_seconds)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct MessagesConfig {
+    pub display: MessageDisplay,
+    pub locale: String,
+    pub per_player_locale: bool,
+}
+
+impl Default for MessagesConfig {
+    fn default() -> Self {
+        Self {
+            display: MessageDisplay::Chat,
+            locale: String::from("en_us"),
+            per_player_locale: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    #[default]
+    Memory,
+    File,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    pub directory: String,
+    pub flush_interval_seconds: u64,
+    /// Only keep claims in memory for regions of the world that are loaded.
+    pub lazy_loading: bool,
+    pub unload_after_minutes: u64,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::Memory,
+            directory: String::from("plugins/LandClaiming/data"),
+            flush_interval_seconds: 5,
+            lazy_loading: false,
+            unload_after_minutes: 10,
+        }
+    }
+}
+
+impl StorageConfig {
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_secs(self.flush_interval_seconds.max(1))
+    }
+
+    /// Never shorter than the flush interval, so claims are saved before
+    /// they are dropped.
+    pub fn unload_after(&self) -> Duration {
+        Duration::from_secs(self.unload_after_minutes * 60).max(self.flush_interval())
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct ExpirationConfig {
+    pub inactivity_days: u64,
+    pub action: ExpirationAction,
+}
+
+impl Default for ExpirationConfig {
+    fn default() -> Self {
+        Self {
+            inactivity_days: 60,
+            action: ExpirationAction::FlagForReview,
+        }
+    }
+}
+
+impl ExpirationConfig {
+    pub fn inactivity_limit(&self) -> Duration {
+        Duration::from_secs(self.inactivity_days * 24 * 60 * 60)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct HomesConfig {
+    pub warmup_seconds: u64,
+    pub cooldown_seconds: u64,
+    pub allow_trusted: bool,
+}
+
+impl Default for HomesConfig {
+    fn default() -> Self {
+        Self {
+            warmup_seconds: 5,
+            cooldown_seconds: 60,
+            allow_trusted: false,
+        }
+    }
+}
+
+impl HomesConfig {
+    pub fn warmup(&self) -> Duration {
+        Duration::from_secs(self.warmup_seconds)
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_seconds)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Where notices are posted as Discord-style JSON. Empty for nowhere.
+    pub webhook_url: String,
+    pub webhook_events: Vec<NoticeKind>,
+    /// Sent to online players with `landclaiming.admin.notify`.
+    pub staff_events: Vec<NoticeKind>,
+    /// Denials of one player in one claim that make a repeated_denials
+    /// notice. 0 turns those off.
+    pub denial_threshold: u32,
+    pub denial_window_minutes: u64,
+}
+
+impl NotificationsConfig {
+    pub fn denial_window(&self) -> Duration {
+        Duration::from_secs(self.denial_window_minutes * 60)
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: String::new(),
+            webhook_events: vec![NoticeKind::ClaimDeleted, NoticeKind::RepeatedDenials],
+            staff_events: vec![NoticeKind::RepeatedDenials],
+            denial_threshold: 10,
+            denial_window_minutes: 5,
+        }
+    }
+}
+
+/// Defaults for claims that have not set the flight or keep_inventory
+/// flags.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct PerksConfig {
+    pub flight: bool,
+    pub keep_inventory: bool,
+}
+
+impl Default for PerksConfig {
+    fn default() -> Self {
+        Self {
+            flight: true,
+            keep_inventory: false,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HudConfig {
+    pub display: HudDisplay,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct UpkeepConfig {
+    pub enabled: bool,
+    pub charge: UpkeepCharge,
+    pub per_chunk_per_day: f64,
+    pub grace_days: u64,
+    pub exempt_owners: Vec<String>,
+}
+
+impl Default for UpkeepConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            charge: UpkeepCharge::ClaimBlocks,
+            per_chunk_per_day: 16.0,
+            grace_days: 7,
+            exempt_owners: Vec::new(),
+        }
+    }
+}
+
+impl UpkeepConfig {
+    pub fn grace(&self) -> Duration {
+        Duration::from_secs(self.grace_days * 24 * 60 * 60)
+    }
+
+    /// Group-owned and admin claims have no one account to charge, so they
+    /// never pay upkeep.
+    pub fn is_exempt(&self, owner: &str) -> bool {
+        owner == claims::ADMIN_OWNER
+            || groups::group_of_owner(owner).is_some()
+            || self.exempt_owners.iter().any(|exempt| exempt.eq_ignore_ascii_case(owner))
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct ValidationConfig {
+    pub buffer_chunks: u32,
+    /// Refuses claims that would wall in another owner's claim.
+    pub prevent_enclosure: bool,
+    pub border_radius: u32,
+    pub banned_regions: Vec<BannedRegion>,
+    /// Claiming in the Overworld keeps others from claiming the Nether
+    /// chunks its portals lead to.
+    pub reserve_nether: bool,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            buffer_chunks: 0,
+            prevent_enclosure: true,
+            border_radius: 29_999_984,
+            banned_regions: Vec::new(),
+            reserve_nether: false,
+        }
+    }
+}
+
+/// A square of blocks, such as the area around spawn, where nobody may
+/// claim.
+#[derive(Clone, Deserialize)]
+pub struct BannedRegion {
+    pub name: String,
+    pub world: String,
+    pub x: i32,
+    pub z: i32,
+    pub radius: u32,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct WebMapConfig {
+    pub enabled: bool,
+    pub file: String,
+    pub http_bind: String,
+    pub http_port: u16,
+}
+
+impl Default for WebMapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: String::from("plugins/LandClaiming/webmap/claims.geojson"),
+            http_bind: String::from("127.0.0.1"),
+            http_port: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "could not read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "invalid {}: {}", path.display(), err),
+        }
+    }
+}
+
+/// Reads a TOML file, first writing `default_contents` to it if it does
+/// not exist yet.
+pub fn read_or_create<T: DeserializeOwned>(
+    path: &Path,
+    default_contents: &str,
+) -> Result<T, ConfigError> {
+    let io_error = |err| ConfigError::Io(path.to_owned(), err);
+
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        fs::write(path, default_contents).map_err(io_error)?;
+    }
+
+    let contents = fs::read_to_string(path).map_err(io_error)?;
+    toml::from_str(&contents).map_err(|err| ConfigError::Parse(path.to_owned(), err))
+}
+
+/// The live configuration. Readers take a cheap snapshot with `get`, so a
+/// reload never changes settings halfway through handling an event.
+pub struct ConfigHandle {
+    path: PathBuf,
+    current: RwLock<Arc<Config>>,
+}
+
+impl Default for ConfigHandle {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from(CONFIG_PATH),
+            current: RwLock::new(Arc::new(Config::default())),
+        }
+    }
+}
+
+impl ConfigHandle {
+    pub fn get(&self) -> Arc<Config> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Reads the config file, creating it with the defaults if it does not
+    /// exist yet. The previous settings stay active if anything fails.
+    pub fn load(&self) -> Result<(), ConfigError> {
+        let config: Config = read_or_create(&self.path, DEFAULT_CONFIG)?;
+        *self.current.write().unwrap() = Arc::new(config);
+        Ok(())
+    }
+}
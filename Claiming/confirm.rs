@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::claims::ClaimKey;
+
+/// How long `/claim confirm` is accepted after a prompt.
+pub const CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Something that cannot be undone, waiting on `/claim confirm`.
+#[derive(Clone)]
+pub enum PendingAction {
+    Unclaim(ClaimKey),
+    AbandonAll,
+    Transfer { key: ClaimKey, recipient: String },
+    Buy { key: ClaimKey, price: f64 },
+    Purge { owner: String },
+    RestoreClaims { path: PathBuf },
+}
+
+/// The last unconfirmed action of each player. Asking for another
+/// replaces it.
+#[derive(Default)]
+pub struct Confirmations {
+    pending: Mutex<HashMap<String, (PendingAction, Instant)>>,
+}
+
+impl Confirmations {
+    pub fn ask(&self, player: &str, action: PendingAction) {
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(player.to_owned(), (action, Instant::now() + CONFIRM_TIMEOUT));
+    }
+
+    pub fn take(&self, player: &str) -> Option<PendingAction> {
+        self.pending
+            .lock()
+            .unwrap()
+            .remove(player)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(action, _)| action)
+    }
+
+    pub fn forget(&self, player: &str) {
+        self.pending.lock().unwrap().remove(player);
+    }
+}
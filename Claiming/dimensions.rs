@@ -0,0 +1,72 @@
+use feather_core::world::{ChunkPosition, Dimension as WorldDimension};
+use feather_server::world::World;
+use serde::Deserialize;
+
+/// Overworld blocks travelled for each block travelled in the Nether.
+pub const NETHER_SCALE: i32 = 8;
+
+/// The kind of world a claim is in, for settings that differ between the
+/// Overworld, the Nether and the End.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    Overworld,
+    Nether,
+    End,
+}
+
+impl Dimension {
+    pub fn of(world: &World) -> Self {
+        match world.dimension() {
+            WorldDimension::Nether => Dimension::Nether,
+            WorldDimension::End => Dimension::End,
+            _ => Dimension::Overworld,
+        }
+    }
+}
+
+/// Every Overworld chunk whose portals come out in Nether `chunk`: eight
+/// by eight of them.
+pub fn overworld_chunks_of(chunk: ChunkPosition) -> impl Iterator<Item = ChunkPosition> {
+    let (min_x, min_z) = (chunk.x * NETHER_SCALE, chunk.z * NETHER_SCALE);
+    (min_x..min_x + NETHER_SCALE)
+        .flat_map(move |x| (min_z..min_z + NETHER_SCALE).map(move |z| ChunkPosition::new(x, z)))
+}
+
+/// Who a Nether chunk is reserved for, given the `owners` of its Overworld
+/// chunks: nobody when `claimant` holds one of them, since their own land
+/// is on the other side too, and otherwise the first other owner.
+pub fn reserved_by(claimant: &str, owners: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut reserved_by = None;
+    for owner in owners {
+        if owner == claimant {
+            return None;
+        }
+        reserved_by.get_or_insert(owner);
+    }
+    reserved_by
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nether_chunks_cover_eight_by_eight_overworld_chunks() {
+        let chunks: Vec<ChunkPosition> = overworld_chunks_of(ChunkPosition::new(-1, 2)).collect();
+
+        assert_eq!(chunks.len(), 64);
+        assert!(chunks.contains(&ChunkPosition::new(-8, 16)));
+        assert!(chunks.contains(&ChunkPosition::new(-1, 23)));
+        assert!(!chunks.contains(&ChunkPosition::new(0, 16)));
+    }
+
+    #[test]
+    fn owners_sharing_the_overworld_side_may_claim_the_nether_chunk() {
+        let owners = || vec![String::from("bob"), String::from("alice")];
+
+        assert_eq!(reserved_by("alice", owners()), None);
+        assert_eq!(reserved_by("carol", owners()), Some(String::from("bob")));
+        assert_eq!(reserved_by("carol", Vec::new()), None);
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::{Arc, RwLock};
+use feather_server::command::{Command, CommandContext, CommandSender};
+use feather_server::player::Player;
+
+use crate::args::ArgReader;
+use crate::permissions;
+use crate::LandClaiming;
+
+/// Moves server currency in and out of player accounts. Economy plugins
+/// install theirs through `LandClaimingApi::set_economy_provider`.
+pub trait EconomyProvider: Send + Sync {
+    fn balance(&self, player: &str) -> f64;
+
+    /// Takes `amount` from the player, returning false if they cannot pay.
+    fn withdraw(&self, player: &str, amount: f64) -> bool;
+
+    fn deposit(&self, player: &str, amount: f64);
+
+    fn format(&self, amount: f64) -> String {
+        format!("{:.2}", amount)
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// Used until an economy plugin installs a provider; nothing can be bought
+/// or sold.
+pub struct NoEconomy;
+
+impl EconomyProvider for NoEconomy {
+    fn balance(&self, _player: &str) -> f64 {
+        0.0
+    }
+
+    fn withdraw(&self, _player: &str, _amount: f64) -> bool {
+        false
+    }
+
+    fn deposit(&self, _player: &str, _amount: f64) {}
+
+    fn is_available(&self) -> bool {
+        false
+    }
+}
+
+pub struct Economy {
+    provider: RwLock<Box<dyn EconomyProvider>>,
+}
+
+impl Default for Economy {
+    fn default() -> Self {
+        Self {
+            provider: RwLock::new(Box::new(NoEconomy)),
+        }
+    }
+}
+
+impl Economy {
+    pub fn set_provider(&self, provider: impl EconomyProvider + 'static) {
+        *self.provider.write().unwrap() = Box::new(provider);
+    }
+
+    pub fn with<R>(&self, f: impl FnOnce(&dyn EconomyProvider) -> R) -> R {
+        f(self.provider.read().unwrap().as_ref())
+    }
+}
+
+fn parse_amount(args: &[String]) -> Option<i64> {
+    ArgReader::new(args).count::<i64>()
+}
+
+pub(crate) struct BuyClaimBlocksCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl BuyClaimBlocksCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+
+    fn buy(&self, player: &Player, amount: i64) {
+        let rate = self.plugin.config.get().economy.buy_price;
+        if rate <= 0.0 {
+            return self.plugin.tell(player, "economy.buying_disabled", &[]);
+        }
+
+        let price = rate * amount as f64;
+        let paid = self.plugin.economy.with(|economy| {
+            if !economy.is_available() {
+                Err("economy.unavailable")
+            } else if economy.withdraw(player.name(), price) {
+                Ok(economy.format(price))
+            } else {
+                Err("economy.cannot_afford")
+            }
+        });
+
+        match paid {
+            Ok(price) => {
+                self.plugin.claim_blocks.adjust(player.name(), amount);
+                let remaining = self.plugin.remaining_claim_blocks(player.name());
+                self.plugin.tell(
+                    player,
+                    "economy.bought",
+                    &[("count", &amount), ("price", &price), ("remaining", &remaining)],
+                );
+            }
+            Err(message) => {
+                let price = self.plugin.economy.with(|economy| economy.format(price));
+                self.plugin.tell(player, message, &[("price", &price)]);
+            }
+        }
+    }
+}
+
+impl Command for BuyClaimBlocksCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        if !self.plugin.require(player, permissions::BUY_CLAIM_BLOCKS) {
+            return;
+        }
+
+        match parse_amount(&args) {
+            Some(amount) => self.buy(player, amount),
+            None => self.plugin.tell(player, "general.usage", &[("usage", &self.get_usage())]),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        String::from("buyclaimblocks")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/buyclaimblocks <amount>")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Buys claim blocks with server currency.")
+    }
+}
+
+pub(crate) struct SellClaimBlocksCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl SellClaimBlocksCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+
+    fn sell(&self, player: &Player, amount: i64) {
+        let rate = self.plugin.config.get().economy.sell_price;
+        if rate <= 0.0 {
+            return self.plugin.tell(player, "economy.selling_disabled", &[]);
+        }
+        if !self.plugin.economy.with(|economy| economy.is_available()) {
+            return self.plugin.tell(player, "economy.unavailable", &[]);
+        }
+
+        let remaining = match self.plugin.spend_claim_blocks(player.name(), amount) {
+            Ok(remaining) => remaining,
+            Err(remaining) => {
+                return self.plugin.tell(
+                    player,
+                    "economy.not_enough_blocks",
+                    &[("remaining", &remaining)],
+                )
+            }
+        };
+
+        let price = rate * amount as f64;
+        let price = self.plugin.economy.with(|economy| {
+            economy.deposit(player.name(), price);
+            economy.format(price)
+        });
+        self.plugin.tell(
+            player,
+            "economy.sold",
+            &[("count", &amount), ("price", &price), ("remaining", &remaining)],
+        );
+    }
+}
+
+impl Command for SellClaimBlocksCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        if !self.plugin.require(player, permissions::SELL_CLAIM_BLOCKS) {
+            return;
+        }
+
+        match parse_amount(&args) {
+            Some(amount) => self.sell(player, amount),
+            None => self.plugin.tell(player, "general.usage", &[("usage", &self.get_usage())]),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        String::from("sellclaimblocks")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/sellclaimblocks <amount>")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Sells unused claim blocks for server currency.")
+    }
+}
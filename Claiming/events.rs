@@ -0,0 +1,89 @@
+use feather_core::world::ChunkPosition;
+use feather_server::{
+    event::{Cancellable, Event},
+    world::WorldId,
+};
+
+/// Fired before a chunk is claimed. Cancelling it leaves the chunk
+/// unclaimed.
+pub struct ClaimCreateEvent {
+    pub world: WorldId,
+    pub chunk: ChunkPosition,
+    pub owner: String,
+    cancelled: bool,
+}
+
+impl ClaimCreateEvent {
+    pub fn new(world: WorldId, chunk: ChunkPosition, owner: &str) -> Self {
+        Self {
+            world,
+            chunk,
+            owner: owner.to_owned(),
+            cancelled: false,
+        }
+    }
+}
+
+impl Event for ClaimCreateEvent {}
+
+impl Cancellable for ClaimCreateEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+/// Fired before a claim is removed, whether by its owner, an admin or
+/// expiration. Cancelling it keeps the claim.
+pub struct ClaimDeleteEvent {
+    pub world: WorldId,
+    pub chunk: ChunkPosition,
+    pub owner: String,
+    cancelled: bool,
+}
+
+impl ClaimDeleteEvent {
+    pub fn new(world: WorldId, chunk: ChunkPosition, owner: &str) -> Self {
+        Self {
+            world,
+            chunk,
+            owner: owner.to_owned(),
+            cancelled: false,
+        }
+    }
+}
+
+impl Event for ClaimDeleteEvent {}
+
+impl Cancellable for ClaimDeleteEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+/// Fired when a player walks into a different claim.
+pub struct ClaimEnterEvent {
+    pub player: String,
+    pub world: WorldId,
+    pub chunk: ChunkPosition,
+    pub owner: String,
+}
+
+impl Event for ClaimEnterEvent {}
+
+/// Fired after a player gains or loses trust in a claim.
+pub struct ClaimTrustChangeEvent {
+    pub world: WorldId,
+    pub chunk: ChunkPosition,
+    pub player: String,
+    pub trusted: bool,
+}
+
+impl Event for ClaimTrustChangeEvent {}
@@ -0,0 +1,117 @@
+use std::collections::{HashSet, VecDeque};
+use feather_core::world::ChunkPosition;
+
+/// The most rows a single `/claim expand` or `/claim shrink` may move an edge.
+pub const MAX_ROWS: u32 = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] =
+        [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "north" | "n" => Some(Direction::North),
+            "east" | "e" => Some(Direction::East),
+            "south" | "s" => Some(Direction::South),
+            "west" | "w" => Some(Direction::West),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::North => "north",
+            Direction::East => "east",
+            Direction::South => "south",
+            Direction::West => "west",
+        }
+    }
+}
+
+/// The chunks sharing an edge with `chunk`.
+pub fn neighbours(chunk: ChunkPosition) -> [ChunkPosition; 4] {
+    [
+        ChunkPosition::new(chunk.x, chunk.z - 1),
+        ChunkPosition::new(chunk.x + 1, chunk.z),
+        ChunkPosition::new(chunk.x, chunk.z + 1),
+        ChunkPosition::new(chunk.x - 1, chunk.z),
+    ]
+}
+
+/// Every chunk reachable from `start` through edge-sharing chunks for
+/// which `belongs` is true.
+pub fn connected(
+    start: ChunkPosition,
+    belongs: impl Fn(ChunkPosition) -> bool,
+) -> Vec<ChunkPosition> {
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(start);
+    queue.push_back(start);
+
+    while let Some(chunk) = queue.pop_front() {
+        for next in neighbours(chunk).iter().copied() {
+            if !seen.contains(&next) && belongs(next) {
+                seen.insert(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+struct Bounds {
+    min_x: i32,
+    max_x: i32,
+    min_z: i32,
+    max_z: i32,
+}
+
+fn bounds(area: &[ChunkPosition]) -> Bounds {
+    Bounds {
+        min_x: area.iter().map(|chunk| chunk.x).min().unwrap_or(0),
+        max_x: area.iter().map(|chunk| chunk.x).max().unwrap_or(0),
+        min_z: area.iter().map(|chunk| chunk.z).min().unwrap_or(0),
+        max_z: area.iter().map(|chunk| chunk.z).max().unwrap_or(0),
+    }
+}
+
+/// The `rows` rows of chunks beyond the `direction` edge of `area`'s
+/// bounding box, spanning its full width.
+pub fn added(area: &[ChunkPosition], direction: Direction, rows: u32) -> Vec<ChunkPosition> {
+    let b = bounds(area);
+    let rows = rows as i32;
+
+    let (xs, zs) = match direction {
+        Direction::North => (b.min_x..=b.max_x, b.min_z - rows..=b.min_z - 1),
+        Direction::South => (b.min_x..=b.max_x, b.max_z + 1..=b.max_z + rows),
+        Direction::East => (b.max_x + 1..=b.max_x + rows, b.min_z..=b.max_z),
+        Direction::West => (b.min_x - rows..=b.min_x - 1, b.min_z..=b.max_z),
+    };
+    xs.flat_map(|x| zs.clone().map(move |z| ChunkPosition::new(x, z)))
+        .collect()
+}
+
+/// The chunks of `area` within `rows` rows of its `direction` edge.
+pub fn removed(area: &[ChunkPosition], direction: Direction, rows: u32) -> Vec<ChunkPosition> {
+    let b = bounds(area);
+    let rows = rows as i32;
+
+    area.iter()
+        .copied()
+        .filter(|chunk| match direction {
+            Direction::North => chunk.z < b.min_z + rows,
+            Direction::South => chunk.z > b.max_z - rows,
+            Direction::East => chunk.x > b.max_x - rows,
+            Direction::West => chunk.x < b.min_x + rows,
+        })
+        .collect()
+}
@@ -0,0 +1,111 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use serde::Deserialize;
+
+/// How often claim owners are checked for inactivity.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpirationAction {
+    Unclaim,
+    FlagForReview,
+}
+
+/// Tracks when claim owners were last online and which of them have been
+/// gone long enough for their claims to expire.
+#[derive(Default)]
+pub struct ClaimExpiry {
+    last_seen: Mutex<HashMap<String, SystemTime>>,
+    flagged: Mutex<HashSet<String>>,
+    /// Set when `last_seen` changes, so it is only saved when needed.
+    changed: AtomicBool,
+}
+
+impl ClaimExpiry {
+    pub fn record_seen(&self, player_name: &str) {
+        self.last_seen
+            .lock()
+            .unwrap()
+            .insert(player_name.to_owned(), SystemTime::now());
+        self.flagged.lock().unwrap().remove(player_name);
+        self.changed.store(true, Ordering::Relaxed);
+    }
+
+    /// Puts back the visits loaded from storage.
+    pub fn restore(&self, last_seen: impl IntoIterator<Item = (String, SystemTime)>) {
+        self.last_seen.lock().unwrap().extend(last_seen);
+    }
+
+    pub fn last_seen(&self) -> HashMap<String, SystemTime> {
+        self.last_seen.lock().unwrap().clone()
+    }
+
+    /// Whether `last_seen` changed since the previous call.
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+
+    /// The recorded spelling of `player`, if they were ever seen.
+    pub fn known_name(&self, player: &str) -> Option<String> {
+        self.last_seen
+            .lock()
+            .unwrap()
+            .keys()
+            .find(|name| name.eq_ignore_ascii_case(player))
+            .cloned()
+    }
+
+    /// Whether `player` was last seen within `limit`. Players never seen
+    /// count as away.
+    pub fn seen_within(&self, player: &str, limit: Duration) -> bool {
+        self.last_seen.lock().unwrap().get(player).map_or(false, |&seen| {
+            SystemTime::now().duration_since(seen).unwrap_or_default() <= limit
+        })
+    }
+
+    /// Filters `owners` down to those who have been offline longer than
+    /// `limit`. Owners with no recorded visit start their clock now.
+    pub fn inactive_owners(
+        &self,
+        owners: &[String],
+        limit: Duration,
+        is_online: impl Fn(&str) -> bool,
+    ) -> Vec<String> {
+        let now = SystemTime::now();
+        let mut last_seen = self.last_seen.lock().unwrap();
+        self.changed.store(true, Ordering::Relaxed);
+
+        owners
+            .iter()
+            .filter(|owner| {
+                if is_online(owner) {
+                    last_seen.insert(owner.to_string(), now);
+                    return false;
+                }
+
+                let seen = *last_seen.entry(owner.to_string()).or_insert(now);
+                now.duration_since(seen).unwrap_or_default() > limit
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn flag(&self, owner: &str) {
+        self.flagged.lock().unwrap().insert(owner.to_owned());
+    }
+
+    pub fn flagged(&self) -> Vec<String> {
+        let mut flagged: Vec<String> = self.flagged.lock().unwrap().iter().cloned().collect();
+        flagged.sort();
+        flagged
+    }
+
+    pub fn forget(&self, owner: &str) {
+        self.last_seen.lock().unwrap().remove(owner);
+        self.flagged.lock().unwrap().remove(owner);
+        self.changed.store(true, Ordering::Relaxed);
+    }
+}
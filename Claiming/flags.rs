@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use feather_server::command::{Command, CommandContext, CommandSender};
+use serde::{Deserialize, Serialize};
+
+use crate::args::{ArgKind, Syntax};
+use crate::LandClaiming;
+
+/// A per-claim toggle. Claims only store the flags their managers have
+/// set; anything unset falls back to the server configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimFlag {
+    /// Players may hurt each other inside the claim.
+    Pvp,
+    /// Hostile mobs may spawn naturally inside the claim.
+    MobSpawning,
+    /// Mobs may change blocks: endermen taking blocks, creeper and wither
+    /// explosions, ravagers trampling crops and so on.
+    MobGriefing,
+    /// Anyone may pick and break crops, trusted or not. Trampling farmland
+    /// still needs trust.
+    PublicHarvest,
+    /// Trusted players may fly with `/claim fly` while inside the claim.
+    Flight,
+    /// Trusted players keep their items and experience when they die inside
+    /// the claim.
+    KeepInventory,
+}
+
+impl ClaimFlag {
+    pub const ALL: [ClaimFlag; 6] = [
+        ClaimFlag::Pvp,
+        ClaimFlag::MobSpawning,
+        ClaimFlag::MobGriefing,
+        ClaimFlag::PublicHarvest,
+        ClaimFlag::Flight,
+        ClaimFlag::KeepInventory,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "pvp" => Some(ClaimFlag::Pvp),
+            "mob_spawning" => Some(ClaimFlag::MobSpawning),
+            "mob_griefing" => Some(ClaimFlag::MobGriefing),
+            "public_harvest" => Some(ClaimFlag::PublicHarvest),
+            "flight" => Some(ClaimFlag::Flight),
+            "keep_inventory" => Some(ClaimFlag::KeepInventory),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClaimFlag::Pvp => "pvp",
+            ClaimFlag::MobSpawning => "mob_spawning",
+            ClaimFlag::MobGriefing => "mob_griefing",
+            ClaimFlag::PublicHarvest => "public_harvest",
+            ClaimFlag::Flight => "flight",
+            ClaimFlag::KeepInventory => "keep_inventory",
+        }
+    }
+}
+
+pub type ClaimFlags = HashMap<ClaimFlag, bool>;
+
+/// Parses `on`/`off` style values. `None` means the text was not one.
+pub fn parse_value(text: &str) -> Option<bool> {
+    match text.to_lowercase().as_str() {
+        "on" | "true" | "allow" | "yes" => Some(true),
+        "off" | "false" | "deny" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// `/claimflag`, the same as `/claim flag` but shorter to type when naming
+/// a claim elsewhere.
+pub struct ClaimFlagCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl ClaimFlagCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for ClaimFlagCommand {
+    fn execute(&self, ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        self.plugin.flag_command(&ctx.game, sender, &args);
+    }
+
+    fn tab_complete(
+        &self,
+        ctx: &mut CommandContext,
+        sender: &dyn CommandSender,
+        args: Vec<String>,
+    ) -> Vec<String> {
+        const SYNTAX: Syntax = Syntax::Args(&[
+            ArgKind::Either(&ArgKind::ClaimName, &ArgKind::Flag),
+            ArgKind::Either(&ArgKind::Flag, &ArgKind::FlagValue),
+            ArgKind::FlagValue,
+        ]);
+        self.plugin.complete(&ctx.game, sender, &SYNTAX, &args)
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claimflag")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claimflag [claim] <flag> [on|off|default] [<world> <x> <z>]")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Shows or changes a flag of the claim you are standing in, name or locate.")
+    }
+}
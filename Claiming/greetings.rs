@@ -0,0 +1,31 @@
+use feather_server::player::Player;
+use serde::Deserialize;
+
+/// Longest greeting or farewell an owner may set.
+pub const MAX_MESSAGE_LENGTH: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryMessage {
+    Greeting,
+    Farewell,
+}
+
+/// Where claim greetings and farewells are shown to the player.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageDisplay {
+    #[default]
+    Chat,
+    Title,
+    ActionBar,
+}
+
+impl MessageDisplay {
+    pub fn send(self, player: &Player, text: &str) {
+        match self {
+            MessageDisplay::Chat => player.send_message(text),
+            MessageDisplay::Title => player.send_title(text, ""),
+            MessageDisplay::ActionBar => player.send_action_bar(text),
+        }
+    }
+}
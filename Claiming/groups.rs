@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use feather_server::command::{Command, CommandContext, CommandSender};
+use feather_server::player::Player;
+
+use crate::args::{ArgKind, Syntax};
+use crate::claims::ClaimKey;
+use crate::trust::TrustLevel;
+use crate::LandClaiming;
+
+/// Claims owned by a group store `@<group>` as their owner. Player names
+/// cannot contain `@`, so the two never collide.
+pub const OWNER_PREFIX: char = '@';
+
+const MAX_NAME_LENGTH: usize = 16;
+
+pub fn owner_name(group: &str) -> String {
+    format!("{}{}", OWNER_PREFIX, group)
+}
+
+/// The group behind a claim owner, if the owner is a group.
+pub fn group_of_owner(owner: &str) -> Option<&str> {
+    owner.strip_prefix(OWNER_PREFIX)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GroupRank {
+    Recruit,
+    Member,
+    Officer,
+    Leader,
+}
+
+impl GroupRank {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "recruit" => Some(GroupRank::Recruit),
+            "member" => Some(GroupRank::Member),
+            "officer" => Some(GroupRank::Officer),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GroupRank::Recruit => "recruit",
+            GroupRank::Member => "member",
+            GroupRank::Officer => "officer",
+            GroupRank::Leader => "leader",
+        }
+    }
+
+    /// The trust this rank carries in every claim the group owns.
+    pub fn trust(self) -> TrustLevel {
+        match self {
+            GroupRank::Recruit => TrustLevel::Access,
+            GroupRank::Member => TrustLevel::Build,
+            GroupRank::Officer | GroupRank::Leader => TrustLevel::Manage,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Group {
+    pub name: String,
+    pub ranks: HashMap<String, GroupRank>,
+}
+
+/// Named groups such as towns or guilds that can own claims together.
+/// Names are unique ignoring case.
+#[derive(Default)]
+pub struct ClaimGroups {
+    groups: RwLock<HashMap<String, Group>>,
+}
+
+impl ClaimGroups {
+    pub fn is_valid_name(name: &str) -> bool {
+        (3..=MAX_NAME_LENGTH).contains(&name.len())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    pub fn create(&self, name: &str, leader: &str) -> bool {
+        let mut groups = self.groups.write().unwrap();
+        let id = name.to_lowercase();
+
+        if groups.contains_key(&id) {
+            return false;
+        }
+        let mut ranks = HashMap::new();
+        ranks.insert(leader.to_owned(), GroupRank::Leader);
+        groups.insert(
+            id,
+            Group {
+                name: name.to_owned(),
+                ranks,
+            },
+        );
+        true
+    }
+
+    /// Every group's name, as it was written when created.
+    pub fn names(&self) -> Vec<String> {
+        self.groups.read().unwrap().values().map(|group| group.name.clone()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Group> {
+        self.groups.read().unwrap().get(&name.to_lowercase()).cloned()
+    }
+
+    pub fn rank_of(&self, name: &str, player: &str) -> Option<GroupRank> {
+        self.groups
+            .read()
+            .unwrap()
+            .get(&name.to_lowercase())
+            .and_then(|group| group.ranks.get(player).copied())
+    }
+
+    pub fn set_rank(&self, name: &str, player: &str, rank: GroupRank) {
+        if let Some(group) = self.groups.write().unwrap().get_mut(&name.to_lowercase()) {
+            group.ranks.insert(player.to_owned(), rank);
+        }
+    }
+
+    pub fn remove_member(&self, name: &str, player: &str) -> bool {
+        self.groups
+            .write()
+            .unwrap()
+            .get_mut(&name.to_lowercase())
+            .map_or(false, |group| group.ranks.remove(player).is_some())
+    }
+
+    pub fn disband(&self, name: &str) -> Option<Group> {
+        self.groups.write().unwrap().remove(&name.to_lowercase())
+    }
+}
+
+pub struct ClaimGroupCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl ClaimGroupCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+
+    fn usage(&self, player: &Player, usage: &str) {
+        self.plugin.tell(player, "general.usage", &[("usage", &usage)]);
+    }
+
+    /// The group called `name` along with `player`'s rank in it, telling the
+    /// player when they do not hold at least `needed`.
+    fn check_rank(
+        &self,
+        player: &Player,
+        name: &str,
+        needed: GroupRank,
+    ) -> Option<(Group, GroupRank)> {
+        let group = match self.plugin.groups.get(name) {
+            Some(group) => group,
+            None => {
+                self.plugin.tell(player, "group.unknown", &[("group", &name)]);
+                return None;
+            }
+        };
+
+        match group.ranks.get(player.name()).copied() {
+            Some(rank) if rank >= needed => Some((group, rank)),
+            _ => {
+                self.plugin.tell(player, "group.rank_required", &[("rank", &needed.name())]);
+                None
+            }
+        }
+    }
+
+    fn create(&self, player: &Player, name: Option<&String>) {
+        let name = match name {
+            Some(name) => name,
+            None => return self.usage(player, "/claimgroup create <name>"),
+        };
+
+        if !ClaimGroups::is_valid_name(name) {
+            return self.plugin.tell(player, "group.invalid_name", &[("max", &MAX_NAME_LENGTH)]);
+        }
+        if self.plugin.groups.create(name, player.name()) {
+            self.plugin.tell(player, "group.created", &[("group", name)]);
+        } else {
+            self.plugin.tell(player, "group.name_taken", &[("group", name)]);
+        }
+    }
+
+    fn add(&self, player: &Player, args: &[String]) {
+        let (name, target) = match (args.first(), args.get(1)) {
+            (Some(name), Some(target)) => (name, target),
+            _ => {
+                return self
+                    .usage(player, "/claimgroup add <group> <player> [recruit|member|officer]")
+            }
+        };
+        let rank = match args.get(2) {
+            Some(rank) => match GroupRank::parse(rank) {
+                Some(rank) => rank,
+                None => {
+                    return self
+                        .usage(player, "/claimgroup add <group> <player> [recruit|member|officer]")
+                }
+            },
+            None => GroupRank::Member,
+        };
+
+        let (group, own_rank) = match self.check_rank(player, name, GroupRank::Officer) {
+            Some(found) => found,
+            None => return,
+        };
+        let current = group.ranks.get(target.as_str()).copied();
+        if rank >= own_rank || current.map_or(false, |current| current >= own_rank) {
+            return self.plugin.tell(
+                player,
+                "group.rank_required",
+                &[("rank", &GroupRank::Leader.name())],
+            );
+        }
+
+        self.plugin.groups.set_rank(name, target, rank);
+        self.plugin.tell(
+            player,
+            "group.member_set",
+            &[("player", target), ("group", &group.name), ("rank", &rank.name())],
+        );
+    }
+
+    fn remove(&self, player: &Player, args: &[String]) {
+        let (name, target) = match (args.first(), args.get(1)) {
+            (Some(name), Some(target)) => (name, target),
+            _ => return self.usage(player, "/claimgroup remove <group> <player>"),
+        };
+
+        let leaving = target == player.name();
+        let needed = if leaving { GroupRank::Recruit } else { GroupRank::Officer };
+        let (group, own_rank) = match self.check_rank(player, name, needed) {
+            Some(found) => found,
+            None => return,
+        };
+
+        let target_rank = match group.ranks.get(target.as_str()).copied() {
+            Some(rank) => rank,
+            None => {
+                return self.plugin.tell(
+                    player,
+                    "group.not_member",
+                    &[("player", target), ("group", &group.name)],
+                )
+            }
+        };
+        if target_rank == GroupRank::Leader {
+            return self.plugin.tell(player, "group.leader_cannot_leave", &[]);
+        }
+        if !leaving && target_rank >= own_rank {
+            return self.plugin.tell(
+                player,
+                "group.rank_required",
+                &[("rank", &GroupRank::Leader.name())],
+            );
+        }
+
+        self.plugin.groups.remove_member(name, target);
+        self.plugin.tell(
+            player,
+            "group.member_removed",
+            &[("player", target), ("group", &group.name)],
+        );
+    }
+
+    /// Hands the claim `player` is standing in over to the group.
+    fn claim(&self, player: &Player, name: Option<&String>) {
+        let name = match name {
+            Some(name) => name,
+            None => return self.usage(player, "/claimgroup claim <group>"),
+        };
+        let (group, _) = match self.check_rank(player, name, GroupRank::Officer) {
+            Some(found) => found,
+            None => return,
+        };
+
+        let key = ClaimKey::of(player);
+        if !self.plugin.check_owner(player, key) {
+            return;
+        }
+
+        self.plugin.transfer_claim(key, &owner_name(&group.name));
+        self.plugin.tell(player, "group.claim_given", &[("group", &group.name)]);
+    }
+
+    fn info(&self, player: &Player, name: Option<&String>) {
+        let name = match name {
+            Some(name) => name,
+            None => return self.usage(player, "/claimgroup info <group>"),
+        };
+        let group = match self.plugin.groups.get(name) {
+            Some(group) => group,
+            None => return self.plugin.tell(player, "group.unknown", &[("group", name)]),
+        };
+
+        let chunks = self.plugin.claims_of(&owner_name(&group.name)).len();
+        self.plugin.tell(
+            player,
+            "group.info_header",
+            &[("group", &group.name), ("members", &group.ranks.len()), ("chunks", &chunks)],
+        );
+
+        let mut members: Vec<(&String, &GroupRank)> = group.ranks.iter().collect();
+        members.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (member, rank) in members {
+            self.plugin.tell(
+                player,
+                "group.info_entry",
+                &[("player", member), ("rank", &rank.name())],
+            );
+        }
+    }
+
+    /// Removes the group. Its claims go back to the leader.
+    fn disband(&self, player: &Player, name: Option<&String>) {
+        let name = match name {
+            Some(name) => name,
+            None => return self.usage(player, "/claimgroup disband <group>"),
+        };
+        let (group, _) = match self.check_rank(player, name, GroupRank::Leader) {
+            Some(found) => found,
+            None => return,
+        };
+
+        let owner = owner_name(&group.name);
+        self.plugin.load_claims_of(&owner);
+        for claim in self.plugin.claims.owned_by(&owner) {
+            if let Some(key) = claim.keys().next() {
+                self.plugin.transfer_claim(key, player.name());
+            }
+        }
+        self.plugin.groups.disband(name);
+        self.plugin.tell(player, "group.disbanded", &[("group", &group.name)]);
+    }
+}
+
+impl Command for ClaimGroupCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        match args.first().map(String::as_str) {
+            Some("create") => self.create(player, args.get(1)),
+            Some("add") => self.add(player, &args[1..]),
+            Some("remove") => self.remove(player, &args[1..]),
+            Some("claim") => self.claim(player, args.get(1)),
+            Some("info") => self.info(player, args.get(1)),
+            Some("disband") => self.disband(player, args.get(1)),
+            _ => self.usage(player, &self.get_usage()),
+        }
+    }
+
+    fn tab_complete(
+        &self,
+        ctx: &mut CommandContext,
+        sender: &dyn CommandSender,
+        args: Vec<String>,
+    ) -> Vec<String> {
+        const SYNTAX: Syntax = Syntax::Subcommands(&[
+            ("create", &[ArgKind::Text]),
+            (
+                "add",
+                &[
+                    ArgKind::Group,
+                    ArgKind::Player,
+                    ArgKind::Choice(&["recruit", "member", "officer"]),
+                ],
+            ),
+            ("remove", &[ArgKind::Group, ArgKind::Player]),
+            ("claim", &[ArgKind::Group]),
+            ("info", &[ArgKind::Group]),
+            ("disband", &[ArgKind::Group]),
+        ]);
+        self.plugin.complete(&ctx.game, sender, &SYNTAX, &args)
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claimgroup")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claimgroup <create|add|remove|claim|info|disband> <group> [player] [rank]")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Creates and manages groups that own claims together.")
+    }
+}
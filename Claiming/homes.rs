@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use feather_core::Position;
+
+/// Longest name an owner may give a claim home.
+pub const MAX_NAME_LENGTH: usize = 16;
+
+/// How far a player may drift during the warm-up before the teleport is
+/// called off, in blocks.
+const MAX_WARMUP_DRIFT: f64 = 0.5;
+
+/// Where `/claim home` takes players. Always inside the claim it belongs to.
+#[derive(Clone)]
+pub struct ClaimHome {
+    pub position: Position,
+    pub name: Option<String>,
+}
+
+struct PendingTeleport {
+    target: Position,
+    started_at: Position,
+    due: Instant,
+    visiting: Option<String>,
+}
+
+/// A teleport whose warm-up is over.
+pub struct DueTeleport {
+    pub player: String,
+    pub target: Position,
+    /// The owner whose claim is being visited, or `None` for a home.
+    pub visiting: Option<String>,
+}
+
+/// Home and visit teleports waiting out their warm-up, and when each player
+/// last used one. Both share the same cooldown.
+#[derive(Default)]
+pub struct HomeTeleports {
+    pending: Mutex<HashMap<String, PendingTeleport>>,
+    last_used: Mutex<HashMap<String, Instant>>,
+}
+
+impl HomeTeleports {
+    /// Time left before `player` may use a home again.
+    pub fn cooldown_left(&self, player: &str, cooldown: Duration) -> Option<Duration> {
+        let last_used = *self.last_used.lock().unwrap().get(player)?;
+        cooldown.checked_sub(last_used.elapsed()).filter(|left| !left.is_zero())
+    }
+
+    pub fn record_use(&self, player: &str) {
+        self.last_used.lock().unwrap().insert(player.to_owned(), Instant::now());
+    }
+
+    /// Queues a teleport to `target` once `warmup` has passed. A newer
+    /// request replaces an older one.
+    pub fn start(
+        &self,
+        player: &str,
+        target: Position,
+        from: Position,
+        warmup: Duration,
+        visiting: Option<String>,
+    ) {
+        let pending = PendingTeleport {
+            target,
+            started_at: from,
+            due: Instant::now() + warmup,
+            visiting,
+        };
+        self.pending.lock().unwrap().insert(player.to_owned(), pending);
+    }
+
+    /// Drops `player`'s pending teleport if they have moved away from where
+    /// they asked for it, and returns whether it did.
+    pub fn cancel_if_moved(&self, player: &str, position: Position) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+
+        let moved = pending.get(player).map_or(false, |teleport| {
+            let dx = position.x - teleport.started_at.x;
+            let dy = position.y - teleport.started_at.y;
+            let dz = position.z - teleport.started_at.z;
+            dx * dx + dy * dy + dz * dz > MAX_WARMUP_DRIFT * MAX_WARMUP_DRIFT
+        });
+        if moved {
+            pending.remove(player);
+        }
+        moved
+    }
+
+    /// Removes and returns every teleport whose warm-up is over.
+    pub fn take_due(&self) -> Vec<DueTeleport> {
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+
+        let due: Vec<String> = pending
+            .iter()
+            .filter(|(_, teleport)| teleport.due <= now)
+            .map(|(player, _)| player.clone())
+            .collect();
+        due.into_iter()
+            .filter_map(|player| {
+                let teleport = pending.remove(&player)?;
+                Some(DueTeleport {
+                    player,
+                    target: teleport.target,
+                    visiting: teleport.visiting,
+                })
+            })
+            .collect()
+    }
+
+    pub fn forget(&self, player: &str) {
+        self.pending.lock().unwrap().remove(player);
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use feather_server::player::Player;
+use serde::Deserialize;
+
+/// Where `/claim hud` shows the claim a player is standing in.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HudDisplay {
+    /// One line above the hotbar, shown each time the claim changes.
+    #[default]
+    ActionBar,
+    /// A scoreboard sidebar that stays up until the next change.
+    Sidebar,
+}
+
+impl HudDisplay {
+    /// Shows `title` with `lines` below it. The action bar only has room
+    /// for `summary`.
+    pub fn show(self, player: &Player, title: &str, lines: &[String], summary: &str) {
+        match self {
+            HudDisplay::ActionBar => player.send_action_bar(summary),
+            HudDisplay::Sidebar => player.show_sidebar(title, lines),
+        }
+    }
+
+    pub fn clear(self, player: &Player) {
+        match self {
+            HudDisplay::ActionBar => player.send_action_bar(""),
+            HudDisplay::Sidebar => player.hide_sidebar(),
+        }
+    }
+}
+
+/// Players who turned the claim HUD on. It stays on for them when they log
+/// out and back in, until they turn it off.
+#[derive(Default)]
+pub struct ClaimHud {
+    enabled: Mutex<HashSet<String>>,
+}
+
+impl ClaimHud {
+    pub fn is_enabled(&self, player: &str) -> bool {
+        self.enabled.lock().unwrap().contains(player)
+    }
+
+    /// Flips the HUD for `player` and returns whether it is now on.
+    pub fn toggle(&self, player: &str) -> bool {
+        let mut enabled = self.enabled.lock().unwrap();
+
+        if enabled.remove(player) {
+            false
+        } else {
+            enabled.insert(player.to_owned());
+            true
+        }
+    }
+}
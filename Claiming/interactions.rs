@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::trust::TrustLevel;
+
+/// Something a trusted player may or may not do in a claim, depending on
+/// the claim's interaction matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interaction {
+    /// Buttons, levers and pressure plates.
+    Buttons,
+    /// Doors, trapdoors and fence gates.
+    Doors,
+    Containers,
+    Villagers,
+    Beds,
+    /// Picking crops by hand, such as berries.
+    Crops,
+}
+
+impl Interaction {
+    pub const ALL: [Interaction; 6] = [
+        Interaction::Buttons,
+        Interaction::Doors,
+        Interaction::Containers,
+        Interaction::Villagers,
+        Interaction::Beds,
+        Interaction::Crops,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|action| action.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Interaction::Buttons => "buttons",
+            Interaction::Doors => "doors",
+            Interaction::Containers => "containers",
+            Interaction::Villagers => "villagers",
+            Interaction::Beds => "beds",
+            Interaction::Crops => "crops",
+        }
+    }
+
+    /// The lowest trust allowed to do this in a claim that has not said
+    /// otherwise, following what each trust level has always meant.
+    pub fn default_level(self) -> TrustLevel {
+        match self {
+            Interaction::Buttons | Interaction::Doors | Interaction::Beds => TrustLevel::Access,
+            Interaction::Containers | Interaction::Villagers => TrustLevel::Container,
+            Interaction::Crops => TrustLevel::Build,
+        }
+    }
+
+    /// What using the block called `block` counts as, if it is anything the
+    /// matrix covers.
+    pub fn of_block(block: &str) -> Option<Self> {
+        const CONTAINERS: [&str; 12] = [
+            "chest",
+            "trapped_chest",
+            "barrel",
+            "furnace",
+            "blast_furnace",
+            "smoker",
+            "hopper",
+            "dropper",
+            "dispenser",
+            "brewing_stand",
+            "chiseled_bookshelf",
+            "lectern",
+        ];
+        const HAND_PICKED: [&str; 5] =
+            ["sweet_berry_bush", "cave_vines", "cave_vines_plant", "cocoa", "nether_wart"];
+
+        let is_door = block.ends_with("_door")
+            || block.ends_with("_trapdoor")
+            || block.ends_with("_fence_gate");
+        let is_button =
+            block.ends_with("_button") || block.ends_with("_pressure_plate") || block == "lever";
+
+        if is_door {
+            Some(Interaction::Doors)
+        } else if is_button {
+            Some(Interaction::Buttons)
+        } else if block.ends_with("_bed") {
+            Some(Interaction::Beds)
+        } else if block.ends_with("shulker_box") || CONTAINERS.contains(&block) {
+            Some(Interaction::Containers)
+        } else if HAND_PICKED.contains(&block) {
+            Some(Interaction::Crops)
+        } else {
+            None
+        }
+    }
+}
+
+/// Blocks that are harvested by breaking them.
+const CROPS: [&str; 16] = [
+    "wheat",
+    "carrots",
+    "potatoes",
+    "beetroots",
+    "melon",
+    "pumpkin",
+    "melon_stem",
+    "pumpkin_stem",
+    "sugar_cane",
+    "cocoa",
+    "nether_wart",
+    "sweet_berry_bush",
+    "cave_vines",
+    "cave_vines_plant",
+    "torchflower_crop",
+    "pitcher_crop",
+];
+
+pub const FARMLAND: &str = "farmland";
+
+/// Items that put blocks or entities into the world when used, but do not
+/// go through block placing: spawn eggs, fire, and vehicles and stands.
+pub fn places_with_item(item: &str) -> bool {
+    const PLACING: [&str; 6] =
+        ["flint_and_steel", "fire_charge", "armor_stand", "end_crystal", "bone_meal", "minecart"];
+
+    item.ends_with("_spawn_egg")
+        || item.ends_with("_boat")
+        || item.ends_with("_minecart")
+        || PLACING.contains(&item)
+}
+
+pub fn is_crop(block: &str) -> bool {
+    CROPS.contains(&block)
+}
+
+/// A claim's changes to which trust levels may do what. Anything not set
+/// follows `Interaction::default_level`; managers may always do everything.
+pub type InteractionMatrix = HashMap<TrustLevel, HashMap<Interaction, bool>>;
+
+/// The trust levels the matrix can change.
+pub const LEVELS: [TrustLevel; 3] = [TrustLevel::Access, TrustLevel::Container, TrustLevel::Build];
+
+pub fn allows(matrix: &InteractionMatrix, level: TrustLevel, action: Interaction) -> bool {
+    if level == TrustLevel::Manage {
+        return true;
+    }
+    matrix
+        .get(&level)
+        .and_then(|row| row.get(&action))
+        .copied()
+        .unwrap_or(level >= action.default_level())
+}
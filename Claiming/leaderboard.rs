@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use feather_server::command::{Command, CommandContext, CommandSender};
+
+use crate::args::ArgReader;
+use crate::claims::ADMIN_OWNER;
+use crate::residency::IndexedClaim;
+use crate::LandClaiming;
+
+/// Owners listed on each page of `/claimtop`.
+const PAGE_SIZE: usize = 10;
+
+/// How much land one player or group holds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Standing {
+    pub owner: String,
+    pub chunks: usize,
+    pub claims: usize,
+}
+
+/// Owners by claimed chunks, most first, ties by name. Admin claims are
+/// left out.
+pub fn rank(listed: &[IndexedClaim]) -> Vec<Standing> {
+    let mut totals: HashMap<&str, (usize, usize)> = HashMap::new();
+    for claim in listed.iter().filter(|claim| claim.owner != ADMIN_OWNER) {
+        let (chunks, claims) = totals.entry(&claim.owner).or_default();
+        *chunks += claim.chunk_count;
+        *claims += 1;
+    }
+
+    let mut standings: Vec<Standing> = totals
+        .into_iter()
+        .map(|(owner, (chunks, claims))| Standing { owner: owner.to_owned(), chunks, claims })
+        .collect();
+    standings.sort_by(|a, b| b.chunks.cmp(&a.chunks).then_with(|| a.owner.cmp(&b.owner)));
+    standings
+}
+
+pub struct ClaimTopCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl ClaimTopCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for ClaimTopCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let page = if args.is_empty() { Some(1) } else { ArgReader::new(&args).count::<usize>() };
+        let page = match page {
+            Some(page) => page,
+            None => {
+                return self.plugin.tell_sender(
+                    sender,
+                    "general.usage",
+                    &[("usage", &self.get_usage())],
+                )
+            }
+        };
+
+        let standings = rank(&self.plugin.all_listed_claims());
+        if standings.is_empty() {
+            return self.plugin.tell_sender(sender, "top.empty", &[]);
+        }
+
+        let pages = (standings.len() + PAGE_SIZE - 1) / PAGE_SIZE;
+        let page = page.min(pages);
+        self.plugin.tell_sender(sender, "top.header", &[("page", &page), ("pages", &pages)]);
+        let shown = standings.iter().enumerate().skip((page - 1) * PAGE_SIZE).take(PAGE_SIZE);
+        for (index, standing) in shown {
+            self.plugin.tell_sender(
+                sender,
+                "top.entry",
+                &[
+                    ("rank", &(index + 1)),
+                    ("owner", &standing.owner),
+                    ("chunks", &standing.chunks),
+                    ("claims", &standing.claims),
+                ],
+            );
+        }
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claimtop")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claimtop [page]")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Lists the players and groups that have claimed the most land.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use feather_core::world::ChunkPosition;
+    use feather_server::world::WorldId;
+
+    use super::*;
+    use crate::claims::{ClaimKey, ClaimManager};
+
+    #[test]
+    fn owners_rank_by_chunks_across_their_claims() {
+        let claims = ClaimManager::default();
+        let key = |x, z| ClaimKey::new(WorldId::default(), ChunkPosition::new(x, z));
+        for (x, owner) in [(0, "bob"), (1, "bob"), (5, "bob"), (10, "alice"), (11, "alice")] {
+            claims.insert_if_vacant(key(x, 0), owner);
+        }
+        claims.insert_if_vacant(key(20, 0), ADMIN_OWNER);
+        let listed: Vec<IndexedClaim> = claims.all().iter().map(IndexedClaim::of).collect();
+
+        let standings = rank(&listed);
+        assert_eq!(standings.len(), 2);
+        assert_eq!((standings[0].owner.as_str(), standings[0].chunks), ("bob", 3));
+        assert_eq!(standings[0].claims, 2);
+        assert_eq!((standings[1].owner.as_str(), standings[1].chunks), ("alice", 2));
+    }
+}
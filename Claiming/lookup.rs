@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::claims::{Claim, ClaimKey, ClaimManager};
+
+struct CachedLookup {
+    key: ClaimKey,
+    revision: u64,
+    claim: Option<Arc<Claim>>,
+}
+
+/// The claim each online player last looked up, for the chunk they looked
+/// it up in. Move and interact events mostly happen in the chunk a player
+/// was already in, so they are answered from here without locking the
+/// claim store. Any change to any claim makes every cached lookup stale.
+#[derive(Default)]
+pub struct LookupCache {
+    last: Mutex<HashMap<String, CachedLookup>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl LookupCache {
+    /// The claim covering `key`, as `player` last saw it if neither their
+    /// chunk nor any claim has changed since.
+    pub fn claim_at(
+        &self,
+        player: &str,
+        key: ClaimKey,
+        claims: &ClaimManager,
+    ) -> Option<Arc<Claim>> {
+        let mut last = self.last.lock().unwrap();
+
+        // Read the revision before the claim: a change landing in between
+        // leaves the entry marked stale rather than wrongly fresh.
+        let revision = claims.revision();
+        if let Some(cached) = last.get(player) {
+            if cached.key == key && cached.revision == revision {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return cached.claim.clone();
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let claim = claims.get(key).map(Arc::new);
+        last.insert(
+            player.to_owned(),
+            CachedLookup {
+                key,
+                revision,
+                claim: claim.clone(),
+            },
+        );
+        claim
+    }
+
+    pub fn forget(&self, player: &str) {
+        self.last.lock().unwrap().remove(player);
+    }
+
+    pub fn hits_and_misses(&self) -> (u64, u64) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+    use feather_core::world::ChunkPosition;
+    use feather_server::world::WorldId;
+
+    use super::*;
+
+    fn key(x: i32, z: i32) -> ClaimKey {
+        ClaimKey::new(WorldId::default(), ChunkPosition::new(x, z))
+    }
+
+    fn manager_with_claims(side: i32) -> ClaimManager {
+        let claims = ClaimManager::default();
+        for x in 0..side {
+            for z in 0..side {
+                claims.insert_if_vacant(key(x, z), &format!("owner{}", (x + z) % 64));
+            }
+        }
+        claims
+    }
+
+    #[test]
+    fn repeats_lookups_in_the_same_chunk() {
+        let claims = manager_with_claims(4);
+        let cache = LookupCache::default();
+
+        let first = cache.claim_at("alice", key(1, 1), &claims).unwrap();
+        let second = cache.claim_at("alice", key(1, 1), &claims).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(cache.claim_at("alice", key(10, 10), &claims).is_none());
+        assert_eq!(cache.hits_and_misses(), (1, 2));
+    }
+
+    #[test]
+    fn claim_changes_invalidate_lookups() {
+        let claims = manager_with_claims(4);
+        let cache = LookupCache::default();
+
+        assert!(cache.claim_at("alice", key(1, 1), &claims).is_some());
+        claims.remove(key(1, 1));
+        assert!(cache.claim_at("alice", key(1, 1), &claims).is_none());
+
+        assert!(cache.claim_at("alice", key(10, 10), &claims).is_none());
+        claims.insert_if_vacant(key(10, 10), "bob");
+        let claim = cache.claim_at("alice", key(10, 10), &claims).unwrap();
+        assert_eq!(claim.owner, "bob");
+
+        claims.update(key(10, 10), |claim| claim.greeting = Some(String::from("hi")));
+        let claim = cache.claim_at("alice", key(10, 10), &claims).unwrap();
+        assert_eq!(claim.greeting.as_deref(), Some("hi"));
+    }
+
+    /// Compares looking up the claim a player stands in straight from the
+    /// store with going through the cache, for a player who stays in one
+    /// chunk; the cache has to be at least as fast. Timing is only
+    /// meaningful in release builds, so run it with
+    /// `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn bench_same_chunk_lookups() {
+        const LOOKUPS: u32 = 1_000_000;
+        let claims = manager_with_claims(128);
+        let cache = LookupCache::default();
+        let here = key(64, 64);
+
+        let started = Instant::now();
+        for _ in 0..LOOKUPS {
+            assert!(claims.get(here).is_some());
+        }
+        let direct = started.elapsed();
+
+        let started = Instant::now();
+        for _ in 0..LOOKUPS {
+            assert!(cache.claim_at("alice", here, &claims).is_some());
+        }
+        let cached = started.elapsed();
+
+        assert!(
+            cached <= direct,
+            "{} cached lookups took {:?}, {:?} from the store",
+            LOOKUPS,
+            cached,
+            direct
+        );
+    }
+}
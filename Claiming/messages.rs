@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use crate::config::{self, ConfigError};
+
+pub const LANG_DIR: &str = "plugins/LandClaiming/lang";
+
+const DEFAULT_LOCALE: &str = "en_us";
+
+/// The built-in English messages. Also written to `LANG_DIR` on first start
+/// as a starting point for edits and translations.
+const BUNDLED_EN_US: &str = include_str!("lang/en_us.toml");
+
+/// A placeholder name and the value it is replaced with.
+pub type Arg<'a> = (&'a str, &'a dyn Display);
+
+type Table = HashMap<String, String>;
+
+/// Player-facing text, looked up by key such as `claim.created` from one
+/// file per locale. Lookups fall back to the configured locale, then to the
+/// bundled English text, so a partial translation never shows a blank.
+pub struct Messages {
+    dir: PathBuf,
+    bundled: Table,
+    locales: RwLock<HashMap<String, Table>>,
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        let bundled = toml::from_str(BUNDLED_EN_US)
+            .map(flatten)
+            .expect("bundled lang/en_us.toml is valid");
+
+        Self {
+            dir: PathBuf::from(LANG_DIR),
+            bundled,
+            locales: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl Messages {
+    /// Reads every `<locale>.toml` in the lang directory, replacing all
+    /// previously loaded translations.
+    pub fn load(&self) -> Result<(), ConfigError> {
+        let default_path = self.dir.join(format!("{}.toml", DEFAULT_LOCALE));
+        let mut locales = HashMap::new();
+        locales.insert(
+            DEFAULT_LOCALE.to_owned(),
+            flatten(config::read_or_create(&default_path, BUNDLED_EN_US)?),
+        );
+
+        let entries =
+            fs::read_dir(&self.dir).map_err(|err| ConfigError::Io(self.dir.clone(), err))?;
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            let locale = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(locale) if path.extension().map_or(false, |ext| ext == "toml") => {
+                    locale.to_lowercase()
+                }
+                _ => continue,
+            };
+
+            if locale != DEFAULT_LOCALE {
+                locales.insert(locale, flatten(config::read_or_create(&path, "")?));
+            }
+        }
+
+        *self.locales.write().unwrap() = locales;
+        Ok(())
+    }
+
+    /// Looks up `key` for `locale` and fills in `{name}` placeholders.
+    pub fn render(&self, locale: &str, fallback_locale: &str, key: &str, args: &[Arg]) -> String {
+        let locales = self.locales.read().unwrap();
+
+        let template = [locale, fallback_locale]
+            .iter()
+            .filter_map(|locale| locales.get(&locale.to_lowercase()))
+            .find_map(|table| table.get(key))
+            .or_else(|| self.bundled.get(key))
+            .map_or(key, String::as_str);
+
+        args.iter().fold(template.to_owned(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), &value.to_string())
+        })
+    }
+}
+
+/// Turns nested TOML tables into dotted keys, so `[claim] created = ".."`
+/// becomes `claim.created`.
+fn flatten(value: toml::Value) -> Table {
+    fn walk(prefix: &str, value: toml::Value, table: &mut Table) {
+        match value {
+            toml::Value::Table(entries) => {
+                for (name, value) in entries {
+                    let key = if prefix.is_empty() {
+                        name
+                    } else {
+                        format!("{}.{}", prefix, name)
+                    };
+                    walk(&key, value, table);
+                }
+            }
+            toml::Value::String(text) => {
+                table.insert(prefix.to_owned(), text);
+            }
+            _ => {}
+        }
+    }
+
+    let mut table = Table::new();
+    walk("", value, &mut table);
+    table
+}
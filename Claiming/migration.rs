@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+
+use crate::claims;
+use crate::trust::TrustLevel;
+
+/// One claim read from another plugin's data, in block coordinates.
+pub struct ImportedClaim {
+    pub world: String,
+    pub min_x: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_z: i32,
+    pub owner: String,
+    pub trusted: Vec<(String, TrustLevel)>,
+}
+
+impl ImportedClaim {
+    /// Chunk coordinates of every chunk the claim touches.
+    pub fn chunks(&self) -> impl Iterator<Item = (i32, i32)> {
+        let min = claims::block_chunk(self.min_x, self.min_z);
+        let max = claims::block_chunk(self.max_x, self.max_z);
+        (min.x..=max.x).flat_map(move |x| (min.z..=max.z).map(move |z| (x, z)))
+    }
+}
+
+/// Where the server keeps the UUID to name cache.
+pub const USER_CACHE_PATH: &str = "usercache.json";
+
+/// Counts of what an import did and what it had to leave out.
+#[derive(Default)]
+pub struct ImportReport {
+    pub claims: usize,
+    pub chunks: usize,
+    pub taken_chunks: usize,
+    /// Chunks refused by the claim rules: the border, banned regions or
+    /// the buffer between owners.
+    pub rejected_chunks: usize,
+    pub unknown_worlds: usize,
+    pub unreadable_files: usize,
+    pub admin_claims: usize,
+    pub subdivisions: usize,
+    pub unknown_owners: usize,
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::Io(path, err) => {
+                write!(f, "could not read {}: {}", path.display(), err)
+            }
+            MigrationError::Parse(path, err) => write!(f, "invalid {}: {}", path.display(), err),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UserCacheEntry {
+    name: String,
+    uuid: String,
+}
+
+/// Maps UUIDs to names using the server's `usercache.json`. Other plugins
+/// key players by UUID while claims here are keyed by name. A cache that
+/// cannot be read fails the import rather than leaving every owner unknown.
+pub fn read_user_cache(path: &Path) -> Result<HashMap<String, String>, MigrationError> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| MigrationError::Io(path.to_owned(), err))?;
+    let entries: Vec<UserCacheEntry> = serde_json::from_str(&contents)
+        .map_err(|err| MigrationError::Parse(path.to_owned(), err))?;
+
+    Ok(entries.into_iter().map(|entry| (entry.uuid.to_lowercase(), entry.name)).collect())
+}
+
+#[derive(Deserialize)]
+struct GriefPreventionClaim {
+    #[serde(rename = "Lesser Boundary Corner")]
+    lesser_corner: String,
+    #[serde(rename = "Greater Boundary Corner")]
+    greater_corner: String,
+    #[serde(rename = "Owner", default)]
+    owner: String,
+    #[serde(rename = "Builders", default)]
+    builders: Vec<String>,
+    #[serde(rename = "Containers", default)]
+    containers: Vec<String>,
+    #[serde(rename = "Accessors", default)]
+    accessors: Vec<String>,
+    #[serde(rename = "Managers", default)]
+    managers: Vec<String>,
+    #[serde(rename = "Parent Claim ID", default = "no_parent")]
+    parent: i64,
+}
+
+fn no_parent() -> i64 {
+    -1
+}
+
+/// Parses a `world;x;y;z` corner.
+fn parse_corner(corner: &str) -> Option<(String, i32, i32)> {
+    let mut parts = corner.split(';');
+    let world = parts.next()?.to_owned();
+    let x = parts.next()?.trim().parse().ok()?;
+    let _y = parts.next()?;
+    let z = parts.next()?.trim().parse().ok()?;
+    Some((world, x, z))
+}
+
+/// Reads GriefPrevention's flat-file claims from `<data_dir>/ClaimData`.
+/// Servers on GriefPrevention's database storage can switch it back to
+/// files to export them. Administrative claims and subdivisions are left
+/// out; subdivisions sit inside their parent claim anyway.
+pub fn read_griefprevention(
+    data_dir: &Path,
+    names: &HashMap<String, String>,
+) -> Result<(Vec<ImportedClaim>, ImportReport), MigrationError> {
+    let claim_dir = data_dir.join("ClaimData");
+    let entries =
+        fs::read_dir(&claim_dir).map_err(|err| MigrationError::Io(claim_dir.clone(), err))?;
+    let name_of = |uuid: &str| names.get(&uuid.to_lowercase()).cloned();
+
+    let mut claims = Vec::new();
+    let mut report = ImportReport::default();
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.extension().map_or(true, |ext| ext != "yml") {
+            continue;
+        }
+
+        let parsed = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_yaml::from_str::<GriefPreventionClaim>(&contents).ok());
+        let claim = match parsed {
+            Some(claim) => claim,
+            None => {
+                report.unreadable_files += 1;
+                continue;
+            }
+        };
+
+        if claim.parent != -1 {
+            report.subdivisions += 1;
+            continue;
+        }
+        if claim.owner.is_empty() {
+            report.admin_claims += 1;
+            continue;
+        }
+        let owner = match name_of(&claim.owner) {
+            Some(owner) => owner,
+            None => {
+                report.unknown_owners += 1;
+                continue;
+            }
+        };
+
+        let corners = parse_corner(&claim.lesser_corner).zip(parse_corner(&claim.greater_corner));
+        let ((world, x1, z1), (_, x2, z2)) = match corners {
+            Some(corners) => corners,
+            None => {
+                report.unreadable_files += 1;
+                continue;
+            }
+        };
+
+        let mut trusted = Vec::new();
+        for (list, level) in [
+            (&claim.accessors, TrustLevel::Access),
+            (&claim.containers, TrustLevel::Container),
+            (&claim.builders, TrustLevel::Build),
+            (&claim.managers, TrustLevel::Manage),
+        ]
+        .iter()
+        {
+            trusted.extend(list.iter().filter_map(|uuid| name_of(uuid)).map(|name| (name, *level)));
+        }
+
+        claims.push(ImportedClaim {
+            world,
+            min_x: x1.min(x2),
+            min_z: z1.min(z2),
+            max_x: x1.max(x2),
+            max_z: z1.max(z2),
+            owner,
+            trusted,
+        });
+    }
+    Ok((claims, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_broken_user_cache_is_an_error() {
+        let path = std::env::temp_dir().join(format!("usercache-{}.json", std::process::id()));
+        fs::write(&path, r#"[{"name": "alice", "uuid": "ABC-1"}]"#).unwrap();
+        let names = read_user_cache(&path).unwrap();
+        assert_eq!(names.get("abc-1").map(String::as_str), Some("alice"));
+
+        fs::write(&path, "[{\"name\": ").unwrap();
+        assert!(matches!(read_user_cache(&path), Err(MigrationError::Parse(..))));
+        fs::remove_file(&path).unwrap();
+    }
+}
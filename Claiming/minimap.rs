@@ -0,0 +1,107 @@
+use std::sync::Arc;
+use feather_core::world::ChunkPosition;
+use feather_server::command::{Command, CommandContext, CommandSender};
+
+use crate::claims::{self, ClaimKey};
+use crate::LandClaiming;
+
+/// Chunks shown on each side of the player, across and down. Chat is
+/// wider than it is tall.
+const HALF_WIDTH: i32 = 10;
+const HALF_HEIGHT: i32 = 5;
+
+/// What a chunk on the map is to the player looking at it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Wilderness,
+    Own,
+    Trusted,
+    Other,
+    Admin,
+}
+
+impl Tile {
+    fn symbol(self) -> char {
+        match self {
+            Tile::Wilderness => '-',
+            Tile::Own => '#',
+            Tile::Trusted => '+',
+            Tile::Other => 'X',
+            Tile::Admin => 'A',
+        }
+    }
+}
+
+/// The map's rows, north at the top, with `@` on `center`.
+pub fn render(center: ChunkPosition, tile_at: impl Fn(ChunkPosition) -> Tile) -> Vec<String> {
+    (center.z - HALF_HEIGHT..=center.z + HALF_HEIGHT)
+        .map(|z| {
+            (center.x - HALF_WIDTH..=center.x + HALF_WIDTH)
+                .map(|x| match ChunkPosition::new(x, z) {
+                    chunk if chunk == center => '@',
+                    chunk => tile_at(chunk).symbol(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub struct ClaimMapCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl ClaimMapCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for ClaimMapCommand {
+    fn execute(&self, _ctx: &mut CommandContext, sender: &dyn CommandSender, _args: Vec<String>) {
+        let player = match sender.as_player() {
+            Some(player) => player,
+            None => return self.plugin.tell_sender(sender, "general.players_only", &[]),
+        };
+
+        let center = ClaimKey::of(player);
+        let rows = render(center.chunk, |chunk| {
+            let key = ClaimKey::new(center.world, chunk);
+            self.plugin
+                .claims
+                .read(key, |claim| {
+                    if claim.owner == claims::ADMIN_OWNER {
+                        Tile::Admin
+                    } else if self.plugin.acts_for_owner(claim, player.name()) {
+                        Tile::Own
+                    } else if self.plugin.trust_level(claim, player.name()).is_some() {
+                        Tile::Trusted
+                    } else {
+                        Tile::Other
+                    }
+                })
+                .unwrap_or(Tile::Wilderness)
+        });
+
+        self.plugin.tell(
+            player,
+            "map.header",
+            &[("x", &center.chunk.x), ("z", &center.chunk.z)],
+        );
+        for row in &rows {
+            self.plugin.tell(player, "map.row", &[("row", row)]);
+        }
+        self.plugin.tell(player, "map.legend", &[]);
+    }
+
+    fn get_name(&self) -> String {
+        String::from("claimmap")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/claimmap")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Shows a map of the claims around you.")
+    }
+}
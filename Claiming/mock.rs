@@ -0,0 +1,70 @@
+use std::sync::Mutex;
+use feather_core::Position;
+use feather_server::world::WorldId;
+
+use crate::api::{ClaimEvent, EventSink};
+use crate::claims::CHUNK_SIZE;
+use crate::player::PlayerRef;
+
+/// A player that only exists for a test.
+#[derive(Clone, Debug)]
+pub struct MockPlayer {
+    pub name: String,
+    pub world: WorldId,
+    pub position: Position,
+}
+
+impl MockPlayer {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            world: WorldId::default(),
+            position: Position::default(),
+        }
+    }
+
+    /// The same player, standing in the middle of chunk `x`, `z`.
+    pub fn in_chunk(mut self, x: i32, z: i32) -> Self {
+        let middle = |chunk: i32| f64::from(chunk * CHUNK_SIZE + CHUNK_SIZE / 2);
+        self.position = Position {
+            x: middle(x),
+            y: 64.0,
+            z: middle(z),
+            ..Position::default()
+        };
+        self
+    }
+}
+
+impl PlayerRef for MockPlayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn world(&self) -> WorldId {
+        self.world
+    }
+
+    fn position(&self) -> Position {
+        self.position
+    }
+}
+
+/// Keeps every event it is given, for tests to look at.
+#[derive(Default)]
+pub struct RecordingSink {
+    events: Mutex<Vec<ClaimEvent>>,
+}
+
+impl RecordingSink {
+    /// The events recorded since the last call.
+    pub fn take(&self) -> Vec<ClaimEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+impl EventSink for RecordingSink {
+    fn emit(&self, event: ClaimEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::Deserialize;
+use serde_json::json;
+
+use feather_core::world::ChunkPosition;
+
+use crate::claims::ClaimId;
+
+/// How long one webhook request may take before it is given up on.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most notices waiting for the webhook. More are dropped rather than
+/// piling up behind a slow endpoint.
+const WEBHOOK_QUEUE: usize = 100;
+
+/// How often chunks claimed or unclaimed are gathered into notices.
+pub const CHUNK_NOTICE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Claim activity that can be passed on to a webhook or to online staff.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoticeKind {
+    ClaimCreated,
+    ClaimDeleted,
+    TrustChanged,
+    InteractionDenied,
+    /// One player was stopped in the same claim again and again.
+    RepeatedDenials,
+}
+
+impl NoticeKind {
+    pub fn message_key(self) -> &'static str {
+        match self {
+            NoticeKind::ClaimCreated => "notify.claim_created",
+            NoticeKind::ClaimDeleted => "notify.claim_deleted",
+            NoticeKind::TrustChanged => "notify.trust_changed",
+            NoticeKind::InteractionDenied => "notify.interaction_denied",
+            NoticeKind::RepeatedDenials => "notify.repeated_denials",
+        }
+    }
+}
+
+/// Chunks one owner claimed or lost in one world since notices were last
+/// sent, reported as one notice at the first of them.
+pub struct ChunkNotice {
+    pub kind: NoticeKind,
+    pub owner: String,
+    pub world: String,
+    pub first: ChunkPosition,
+    pub count: usize,
+}
+
+/// Posts notices to a webhook and keeps the denial counts behind
+/// `RepeatedDenials`.
+#[derive(Default)]
+pub struct Notifier {
+    /// Feeds the thread doing the posting, started on the first notice so
+    /// a slow or unreachable endpoint never holds up the server.
+    webhook: Mutex<Option<SyncSender<(String, String)>>>,
+    /// Notices dropped since the webhook queue was last found full.
+    dropped: AtomicUsize,
+    chunks: Mutex<Vec<ChunkNotice>>,
+    /// When each player's run of denials in a claim started, and how long
+    /// it is so far.
+    denials: Mutex<HashMap<(ClaimId, String), (Instant, u32)>>,
+}
+
+impl Notifier {
+    /// Queues `content` to be posted to `url` as a Discord-style message.
+    pub fn post(&self, url: &str, content: String) {
+        let mut webhook = self.webhook.lock().unwrap();
+        let sender = webhook.get_or_insert_with(start_posting);
+        match sender.try_send((url.to_owned(), content)) {
+            Ok(()) => {
+                let dropped = self.dropped.swap(0, Ordering::Relaxed);
+                if dropped > 0 {
+                    log::warn!(
+                        "Dropped {} LandClaiming notification(s) while the webhook was behind",
+                        dropped
+                    );
+                }
+            }
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                log::warn!("The LandClaiming webhook thread has stopped; dropping a notification");
+                *webhook = None;
+            }
+        }
+    }
+
+    /// Counts `chunk` towards the `kind` notice for `owner` in `world`.
+    pub fn add_chunk(&self, kind: NoticeKind, owner: &str, world: String, chunk: ChunkPosition) {
+        let mut chunks = self.chunks.lock().unwrap();
+        let same = |notice: &&mut ChunkNotice| {
+            notice.kind == kind && notice.owner == owner && notice.world == world
+        };
+        match chunks.iter_mut().find(same) {
+            Some(notice) => notice.count += 1,
+            None => chunks.push(ChunkNotice {
+                kind,
+                owner: owner.to_owned(),
+                world,
+                first: chunk,
+                count: 1,
+            }),
+        }
+    }
+
+    pub fn take_chunk_notices(&self) -> Vec<ChunkNotice> {
+        std::mem::take(&mut *self.chunks.lock().unwrap())
+    }
+
+    /// Counts a denial of `player` in `claim`. Returns true when it makes
+    /// `threshold` denials within `window`; the count then starts over, so
+    /// someone who keeps going is reported once per `threshold`. A
+    /// threshold of 0 never reports.
+    pub fn record_denial(
+        &self,
+        claim: ClaimId,
+        player: &str,
+        threshold: u32,
+        window: Duration,
+    ) -> bool {
+        if threshold == 0 {
+            return false;
+        }
+        let now = Instant::now();
+        let mut denials = self.denials.lock().unwrap();
+        denials.retain(|_, (since, _)| now.duration_since(*since) < window);
+
+        let subject = (claim, player.to_owned());
+        let (_, count) = denials.entry(subject.clone()).or_insert((now, 0));
+        *count += 1;
+        if *count < threshold {
+            return false;
+        }
+        denials.remove(&subject);
+        true
+    }
+}
+
+fn start_posting() -> SyncSender<(String, String)> {
+    let (sender, receiver) = mpsc::sync_channel::<(String, String)>(WEBHOOK_QUEUE);
+    thread::spawn(move || {
+        for (url, content) in receiver {
+            // Player names and claim names end up in the content, so no
+            // mention in it may ping anyone.
+            let payload = json!({
+                "username": "LandClaiming",
+                "content": content,
+                "allowed_mentions": { "parse": [] },
+            });
+            if let Err(err) = ureq::post(&url).timeout(WEBHOOK_TIMEOUT).send_json(payload) {
+                log::warn!("Could not post a LandClaiming notification: {}", err);
+            }
+        }
+    });
+    sender
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_denials_are_reported_once_per_threshold() {
+        let notifier = Notifier::default();
+        let window = Duration::from_secs(60);
+        let deny = |player| notifier.record_denial(ClaimId(1), player, 3, window);
+
+        assert!(!deny("mallory"));
+        assert!(!deny("mallory"));
+        assert!(!deny("bob"));
+        assert!(deny("mallory"));
+        assert!(!deny("mallory"));
+        assert!(!notifier.record_denial(ClaimId(1), "bob", 0, window));
+    }
+
+    #[test]
+    fn chunks_are_gathered_per_owner_and_world() {
+        let notifier = Notifier::default();
+        let world = || String::from("world");
+        for x in 0..3 {
+            let chunk = ChunkPosition::new(x, 0);
+            notifier.add_chunk(NoticeKind::ClaimCreated, "alice", world(), chunk);
+        }
+        notifier.add_chunk(NoticeKind::ClaimDeleted, "alice", world(), ChunkPosition::new(9, 0));
+
+        let notices = notifier.take_chunk_notices();
+        assert_eq!(notices.len(), 2);
+        assert_eq!((notices[0].count, notices[0].first), (3, ChunkPosition::new(0, 0)));
+        assert!(notifier.take_chunk_notices().is_empty());
+    }
+}
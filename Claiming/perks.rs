@@ -0,0 +1,28 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Players flying through `/claim fly`. Flight from anywhere else, such as
+/// creative mode, is not tracked and never taken away.
+#[derive(Default)]
+pub struct ClaimFlight {
+    flying: Mutex<HashSet<String>>,
+}
+
+impl ClaimFlight {
+    pub fn start(&self, player: &str) {
+        self.flying.lock().unwrap().insert(player.to_owned());
+    }
+
+    pub fn is_flying(&self, player: &str) -> bool {
+        self.flying.lock().unwrap().contains(player)
+    }
+
+    pub fn players(&self) -> Vec<String> {
+        self.flying.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Stops tracking `player`, and returns whether they were flying.
+    pub fn stop(&self, player: &str) -> bool {
+        self.flying.lock().unwrap().remove(player)
+    }
+}
@@ -0,0 +1,77 @@
+use feather_server::player::Player;
+
+use crate::config::LimitsConfig;
+
+pub const CLAIM: &str = "landclaiming.claim";
+pub const UNCLAIM: &str = "landclaiming.unclaim";
+pub const HOME: &str = "landclaiming.home";
+pub const VISIT: &str = "landclaiming.visit";
+pub const FLY: &str = "landclaiming.perks.fly";
+pub const KEEP_INVENTORY: &str = "landclaiming.perks.keepinventory";
+pub const ADMIN: &str = "landclaiming.admin";
+pub const ADMIN_BYPASS: &str = "landclaiming.admin.bypass";
+pub const RELOAD: &str = "landclaiming.admin.reload";
+pub const NOTIFY: &str = "landclaiming.admin.notify";
+pub const BUY_CLAIM_BLOCKS: &str = "landclaiming.claimblocks.buy";
+pub const SELL_CLAIM_BLOCKS: &str = "landclaiming.claimblocks.sell";
+pub const UNLIMITED_CLAIMS: &str = "landclaiming.limit.unlimited";
+
+/// Answers whether a player holds a permission node.
+///
+/// The default provider defers to Feather's own permission system; servers
+/// running a separate permissions plugin can inject their own.
+pub trait PermissionProvider: Send + Sync {
+    fn has_permission(&self, player: &Player, node: &str) -> bool;
+}
+
+pub struct FeatherPermissions;
+
+impl PermissionProvider for FeatherPermissions {
+    fn has_permission(&self, player: &Player, node: &str) -> bool {
+        player.has_permission(node)
+    }
+}
+
+pub struct Permissions {
+    provider: Box<dyn PermissionProvider>,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self::new(FeatherPermissions)
+    }
+}
+
+impl Permissions {
+    pub fn new(provider: impl PermissionProvider + 'static) -> Self {
+        Self {
+            provider: Box::new(provider),
+        }
+    }
+
+    pub fn has(&self, player: &Player, node: &str) -> bool {
+        self.provider.has_permission(player, node)
+    }
+
+    /// Permission plugins such as LuckPerms give members of a group the
+    /// `group.<name>` node.
+    pub fn in_group(&self, player: &Player, group: &str) -> bool {
+        self.has(player, &format!("group.{}", group))
+    }
+
+    /// The number of chunks `player` may own, or `None` if unlimited. Tiers
+    /// are granted through `landclaiming.limit.<tier>` and the highest held
+    /// tier wins.
+    pub fn claim_limit(&self, player: &Player, limits: &LimitsConfig) -> Option<usize> {
+        if self.has(player, UNLIMITED_CLAIMS) {
+            return None;
+        }
+
+        let tier = limits
+            .tiers
+            .iter()
+            .filter(|tier| self.has(player, &format!("landclaiming.limit.{}", tier)))
+            .max();
+        Some(tier.copied().unwrap_or(limits.default_claims))
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use feather_core::world::ChunkPosition;
+use feather_core::Position;
+use feather_server::world::WorldId;
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditEntry;
+use crate::claims::{Claim, ClaimId};
+use crate::flags::ClaimFlag;
+use crate::homes::ClaimHome;
+use crate::interactions::InteractionMatrix;
+use crate::rental::{Lease, Rental};
+use crate::storage::StorageDriver;
+use crate::trust::TrustLevel;
+use crate::upkeep::UpkeepState;
+
+/// A claim as written to storage. Worlds are kept by name, since world ids
+/// are only stable for one run of the server.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredClaim {
+    pub id: u64,
+    pub world: String,
+    pub chunks: Vec<(i32, i32)>,
+    pub owner: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub trusted: HashMap<String, TrustLevel>,
+    #[serde(default)]
+    pub greeting: Option<String>,
+    #[serde(default)]
+    pub farewell: Option<String>,
+    #[serde(default)]
+    pub banned: Vec<String>,
+    #[serde(default)]
+    pub rental: Option<StoredRental>,
+    #[serde(default)]
+    pub sale_price: Option<f64>,
+    #[serde(default)]
+    pub flags: HashMap<ClaimFlag, bool>,
+    #[serde(default)]
+    pub interactions: InteractionMatrix,
+    #[serde(default)]
+    pub home: Option<StoredHome>,
+    #[serde(default)]
+    pub visit_point: Option<(f64, f64, f64)>,
+    #[serde(default)]
+    pub upkeep: Option<StoredUpkeep>,
+    #[serde(default)]
+    pub heirs: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredRental {
+    pub price: f64,
+    pub duration_secs: u64,
+    pub lease: Option<StoredLease>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredLease {
+    pub renter: String,
+    /// Seconds since the Unix epoch.
+    pub expires_at: u64,
+    pub previous_trust: Option<TrustLevel>,
+}
+
+/// Times are seconds since the Unix epoch.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredUpkeep {
+    pub paid_until: u64,
+    pub overdue_since: Option<u64>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredHome {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub name: Option<String>,
+}
+
+impl StoredClaim {
+    pub fn new(claim: &Claim, world: &str) -> Self {
+        Self {
+            id: claim.id.0,
+            world: world.to_owned(),
+            chunks: claim.chunks.iter().map(|chunk| (chunk.x, chunk.z)).collect(),
+            owner: claim.owner.clone(),
+            name: claim.name.clone(),
+            trusted: claim.trusted.clone(),
+            greeting: claim.greeting.clone(),
+            farewell: claim.farewell.clone(),
+            banned: claim.banned.clone(),
+            rental: claim.rental.as_ref().map(|rental| StoredRental {
+                price: rental.price,
+                duration_secs: rental.duration.as_secs(),
+                lease: rental.lease.as_ref().map(|lease| StoredLease {
+                    renter: lease.renter.clone(),
+                    expires_at: unix_secs(lease.expires_at),
+                    previous_trust: lease.previous_trust,
+                }),
+            }),
+            sale_price: claim.sale_price,
+            flags: claim.flags.clone(),
+            interactions: claim.interactions.clone(),
+            home: claim.home.as_ref().map(|home| StoredHome {
+                x: home.position.x,
+                y: home.position.y,
+                z: home.position.z,
+                name: home.name.clone(),
+            }),
+            visit_point: claim.visit_point.map(|point| (point.x, point.y, point.z)),
+            upkeep: claim.upkeep.map(|upkeep| StoredUpkeep {
+                paid_until: unix_secs(upkeep.paid_until),
+                overdue_since: upkeep.overdue_since.map(unix_secs),
+            }),
+            heirs: claim.heirs.clone(),
+        }
+    }
+
+    pub fn to_claim(&self, world: WorldId) -> Claim {
+        Claim {
+            id: ClaimId(self.id),
+            world,
+            chunks: self.chunks.iter().map(|&(x, z)| ChunkPosition::new(x, z)).collect(),
+            owner: self.owner.clone(),
+            name: self.name.clone(),
+            trusted: self.trusted.clone(),
+            greeting: self.greeting.clone(),
+            farewell: self.farewell.clone(),
+            banned: self.banned.clone(),
+            rental: self.rental.as_ref().map(|rental| Rental {
+                price: rental.price,
+                duration: Duration::from_secs(rental.duration_secs),
+                lease: rental.lease.as_ref().map(|lease| Lease {
+                    renter: lease.renter.clone(),
+                    expires_at: from_unix_secs(lease.expires_at),
+                    previous_trust: lease.previous_trust,
+                }),
+            }),
+            sale_price: self.sale_price,
+            flags: self.flags.clone(),
+            interactions: self.interactions.clone(),
+            home: self.home.as_ref().map(|home| ClaimHome {
+                position: Position {
+                    x: home.x,
+                    y: home.y,
+                    z: home.z,
+                    ..Position::default()
+                },
+                name: home.name.clone(),
+            }),
+            visit_point: self.visit_point.map(|(x, y, z)| Position {
+                x,
+                y,
+                z,
+                ..Position::default()
+            }),
+            upkeep: self.upkeep.as_ref().map(|upkeep| UpkeepState {
+                paid_until: from_unix_secs(upkeep.paid_until),
+                overdue_since: upkeep.overdue_since.map(from_unix_secs),
+            }),
+            heirs: self.heirs.clone(),
+        }
+    }
+}
+
+/// What is kept about a player outside their claims. Players with nothing
+/// worth keeping are left out.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredPlayer {
+    pub name: String,
+    /// Seconds since the Unix epoch.
+    #[serde(default)]
+    pub last_seen: Option<u64>,
+    /// Claim blocks gained or spent on top of the starting amount.
+    #[serde(default)]
+    pub claim_blocks: i64,
+}
+
+impl StoredPlayer {
+    pub fn new(name: &str, last_seen: Option<SystemTime>, claim_blocks: i64) -> Self {
+        Self {
+            name: name.to_owned(),
+            last_seen: last_seen.map(unix_secs),
+            claim_blocks,
+        }
+    }
+
+    pub fn last_seen(&self) -> Option<SystemTime> {
+        self.last_seen.map(from_unix_secs)
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+fn from_unix_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Claims changed since the previous batch, and ids of removed ones.
+/// `players` is every stored player, when any of them changed.
+#[derive(Default)]
+pub struct ClaimBatch {
+    pub changed: Vec<StoredClaim>,
+    pub removed: Vec<u64>,
+    pub players: Option<Vec<StoredPlayer>>,
+    pub audit: Vec<AuditEntry>,
+}
+
+impl ClaimBatch {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+            && self.removed.is_empty()
+            && self.players.is_none()
+            && self.audit.is_empty()
+    }
+}
+
+/// Writes claim changes and audit entries on a background thread, so a
+/// slow backend never holds up a server tick. The tick side only collects what changed; the
+/// writer thread saves batches in the order they were queued.
+#[derive(Default)]
+pub struct ClaimSaver {
+    queue: Mutex<Option<Sender<(u64, ClaimBatch)>>>,
+    writer: Mutex<Option<JoinHandle<()>>>,
+    /// Numbers handed to batches as they are queued, from 1.
+    queued: AtomicU64,
+    /// The batch up to which every claim change reached storage. It stops
+    /// moving after a failed save, since storage may then miss changes.
+    written: Arc<AtomicU64>,
+    /// The last batch each claim was part of, until that batch is written.
+    pending: Mutex<HashMap<u64, u64>>,
+}
+
+impl ClaimSaver {
+    pub fn start(&self, driver: Arc<dyn StorageDriver>) {
+        let (sender, receiver) = mpsc::channel::<(u64, ClaimBatch)>();
+        let written = self.written.clone();
+        let writer = thread::spawn(move || {
+            let mut failed = false;
+            for (number, batch) in receiver {
+                let count = batch.changed.len() + batch.removed.len();
+                if count > 0 {
+                    if let Err(err) = driver.save_claims(&batch.changed, &batch.removed) {
+                        log::warn!(
+                            "Could not save {} LandClaiming claim change(s): {}",
+                            count,
+                            err
+                        );
+                        failed = true;
+                    }
+                }
+                if !failed {
+                    written.store(number, Ordering::SeqCst);
+                }
+                if let Some(players) = &batch.players {
+                    if let Err(err) = driver.save_players(players) {
+                        log::warn!("Could not save LandClaiming player data: {}", err);
+                    }
+                }
+                for entry in &batch.audit {
+                    if let Err(err) = driver.append_audit(entry) {
+                        log::warn!("Could not save a LandClaiming audit entry: {}", err);
+                    }
+                }
+            }
+        });
+
+        *self.queue.lock().unwrap() = Some(sender);
+        *self.writer.lock().unwrap() = Some(writer);
+    }
+
+    pub fn submit(&self, batch: ClaimBatch) {
+        if batch.is_empty() {
+            return;
+        }
+        if let Some(queue) = self.queue.lock().unwrap().as_ref() {
+            let number = self.queued.fetch_add(1, Ordering::SeqCst) + 1;
+            let mut pending = self.pending.lock().unwrap();
+            let written = self.written.load(Ordering::SeqCst);
+            pending.retain(|_, last| *last > written);
+            let changed = batch.changed.iter().map(|claim| claim.id);
+            for id in changed.chain(batch.removed.iter().copied()) {
+                pending.insert(id, number);
+            }
+            // The writer only stops once the queue is dropped.
+            let _ = queue.send((number, batch));
+        }
+    }
+
+    /// Queues `entry` to be appended to the audit log right away, rather
+    /// than with the next batch of claims.
+    pub fn append_audit(&self, entry: AuditEntry) {
+        self.submit(ClaimBatch {
+            audit: vec![entry],
+            ..ClaimBatch::default()
+        });
+    }
+
+    /// Whether the latest change handed over for claim `id`, if any, has
+    /// reached storage.
+    pub fn is_written(&self, id: ClaimId) -> bool {
+        let written = self.written.load(Ordering::SeqCst);
+        self.pending.lock().unwrap().get(&id.0).map_or(true, |&last| last <= written)
+    }
+
+    /// Waits for every queued batch to be written and stops the writer.
+    pub fn stop(&self) {
+        self.queue.lock().unwrap().take();
+        if let Some(writer) = self.writer.lock().unwrap().take() {
+            if writer.join().is_err() {
+                log::warn!("The LandClaiming claim writer stopped unexpectedly.");
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use feather_core::Position;
+use feather_server::player::Player;
+use feather_server::world::WorldId;
+
+/// What the claim logic needs to know about a player. Implemented for the
+/// server's players, and for stand-ins in tests.
+pub trait PlayerRef {
+    fn name(&self) -> &str;
+
+    fn world(&self) -> WorldId;
+
+    fn position(&self) -> Position;
+}
+
+impl PlayerRef for Player {
+    fn name(&self) -> &str {
+        Player::name(self)
+    }
+
+    fn world(&self) -> WorldId {
+        Player::world(self).id()
+    }
+
+    fn position(&self) -> Position {
+        Player::position(self)
+    }
+}
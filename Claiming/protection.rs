@@ -0,0 +1,39 @@
+use crate::claims::Claim;
+use crate::interactions::{self, Interaction};
+use crate::trust::TrustLevel;
+
+/// Something a player does in a claim that protection may refuse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Placing, breaking or otherwise changing blocks.
+    Build,
+    /// Using a block or entity. `None` is anything the interaction matrix
+    /// does not cover, which needs some trust but nothing more.
+    Interact(Option<Interaction>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    /// They do not hold enough trust for it.
+    Untrusted,
+    /// Their trust would be enough, but the claim's interaction matrix
+    /// takes it away from their level.
+    Forbidden { level: TrustLevel, action: Interaction },
+}
+
+/// Whether someone holding `level` in `claim` may do `action` there.
+pub fn decide(claim: &Claim, level: Option<TrustLevel>, action: Action) -> Decision {
+    match (level, action) {
+        (Some(level), Action::Build) if level >= TrustLevel::Build => Decision::Allow,
+        (None, _) | (Some(_), Action::Build) => Decision::Untrusted,
+        (Some(_), Action::Interact(None)) => Decision::Allow,
+        (Some(level), Action::Interact(Some(action))) => {
+            if interactions::allows(&claim.interactions, level, action) {
+                Decision::Allow
+            } else {
+                Decision::Forbidden { level, action }
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::claims::ClaimId;
+use crate::config::RateLimitsConfig;
+use crate::greetings::EntryMessage;
+
+/// Commands counted against a player's rate limit. Each kind has its own
+/// allowance.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitedAction {
+    Claim,
+    Unclaim,
+    /// `/trust` and `/untrust` together.
+    Trust,
+}
+
+impl LimitedAction {
+    fn allowance(self, config: &RateLimitsConfig) -> u32 {
+        match self {
+            LimitedAction::Claim => config.claims,
+            LimitedAction::Unclaim => config.unclaims,
+            LimitedAction::Trust => config.trust,
+        }
+    }
+}
+
+/// Recent uses of rate limited commands, and when each player last saw a
+/// claim's greeting or farewell.
+#[derive(Default)]
+pub struct RateLimiter {
+    uses: Mutex<HashMap<(String, LimitedAction), VecDeque<Instant>>>,
+    messages_shown: Mutex<HashMap<(String, ClaimId, EntryMessage), Instant>>,
+}
+
+impl RateLimiter {
+    /// Counts a use of `action` by `player`, unless they have used up its
+    /// allowance for the current window. Then nothing is counted and the
+    /// time until the oldest use stops counting is returned instead.
+    pub fn try_use(
+        &self,
+        player: &str,
+        action: LimitedAction,
+        config: &RateLimitsConfig,
+    ) -> Result<(), Duration> {
+        let allowance = action.allowance(config) as usize;
+        if allowance == 0 {
+            return Ok(());
+        }
+        let window = config.window();
+        let now = Instant::now();
+
+        let mut uses = self.uses.lock().unwrap();
+        let recent = uses.entry((player.to_owned(), action)).or_default();
+        while recent.front().map_or(false, |&used| now.duration_since(used) >= window) {
+            recent.pop_front();
+        }
+
+        if recent.len() >= allowance {
+            let oldest = recent[0];
+            return Err(window - now.duration_since(oldest));
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+
+    /// Whether `player` should see `claim`'s greeting or farewell again, so
+    /// walking back and forth over a border does not repeat it every step.
+    /// Records it as shown when they should.
+    pub fn should_show_message(
+        &self,
+        player: &str,
+        claim: ClaimId,
+        kind: EntryMessage,
+        quiet: Duration,
+    ) -> bool {
+        let mut shown = self.messages_shown.lock().unwrap();
+        let now = Instant::now();
+        shown.retain(|_, at| now.duration_since(*at) < quiet);
+
+        match shown.entry((player.to_owned(), claim, kind)) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(now);
+                true
+            }
+        }
+    }
+
+    pub fn forget(&self, player: &str) {
+        self.uses.lock().unwrap().retain(|(name, _), _| name != player);
+        self.messages_shown.lock().unwrap().retain(|(name, _, _), _| name != player);
+    }
+}
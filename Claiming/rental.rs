@@ -0,0 +1,56 @@
+use std::time::{Duration, SystemTime};
+
+use crate::trust::TrustLevel;
+
+/// How often leases are checked for expiry.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What a renter is trusted with for the length of their lease.
+pub const RENTER_TRUST: TrustLevel = TrustLevel::Build;
+
+/// A claim its owner has put up for rent.
+#[derive(Clone)]
+pub struct Rental {
+    pub price: f64,
+    pub duration: Duration,
+    pub lease: Option<Lease>,
+}
+
+#[derive(Clone)]
+pub struct Lease {
+    pub renter: String,
+    pub expires_at: SystemTime,
+    /// The trust the renter had before the lease, restored when it ends.
+    pub previous_trust: Option<TrustLevel>,
+}
+
+/// Parses lengths such as `30m`, `12h`, `7d` or `2w`.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let split = text.len().checked_sub(1)?;
+    let (amount, unit) = text.split_at(split);
+    let amount: u64 = amount.parse().ok().filter(|&amount| amount > 0)?;
+
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    amount.checked_mul(unit_secs).map(Duration::from_secs)
+}
+
+/// Formats `duration` in the largest unit `parse_duration` accepts that
+/// divides it evenly.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    [("w", 7 * 24 * 60 * 60), ("d", 24 * 60 * 60), ("h", 60 * 60)]
+        .iter()
+        .find(|(_, unit_secs)| secs % unit_secs == 0)
+        .map_or_else(
+            || format!("{}m", secs / 60),
+            |(unit, unit_secs)| format!("{}{}", secs / unit_secs, unit),
+        )
+}
+
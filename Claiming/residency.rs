@@ -0,0 +1,290 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use feather_core::world::ChunkPosition;
+use feather_core::Position;
+use feather_server::world::WorldId;
+
+use crate::claims::{Claim, ClaimId, ClaimKey};
+
+/// Chunks along each side of a region. Claims are loaded and dropped a
+/// region at a time.
+pub const REGION_CHUNKS: i32 = 32;
+
+/// How often idle regions are looked for.
+pub const EVICTION_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Region {
+    pub world: WorldId,
+    pub x: i32,
+    pub z: i32,
+}
+
+impl Region {
+    pub fn of(key: ClaimKey) -> Self {
+        Self {
+            world: key.world,
+            x: key.chunk.x.div_euclid(REGION_CHUNKS),
+            z: key.chunk.z.div_euclid(REGION_CHUNKS),
+        }
+    }
+}
+
+/// What is remembered of a claim that may not be loaded: enough to list it
+/// and to know when to load it.
+#[derive(Clone, Debug)]
+pub struct IndexedClaim {
+    pub id: ClaimId,
+    pub world: WorldId,
+    pub owner: String,
+    pub name: Option<String>,
+    pub first_chunk: ChunkPosition,
+    pub chunk_count: usize,
+    pub visit_point: Option<Position>,
+    pub has_home: bool,
+    /// When the claim's lease runs out, while it is rented.
+    pub lease_expires: Option<SystemTime>,
+    /// When upkeep next falls due, or `None` before it was first started.
+    pub upkeep_due: Option<SystemTime>,
+    pub upkeep_overdue: bool,
+    chunks: Vec<ChunkPosition>,
+    regions: Vec<Region>,
+}
+
+impl IndexedClaim {
+    pub fn of(claim: &Claim) -> Self {
+        let mut regions: Vec<Region> = Vec::new();
+        for region in claim.keys().map(Region::of) {
+            if !regions.contains(&region) {
+                regions.push(region);
+            }
+        }
+
+        Self {
+            id: claim.id,
+            world: claim.world,
+            owner: claim.owner.clone(),
+            name: claim.name.clone(),
+            first_chunk: claim.chunks.first().copied().unwrap_or_else(|| ChunkPosition::new(0, 0)),
+            chunk_count: claim.chunks.len(),
+            visit_point: claim.visit_point,
+            has_home: claim.home.is_some(),
+            lease_expires: claim
+                .rental
+                .as_ref()
+                .and_then(|rental| rental.lease.as_ref())
+                .map(|lease| lease.expires_at),
+            upkeep_due: claim.upkeep.map(|state| state.paid_until),
+            upkeep_overdue: claim.upkeep.map_or(false, |state| state.overdue_since.is_some()),
+            chunks: claim.chunks.clone(),
+            regions,
+        }
+    }
+
+    pub fn chunks(&self) -> &[ChunkPosition] {
+        &self.chunks
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = ClaimKey> + '_ {
+        self.chunks.iter().map(move |&chunk| ClaimKey::new(self.world, chunk))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    enabled: bool,
+    claims: HashMap<ClaimId, IndexedClaim>,
+    by_region: HashMap<Region, HashSet<ClaimId>>,
+    by_chunk: HashMap<ClaimKey, ClaimId>,
+    /// Loaded chunks in each region that has any.
+    loaded_chunks: HashMap<Region, usize>,
+    /// Regions whose claims are still held although none of their chunks
+    /// are loaded, and since when.
+    idle_since: HashMap<Region, Instant>,
+}
+
+impl State {
+    fn remove(&mut self, id: ClaimId) {
+        if let Some(old) = self.claims.remove(&id) {
+            for region in &old.regions {
+                if let Some(ids) = self.by_region.get_mut(region) {
+                    ids.remove(&id);
+                }
+            }
+            for key in old.keys() {
+                self.by_chunk.remove(&key);
+            }
+        }
+    }
+
+    fn insert(&mut self, claim: IndexedClaim) {
+        self.remove(claim.id);
+        for &region in &claim.regions {
+            self.by_region.entry(region).or_default().insert(claim.id);
+        }
+        for key in claim.keys() {
+            self.by_chunk.insert(key, claim.id);
+        }
+        self.claims.insert(claim.id, claim);
+    }
+
+    fn is_resident(&self, region: &Region) -> bool {
+        self.loaded_chunks.contains_key(region) || self.idle_since.contains_key(region)
+    }
+}
+
+/// Which claims are kept in memory when `storage.lazy_loading` is on. Every
+/// stored claim stays in a small index by chunk, region and owner; the
+/// claims of a region are loaded when its first chunk loads, and dropped
+/// again once none of its chunks have been loaded for a while.
+#[derive(Default)]
+pub struct ClaimResidency {
+    state: Mutex<State>,
+}
+
+impl ClaimResidency {
+    /// Turns lazy loading on with `claims` as everything in storage.
+    pub fn index(&self, claims: &[Claim]) {
+        let mut state = self.state.lock().unwrap();
+        *state = State {
+            enabled: true,
+            ..State::default()
+        };
+        for claim in claims {
+            state.insert(IndexedClaim::of(claim));
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap().enabled
+    }
+
+    /// Keeps the index in step with what was just saved.
+    pub fn update(&self, changed: &[Claim], removed: &[ClaimId]) {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled {
+            return;
+        }
+        for &id in removed {
+            state.remove(id);
+        }
+        for claim in changed {
+            state.insert(IndexedClaim::of(claim));
+        }
+    }
+
+    /// Counts a chunk as loaded. When it is the first of its region since
+    /// the region's claims were dropped, returns the claims to load.
+    pub fn chunk_loaded(&self, key: ClaimKey) -> Vec<ClaimId> {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled {
+            return Vec::new();
+        }
+        let region = Region::of(key);
+        let was_resident = state.is_resident(&region);
+
+        *state.loaded_chunks.entry(region).or_insert(0) += 1;
+        state.idle_since.remove(&region);
+        if was_resident {
+            return Vec::new();
+        }
+        state
+            .by_region
+            .get(&region)
+            .map_or_else(Vec::new, |ids| ids.iter().copied().collect())
+    }
+
+    pub fn chunk_unloaded(&self, key: ClaimKey) {
+        let mut state = self.state.lock().unwrap();
+        let region = Region::of(key);
+
+        let now_empty = match state.loaded_chunks.get_mut(&region) {
+            Some(count) => {
+                *count -= 1;
+                *count == 0
+            }
+            None => false,
+        };
+        if now_empty {
+            state.loaded_chunks.remove(&region);
+            state.idle_since.insert(region, Instant::now());
+        }
+    }
+
+    /// Lets go of regions that have had no loaded chunks for `after`, and
+    /// returns those of the `loaded` claims no region still held needs.
+    /// Claims that could not be dropped last time are offered again, so
+    /// they go as soon as they can.
+    pub fn evictable(&self, after: Duration, mut loaded: Vec<ClaimId>) -> Vec<ClaimId> {
+        let mut state = self.state.lock().unwrap();
+        state.idle_since.retain(|_, since| since.elapsed() < after);
+
+        loaded.retain(|id| {
+            state.claims.get(id).map_or(false, |claim| {
+                claim.regions.iter().all(|region| !state.is_resident(region))
+            })
+        });
+        loaded
+    }
+
+    /// The indexed claim covering `key`, loaded or not.
+    pub fn claim_at(&self, key: ClaimKey) -> Option<IndexedClaim> {
+        let state = self.state.lock().unwrap();
+        state.by_chunk.get(&key).and_then(|id| state.claims.get(id)).cloned()
+    }
+
+    /// Every indexed claim, loaded or not, by id.
+    pub fn all(&self) -> Vec<IndexedClaim> {
+        self.matching(|_| true)
+    }
+
+    /// Every indexed claim of `owner`, loaded or not, by id.
+    pub fn owned_by(&self, owner: &str) -> Vec<IndexedClaim> {
+        self.matching(|claim| claim.owner == owner)
+    }
+
+    /// Every indexed claim open to visitors, loaded or not, by id.
+    pub fn visitable(&self) -> Vec<IndexedClaim> {
+        self.matching(|claim| claim.visit_point.is_some())
+    }
+
+    /// Every indexed claim `filter` picks, loaded or not, by id.
+    pub fn matching(&self, filter: impl Fn(&IndexedClaim) -> bool) -> Vec<IndexedClaim> {
+        let state = self.state.lock().unwrap();
+        let mut matching: Vec<IndexedClaim> =
+            state.claims.values().filter(|claim| filter(claim)).cloned().collect();
+        matching.sort_by_key(|claim| claim.id);
+        matching
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claims::ClaimManager;
+
+    fn key(x: i32, z: i32) -> ClaimKey {
+        ClaimKey::new(WorldId::default(), ChunkPosition::new(x, z))
+    }
+
+    #[test]
+    fn claims_load_with_their_region_and_drop_once_it_idles() {
+        let claims = ClaimManager::default();
+        let id = claims.insert_if_vacant(key(1, 1), "alice").unwrap();
+        let residency = ClaimResidency::default();
+        residency.index(&claims.all());
+
+        assert_eq!(residency.chunk_loaded(key(0, 0)), vec![id]);
+        assert!(residency.chunk_loaded(key(2, 2)).is_empty());
+        assert!(residency.chunk_loaded(key(40, 0)).is_empty());
+
+        residency.chunk_unloaded(key(0, 0));
+        assert!(residency.evictable(Duration::ZERO, vec![id]).is_empty());
+        residency.chunk_unloaded(key(2, 2));
+        assert_eq!(residency.evictable(Duration::ZERO, vec![id]), vec![id]);
+        assert_eq!(residency.chunk_loaded(key(3, 3)), vec![id]);
+        assert_eq!(residency.claim_at(key(1, 1)).map(|claim| claim.id), Some(id));
+        assert!(residency.claim_at(key(2, 2)).is_none());
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How far each run may drift from its interval, as a share of it, so
+/// tasks on the same interval do not all land on the same tick.
+const JITTER: f64 = 0.1;
+
+/// A job run over and over, `every` apart. The interval is asked for again
+/// after each run, so it follows configuration reloads.
+pub struct Task<C> {
+    name: &'static str,
+    every: fn(&C) -> Duration,
+    job: fn(&C),
+}
+
+impl<C> Task<C> {
+    pub fn new(name: &'static str, every: fn(&C) -> Duration, job: fn(&C)) -> Self {
+        Self { name, every, job }
+    }
+}
+
+/// How long a task's runs have taken.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TaskMetrics {
+    pub runs: u64,
+    pub total: Duration,
+    pub longest: Duration,
+}
+
+impl TaskMetrics {
+    fn record(&mut self, took: Duration) {
+        self.runs += 1;
+        self.total += took;
+        self.longest = self.longest.max(took);
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let nanos = self.total.as_nanos().checked_div(u128::from(self.runs))?;
+        Some(Duration::from_nanos(nanos.min(u128::from(u64::MAX)) as u64))
+    }
+}
+
+pub struct TaskReport {
+    pub name: &'static str,
+    pub metrics: TaskMetrics,
+    pub next_in: Duration,
+}
+
+struct Scheduled<C> {
+    task: Task<C>,
+    next_run: Instant,
+    metrics: TaskMetrics,
+}
+
+/// Repeating maintenance jobs, run from the server tick on `C`.
+pub struct Scheduler<C> {
+    tasks: Mutex<Vec<Scheduled<C>>>,
+}
+
+impl<C> Default for Scheduler<C> {
+    fn default() -> Self {
+        Self {
+            tasks: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<C> Scheduler<C> {
+    /// Adds `task`. Its first run falls anywhere within its first interval,
+    /// so tasks registered together are spread out.
+    pub fn register(&self, context: &C, task: Task<C>) {
+        let first = (task.every)(context).mul_f64(random_share());
+        self.tasks.lock().unwrap().push(Scheduled {
+            task,
+            next_run: Instant::now() + first,
+            metrics: TaskMetrics::default(),
+        });
+    }
+
+    /// Runs every task that is due. Jobs run without the task list locked,
+    /// so they may look at the scheduler themselves.
+    pub fn run_due(&self, context: &C) {
+        let now = Instant::now();
+        let due: Vec<(usize, fn(&C))> = self
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, scheduled)| scheduled.next_run <= now)
+            .map(|(index, scheduled)| (index, scheduled.task.job))
+            .collect();
+
+        for (index, job) in due {
+            let started = Instant::now();
+            job(context);
+            let took = started.elapsed();
+
+            let mut tasks = self.tasks.lock().unwrap();
+            let scheduled = &mut tasks[index];
+            scheduled.metrics.record(took);
+            scheduled.next_run = Instant::now() + jittered((scheduled.task.every)(context));
+        }
+    }
+
+    pub fn reports(&self) -> Vec<TaskReport> {
+        let now = Instant::now();
+        self.tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|scheduled| TaskReport {
+                name: scheduled.task.name,
+                metrics: scheduled.metrics,
+                next_in: scheduled.next_run.saturating_duration_since(now),
+            })
+            .collect()
+    }
+}
+
+fn jittered(interval: Duration) -> Duration {
+    interval.mul_f64(1.0 + JITTER * (2.0 * random_share() - 1.0))
+}
+
+/// A number from 0 to 1. Good enough to spread tasks out, nothing more.
+fn random_share() -> f64 {
+    RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counters {
+        often: AtomicU32,
+        rarely: AtomicU32,
+    }
+
+    #[test]
+    fn runs_only_due_tasks_and_counts_them() {
+        let counters = Counters::default();
+        let scheduler = Scheduler::default();
+        scheduler.register(
+            &counters,
+            Task::new("often", |_| Duration::ZERO, |counters: &Counters| {
+                counters.often.fetch_add(1, Ordering::Relaxed);
+            }),
+        );
+        scheduler.register(
+            &counters,
+            Task::new("rarely", |_| Duration::from_secs(3600), |counters: &Counters| {
+                counters.rarely.fetch_add(1, Ordering::Relaxed);
+            }),
+        );
+
+        scheduler.run_due(&counters);
+        scheduler.run_due(&counters);
+
+        assert_eq!(counters.often.load(Ordering::Relaxed), 2);
+        let reports = scheduler.reports();
+        assert_eq!(reports[0].metrics.runs, 2);
+        assert!(reports[0].metrics.mean().is_some());
+        assert_eq!(reports[1].metrics.runs, 0);
+        assert!(reports[1].metrics.mean().is_none());
+    }
+}
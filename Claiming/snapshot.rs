@@ -0,0 +1,132 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditEntry;
+use crate::persistence::StoredClaim;
+
+/// Marks a file as a LandClaiming claim snapshot.
+const FORMAT: &str = "landclaiming-claims";
+
+/// Bumped whenever a snapshot stops being readable by older versions.
+/// Snapshots from newer versions are refused rather than half read.
+pub const VERSION: u32 = 1;
+
+/// Every claim on the server at one moment, for backups and for moving
+/// claims to another server.
+#[derive(Serialize, Deserialize)]
+pub struct ClaimSnapshot {
+    pub format: String,
+    pub version: u32,
+    /// Seconds since the Unix epoch.
+    pub exported_at: u64,
+    pub claims: Vec<StoredClaim>,
+}
+
+impl ClaimSnapshot {
+    pub fn new(claims: Vec<StoredClaim>) -> Self {
+        Self {
+            format: String::from(FORMAT),
+            version: VERSION,
+            exported_at: AuditEntry::now(),
+            claims,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    NotASnapshot(PathBuf),
+    NewerVersion(PathBuf, u32),
+    /// The path named a file outside the data directory.
+    OutsideDataDir(PathBuf),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(path, err) => {
+                write!(f, "could not access {}: {}", path.display(), err)
+            }
+            SnapshotError::Parse(path, err) => write!(f, "invalid {}: {}", path.display(), err),
+            SnapshotError::NotASnapshot(path) => {
+                write!(f, "{} is not a LandClaiming claim snapshot", path.display())
+            }
+            SnapshotError::NewerVersion(path, version) => write!(
+                f,
+                "{} is snapshot version {}, but this version only reads up to {}",
+                path.display(),
+                version,
+                VERSION
+            ),
+            SnapshotError::OutsideDataDir(path) => write!(
+                f,
+                "{} is not inside the data directory; give a path relative to it",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Where the snapshot `file` is, always inside `dir`, so a command can
+/// never read or overwrite files elsewhere on the server. Absolute paths
+/// and paths with `..` are refused.
+pub fn resolve(dir: &Path, file: &str) -> Result<PathBuf, SnapshotError> {
+    let relative = Path::new(file);
+    let mut components = relative.components().peekable();
+    let named = components.peek().is_some();
+    if named && components.all(|part| matches!(part, Component::Normal(_) | Component::CurDir)) {
+        Ok(dir.join(relative))
+    } else {
+        Err(SnapshotError::OutsideDataDir(relative.to_owned()))
+    }
+}
+
+pub fn write(path: &Path, snapshot: &ClaimSnapshot) -> Result<(), SnapshotError> {
+    let io_error = |err| SnapshotError::Io(path.to_owned(), err);
+
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    let json = serde_json::to_vec_pretty(snapshot)
+        .map_err(|err| SnapshotError::Parse(path.to_owned(), err))?;
+    fs::write(path, json).map_err(io_error)
+}
+
+pub fn read(path: &Path) -> Result<ClaimSnapshot, SnapshotError> {
+    let json = fs::read(path).map_err(|err| SnapshotError::Io(path.to_owned(), err))?;
+    let value: serde_json::Value =
+        serde_json::from_slice(&json).map_err(|err| SnapshotError::Parse(path.to_owned(), err))?;
+
+    // Check what the file claims to be before reading it as claims, so a
+    // newer snapshot gets a clear error instead of a parse failure.
+    if value.get("format").and_then(|format| format.as_str()) != Some(FORMAT) {
+        return Err(SnapshotError::NotASnapshot(path.to_owned()));
+    }
+    let version = value.get("version").and_then(|version| version.as_u64()).unwrap_or(0);
+    if version > u64::from(VERSION) {
+        return Err(SnapshotError::NewerVersion(path.to_owned(), version as u32));
+    }
+
+    serde_json::from_value(value).map_err(|err| SnapshotError::Parse(path.to_owned(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_stay_inside_the_data_directory() {
+        let dir = Path::new("data");
+
+        assert_eq!(resolve(dir, "backups/monday.json").unwrap(), dir.join("backups/monday.json"));
+        for file in ["", "/etc/passwd", "../claims.json", "backups/../../escape.json"] {
+            let refused = matches!(resolve(dir, file), Err(SnapshotError::OutsideDataDir(_)));
+            assert!(refused, "{}", file);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// Upper bounds of the claim lookup latency buckets, in microseconds. One
+/// more bucket counts everything slower.
+pub const LATENCY_BUCKETS_MICROS: [u64; 8] = [1, 5, 10, 50, 100, 500, 1_000, 10_000];
+
+/// Counters kept while the plugin runs. Creations and deletions are counted
+/// per chunk, the same as `ClaimEvent`.
+#[derive(Default)]
+pub struct ClaimStats {
+    created: AtomicU64,
+    deleted: AtomicU64,
+    denied: AtomicU64,
+    recent_created: Mutex<VecDeque<Instant>>,
+    recent_deleted: Mutex<VecDeque<Instant>>,
+    lookups: [AtomicU64; LATENCY_BUCKETS_MICROS.len() + 1],
+    lookup_micros: AtomicU64,
+}
+
+impl ClaimStats {
+    pub fn record_created(&self) {
+        self.created.fetch_add(1, Ordering::Relaxed);
+        push_recent(&self.recent_created);
+    }
+
+    pub fn record_deleted(&self) {
+        self.deleted.fetch_add(1, Ordering::Relaxed);
+        push_recent(&self.recent_deleted);
+    }
+
+    pub fn record_denied(&self) {
+        self.denied.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_lookup(&self, took: Duration) {
+        let micros = took.as_micros().min(u128::from(u64::MAX)) as u64;
+        let bucket = LATENCY_BUCKETS_MICROS
+            .iter()
+            .position(|&bound| micros <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MICROS.len());
+
+        self.lookups[bucket].fetch_add(1, Ordering::Relaxed);
+        self.lookup_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    /// The counters as they are now, with the figures only the caller knows:
+    /// how many claims and chunks exist, and the lookup cache's hits and
+    /// misses.
+    pub fn snapshot(&self, claims: usize, chunks: usize, cache: (u64, u64)) -> StatsSnapshot {
+        StatsSnapshot {
+            claims,
+            chunks,
+            created: self.created.load(Ordering::Relaxed),
+            deleted: self.deleted.load(Ordering::Relaxed),
+            created_last_hour: count_recent(&self.recent_created),
+            deleted_last_hour: count_recent(&self.recent_deleted),
+            denied_interactions: self.denied.load(Ordering::Relaxed),
+            cache_hits: cache.0,
+            cache_misses: cache.1,
+            lookup_buckets: self
+                .lookups
+                .iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
+            lookup_micros: self.lookup_micros.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn push_recent(recent: &Mutex<VecDeque<Instant>>) {
+    let mut recent = recent.lock().unwrap();
+    let now = Instant::now();
+
+    while recent.front().map_or(false, |&at| now.duration_since(at) >= HOUR) {
+        recent.pop_front();
+    }
+    recent.push_back(now);
+}
+
+fn count_recent(recent: &Mutex<VecDeque<Instant>>) -> u64 {
+    let now = Instant::now();
+    recent.lock().unwrap().iter().filter(|&&at| now.duration_since(at) < HOUR).count() as u64
+}
+
+/// A copy of every counter, for `/claimadmin stats` and for other plugins
+/// through `LandClaimingApi::stats`. Totals count from when the plugin was
+/// enabled.
+#[derive(Clone, Debug)]
+pub struct StatsSnapshot {
+    pub claims: usize,
+    pub chunks: usize,
+    pub created: u64,
+    pub deleted: u64,
+    pub created_last_hour: u64,
+    pub deleted_last_hour: u64,
+    pub denied_interactions: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// Lookups per bucket of `LATENCY_BUCKETS_MICROS`, then the lookups
+    /// slower than all of them.
+    pub lookup_buckets: Vec<u64>,
+    pub lookup_micros: u64,
+}
+
+impl StatsSnapshot {
+    pub fn lookups(&self) -> u64 {
+        self.lookup_buckets.iter().sum()
+    }
+
+    /// `None` before the first lookup.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let total = self.cache_hits + self.cache_misses;
+        Some(self.cache_hits as f64 / total as f64).filter(|_| total > 0)
+    }
+
+    pub fn mean_lookup(&self) -> Option<Duration> {
+        let lookups = self.lookups();
+        Some(Duration::from_micros(self.lookup_micros / lookups.max(1))).filter(|_| lookups > 0)
+    }
+
+    /// The counters in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let metrics = [
+            ("claims", "gauge", "Claims that exist.", self.claims as u64),
+            ("chunks", "gauge", "Claimed chunks.", self.chunks as u64),
+            ("created_total", "counter", "Chunks claimed.", self.created),
+            ("deleted_total", "counter", "Chunks unclaimed.", self.deleted),
+            (
+                "denied_interactions_total",
+                "counter",
+                "Interactions refused in claims.",
+                self.denied_interactions,
+            ),
+            ("cache_hits_total", "counter", "Lookups answered from the cache.", self.cache_hits),
+            ("cache_misses_total", "counter", "Lookups that missed the cache.", self.cache_misses),
+        ];
+        for &(name, kind, help, value) in metrics.iter() {
+            describe_metric(&mut out, name, kind, help);
+            let _ = writeln!(out, "landclaiming_{} {}", name, value);
+        }
+
+        describe_metric(&mut out, "lookup_seconds", "histogram", "Time taken by claim lookups.");
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS_MICROS.iter().zip(&self.lookup_buckets) {
+            cumulative += count;
+            let le = *bound as f64 / 1_000_000.0;
+            let bucket = format!("landclaiming_lookup_seconds_bucket{{le=\"{}\"}}", le);
+            let _ = writeln!(out, "{} {}", bucket, cumulative);
+        }
+        let lookups = self.lookups();
+        let sum = self.lookup_micros as f64 / 1_000_000.0;
+        let _ = writeln!(out, "landclaiming_lookup_seconds_bucket{{le=\"+Inf\"}} {}", lookups);
+        let _ = writeln!(out, "landclaiming_lookup_seconds_sum {}", sum);
+        let _ = writeln!(out, "landclaiming_lookup_seconds_count {}", lookups);
+        out
+    }
+}
+
+fn describe_metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP landclaiming_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE landclaiming_{} {}", name, kind);
+}
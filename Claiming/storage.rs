@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::audit::AuditEntry;
+use crate::config::{StorageBackend, StorageConfig};
+use crate::persistence::{StoredClaim, StoredPlayer};
+
+/// Where plugin data outlives a restart. Picked once on enable from the
+/// `[storage]` config section.
+pub trait StorageDriver: Send + Sync {
+    /// Called from a background thread.
+    fn append_audit(&self, entry: &AuditEntry) -> io::Result<()>;
+
+    /// The newest `limit` audit entries, oldest first.
+    fn load_audit(&self, limit: usize) -> io::Result<Vec<AuditEntry>>;
+
+    /// Saves `changed` claims over any earlier copies and forgets the
+    /// `removed` ids. Called from a background thread.
+    fn save_claims(&self, changed: &[StoredClaim], removed: &[u64]) -> io::Result<()>;
+
+    fn load_claims(&self) -> io::Result<Vec<StoredClaim>>;
+
+    /// Replaces every stored player with `players`. Called from a
+    /// background thread.
+    fn save_players(&self, players: &[StoredPlayer]) -> io::Result<()>;
+
+    fn load_players(&self) -> io::Result<Vec<StoredPlayer>>;
+
+    /// The stored claims among `ids`, for loading claims lazily. Drivers
+    /// that can look claims up by id should do so.
+    fn load_claims_by_id(&self, ids: &[u64]) -> io::Result<Vec<StoredClaim>> {
+        let mut claims = self.load_claims()?;
+        claims.retain(|claim| ids.contains(&claim.id));
+        Ok(claims)
+    }
+}
+
+/// Keeps nothing; everything is lost on restart.
+pub struct MemoryDriver;
+
+impl StorageDriver for MemoryDriver {
+    fn append_audit(&self, _entry: &AuditEntry) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn load_audit(&self, _limit: usize) -> io::Result<Vec<AuditEntry>> {
+        Ok(Vec::new())
+    }
+
+    fn save_claims(&self, _changed: &[StoredClaim], _removed: &[u64]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn load_claims(&self) -> io::Result<Vec<StoredClaim>> {
+        Ok(Vec::new())
+    }
+
+    fn save_players(&self, _players: &[StoredPlayer]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn load_players(&self) -> io::Result<Vec<StoredPlayer>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Plain files under the configured data directory. The audit log is one
+/// JSON object per line, so it can be appended to without rewriting.
+/// Claims and players are one JSON document each, replaced as a whole on
+/// every save. Claims are written one to a line, so single claims can be
+/// read back by where they sit in the file.
+pub struct FileDriver {
+    dir: PathBuf,
+    /// Where each claim sits in claims.json, or `None` for a file written
+    /// some other way. Swapped together with the file, so a lookup never
+    /// reads a file the index does not describe.
+    claim_index: Mutex<Option<HashMap<u64, Range<u64>>>>,
+}
+
+impl FileDriver {
+    fn audit_path(&self) -> PathBuf {
+        self.dir.join("audit.jsonl")
+    }
+
+    fn claims_path(&self) -> PathBuf {
+        self.dir.join("claims.json")
+    }
+
+    fn players_path(&self) -> PathBuf {
+        self.dir.join("players.json")
+    }
+
+    fn replace(&self, path: PathBuf, contents: &impl serde::Serialize) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let temp = self.write_temp(&path, &json)?;
+        fs::rename(temp, path)
+    }
+
+    /// Writes next to `path`, for swapping in afterwards, so a crash
+    /// mid-write never leaves a truncated file behind.
+    fn write_temp(&self, path: &PathBuf, json: &[u8]) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, json)?;
+        Ok(temp)
+    }
+
+    /// Reads every claim, along with where each one sits in the file.
+    fn read_claims(&self) -> io::Result<(Vec<StoredClaim>, Option<HashMap<u64, Range<u64>>>)> {
+        let json = match fs::read(self.claims_path()) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok((Vec::new(), Some(HashMap::new())))
+            }
+            Err(err) => return Err(err),
+        };
+        let claims: Vec<StoredClaim> = serde_json::from_slice(&json)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        // One claim to a line means every line opening an object holds a
+        // whole claim; files written before that have none at the start.
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for line in json.split(|&byte| byte == b'\n') {
+            let end = start + line.len();
+            if line.first() == Some(&b'{') {
+                let trimmed = if line.last() == Some(&b',') { end - 1 } else { end };
+                lines.push(start as u64..trimmed as u64);
+            }
+            start = end + 1;
+        }
+        let index = (lines.len() == claims.len())
+            .then(|| claims.iter().map(|claim| claim.id).zip(lines).collect());
+        Ok((claims, index))
+    }
+
+    fn read<T: serde::de::DeserializeOwned>(&self, path: PathBuf) -> io::Result<Vec<T>> {
+        let json = match fs::read(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        serde_json::from_slice(&json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl StorageDriver for FileDriver {
+    fn append_audit(&self, entry: &AuditEntry) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(self.audit_path())?;
+        let line = serde_json::to_string(entry)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        writeln!(file, "{}", line)
+    }
+
+    fn load_audit(&self, limit: usize) -> io::Result<Vec<AuditEntry>> {
+        let file = match fs::File::open(self.audit_path()) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut entries: Vec<AuditEntry> = BufReader::new(file)
+            .lines()
+            .filter_map(|line| line.ok())
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect();
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+        Ok(entries)
+    }
+
+    fn save_claims(&self, changed: &[StoredClaim], removed: &[u64]) -> io::Result<()> {
+        let mut claims: BTreeMap<u64, StoredClaim> =
+            self.read_claims()?.0.into_iter().map(|claim| (claim.id, claim)).collect();
+        for id in removed {
+            claims.remove(id);
+        }
+        for claim in changed {
+            claims.insert(claim.id, claim.clone());
+        }
+
+        let mut json = b"[".to_vec();
+        let mut index = HashMap::new();
+        for (position, claim) in claims.values().enumerate() {
+            json.extend_from_slice(if position == 0 { b"\n" } else { b",\n" });
+            let start = json.len() as u64;
+            serde_json::to_writer(&mut json, claim)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            index.insert(claim.id, start..json.len() as u64);
+        }
+        json.extend_from_slice(b"\n]\n");
+
+        let temp = self.write_temp(&self.claims_path(), &json)?;
+        let mut claim_index = self.claim_index.lock().unwrap();
+        fs::rename(temp, self.claims_path())?;
+        *claim_index = Some(index);
+        Ok(())
+    }
+
+    fn load_claims(&self) -> io::Result<Vec<StoredClaim>> {
+        let (claims, index) = self.read_claims()?;
+        *self.claim_index.lock().unwrap() = index;
+        Ok(claims)
+    }
+
+    fn load_claims_by_id(&self, ids: &[u64]) -> io::Result<Vec<StoredClaim>> {
+        // Open the file while holding the index, so both are the same
+        // version even if the writer swaps in a new file meanwhile.
+        let (mut file, ranges) = {
+            let index = self.claim_index.lock().unwrap();
+            let index = match index.as_ref() {
+                Some(index) => index,
+                None => {
+                    let mut claims = self.read_claims()?.0;
+                    claims.retain(|claim| ids.contains(&claim.id));
+                    return Ok(claims);
+                }
+            };
+            let ranges: Vec<Range<u64>> =
+                ids.iter().filter_map(|id| index.get(id)).cloned().collect();
+            if ranges.is_empty() {
+                return Ok(Vec::new());
+            }
+            (fs::File::open(self.claims_path())?, ranges)
+        };
+
+        let mut claims = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let mut json = vec![0; (range.end - range.start) as usize];
+            file.seek(SeekFrom::Start(range.start))?;
+            file.read_exact(&mut json)?;
+            let claim = serde_json::from_slice(&json)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            claims.push(claim);
+        }
+        Ok(claims)
+    }
+
+    fn save_players(&self, players: &[StoredPlayer]) -> io::Result<()> {
+        self.replace(self.players_path(), &players)
+    }
+
+    fn load_players(&self) -> io::Result<Vec<StoredPlayer>> {
+        self.read(self.players_path())
+    }
+}
+
+pub struct Storage {
+    driver: Arc<dyn StorageDriver>,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            driver: Arc::new(MemoryDriver),
+        }
+    }
+}
+
+impl Storage {
+    pub fn open(config: &StorageConfig) -> Self {
+        let driver: Arc<dyn StorageDriver> = match config.backend {
+            StorageBackend::Memory => Arc::new(MemoryDriver),
+            StorageBackend::File => Arc::new(FileDriver {
+                dir: PathBuf::from(&config.directory),
+                claim_index: Mutex::default(),
+            }),
+        };
+        Self { driver }
+    }
+
+    pub fn driver(&self) -> &dyn StorageDriver {
+        self.driver.as_ref()
+    }
+
+    /// The driver, for handing to a background writer.
+    pub fn shared_driver(&self) -> Arc<dyn StorageDriver> {
+        self.driver.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use feather_core::world::ChunkPosition;
+    use feather_server::world::WorldId;
+
+    use super::*;
+    use crate::claims::{ClaimKey, ClaimManager};
+
+    #[test]
+    fn single_claims_are_read_back_from_the_claims_file() {
+        let dir = std::env::temp_dir().join(format!("land-claiming-{}", std::process::id()));
+        let driver = FileDriver {
+            dir: dir.clone(),
+            claim_index: Mutex::default(),
+        };
+        let claims = ClaimManager::default();
+        for x in [0, 5, 10] {
+            let key = ClaimKey::new(WorldId::default(), ChunkPosition::new(x, 0));
+            claims.insert_if_vacant(key, "bob");
+        }
+        let stored: Vec<StoredClaim> =
+            claims.all().iter().map(|claim| StoredClaim::new(claim, "world")).collect();
+        driver.save_claims(&stored, &[]).unwrap();
+        driver.save_claims(&[], &[stored[0].id]).unwrap();
+
+        let ids: Vec<u64> = stored.iter().map(|claim| claim.id).collect();
+        let found: Vec<u64> =
+            driver.load_claims_by_id(&ids).unwrap().iter().map(|claim| claim.id).collect();
+        assert_eq!(found, ids[1..]);
+        assert_eq!(driver.load_claims().unwrap().len(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
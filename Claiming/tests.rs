@@ -0,0 +1,239 @@
+use feather_core::world::ChunkPosition;
+
+use crate::api::ClaimEvent;
+use crate::claims::{self, ClaimKey, ClaimManager, ReshapeError};
+use crate::flags::ClaimFlag;
+use crate::interactions::Interaction;
+use crate::mock::{MockPlayer, RecordingSink};
+use crate::protection::{self, Action, Decision};
+use crate::trust::{self, TrustLevel};
+
+fn claimed_by(owner: &MockPlayer, chunks: &[(i32, i32)]) -> (ClaimManager, RecordingSink) {
+    let claims = ClaimManager::default();
+    let events = RecordingSink::default();
+    for &(x, z) in chunks {
+        let key = ClaimKey::of(&owner.clone().in_chunk(x, z));
+        assert!(claims.claim(key, &owner.name, &events).is_some());
+    }
+    events.take();
+    (claims, events)
+}
+
+fn key(x: i32, z: i32) -> ClaimKey {
+    ClaimKey::of(&MockPlayer::new("anyone").in_chunk(x, z))
+}
+
+#[test]
+fn claiming_next_to_an_own_claim_grows_it() {
+    let alice = MockPlayer::new("alice");
+    let (claims, events) = claimed_by(&alice, &[(0, 0)]);
+
+    assert!(claims.claim(key(1, 0), "alice", &events).is_some());
+    assert_eq!(claims.id_at(key(0, 0)), claims.id_at(key(1, 0)));
+    assert_eq!(claims.counts(), (1, 2));
+
+    let events = events.take();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(&events[0], ClaimEvent::Created { owner, .. } if owner == "alice"));
+}
+
+#[test]
+fn claimed_chunks_cannot_be_claimed_again() {
+    let alice = MockPlayer::new("alice");
+    let (claims, events) = claimed_by(&alice, &[(0, 0)]);
+
+    assert!(claims.claim(key(0, 0), "bob", &events).is_none());
+    assert!(claims.is_owned_by(key(0, 0), "alice"));
+    assert!(events.take().is_empty());
+}
+
+#[test]
+fn claims_of_different_owners_stay_apart() {
+    let alice = MockPlayer::new("alice");
+    let (claims, events) = claimed_by(&alice, &[(0, 0)]);
+
+    assert!(claims.claim(key(1, 0), "bob", &events).is_some());
+    assert_ne!(claims.id_at(key(0, 0)), claims.id_at(key(1, 0)));
+}
+
+#[test]
+fn unclaiming_the_middle_splits_a_claim() {
+    let alice = MockPlayer::new("alice");
+    let (claims, events) = claimed_by(&alice, &[(0, 0), (1, 0), (2, 0)]);
+
+    let removed = claims.unclaim(key(1, 0), &events).unwrap();
+    assert_eq!(removed.chunks.len(), 3);
+    assert!(!claims.contains(key(1, 0)));
+    assert_ne!(claims.id_at(key(0, 0)), claims.id_at(key(2, 0)));
+    assert_eq!(claims.counts(), (2, 2));
+
+    let events = events.take();
+    assert!(matches!(&events[..], [ClaimEvent::Deleted { owner, .. }] if owner == "alice"));
+    assert!(claims.unclaim(key(1, 0), &RecordingSink::default()).is_none());
+}
+
+#[test]
+fn merging_claims_keeps_the_absorbed_owner_as_a_manager() {
+    let alice = MockPlayer::new("alice");
+    let (claims, events) = claimed_by(&alice, &[(0, 0)]);
+    claims.claim(key(1, 0), "bob", &events);
+    claims.claim(key(5, 0), "carol", &events);
+    let (into, from) = (claims.id_at(key(0, 0)).unwrap(), claims.id_at(key(1, 0)).unwrap());
+    let far = claims.id_at(key(5, 0)).unwrap();
+    claims.update(key(1, 0), |claim| claim.heirs.push(String::from("dave")));
+
+    assert_eq!(claims.merge_claims(into, far).err(), Some(ReshapeError::NotAdjacent));
+    assert_eq!(claims.merge_claims(into, into).err(), Some(ReshapeError::SameClaim));
+    assert_eq!(claims.merge_claims(into, from).unwrap().owner, "bob");
+
+    let merged = claims.get(key(1, 0)).unwrap();
+    assert_eq!(merged.id, into);
+    assert_eq!(merged.owner, "alice");
+    assert_eq!(merged.trust_of("bob"), Some(TrustLevel::Manage));
+    assert!(merged.heirs.is_empty());
+}
+
+#[test]
+fn splitting_carves_the_selection_into_its_own_claim() {
+    let alice = MockPlayer::new("alice");
+    let (claims, _) = claimed_by(&alice, &[(0, 0), (1, 0), (2, 0)]);
+    claims.update(key(0, 0), |claim| claim.trusted.insert("bob".to_owned(), TrustLevel::Build));
+    let id = claims.id_at(key(0, 0)).unwrap();
+
+    let whole = [key(0, 0).chunk, key(1, 0).chunk, key(2, 0).chunk];
+    assert_eq!(claims.split_claim(id, &whole).err(), Some(ReshapeError::NothingToSplit));
+    let piece = claims.split_claim(id, &[key(1, 0).chunk]).unwrap();
+
+    assert_eq!(claims.id_at(key(1, 0)), Some(piece));
+    assert_eq!(claims.counts(), (3, 3));
+    assert_eq!(claims.get(key(1, 0)).unwrap().trust_of("bob"), Some(TrustLevel::Build));
+}
+
+#[test]
+fn trust_counts_the_owner_direct_and_public_trust() {
+    let alice = MockPlayer::new("alice");
+    let (claims, _) = claimed_by(&alice, &[(0, 0)]);
+    claims.update(key(0, 0), |claim| {
+        claim.trusted.insert("bob".to_owned(), TrustLevel::Build);
+        claim.trusted.insert(trust::PUBLIC.to_owned(), TrustLevel::Access);
+        claim.banned.push("mallory".to_owned());
+    });
+
+    let claim = claims.get(key(0, 0)).unwrap();
+    let level = |player: &str| claim.trust_with(player, |subject| subject == trust::PUBLIC);
+    assert_eq!(level("alice"), Some(TrustLevel::Manage));
+    assert_eq!(level("bob"), Some(TrustLevel::Build));
+    assert_eq!(level("carol"), Some(TrustLevel::Access));
+    assert_eq!(level("mallory"), None);
+    assert_eq!(claim.trust_of("carol"), None);
+}
+
+#[test]
+fn flags_fall_back_to_the_default() {
+    let alice = MockPlayer::new("alice");
+    let (claims, _) = claimed_by(&alice, &[(0, 0)]);
+
+    assert_eq!(claims.flag_at(key(5, 5), ClaimFlag::Pvp, || true), None);
+    assert_eq!(claims.flag_at(key(0, 0), ClaimFlag::Pvp, || true), Some(true));
+
+    claims.update(key(0, 0), |claim| claim.flags.insert(ClaimFlag::Pvp, false));
+    assert_eq!(claims.flag_at(key(0, 0), ClaimFlag::Pvp, || true), Some(false));
+    assert_eq!(claims.flag_at(key(0, 0), ClaimFlag::MobSpawning, || false), Some(false));
+}
+
+#[test]
+fn building_needs_build_trust() {
+    let alice = MockPlayer::new("alice");
+    let (claims, _) = claimed_by(&alice, &[(0, 0)]);
+    let claim = claims.get(key(0, 0)).unwrap();
+
+    let decide = |level| protection::decide(&claim, level, Action::Build);
+    assert_eq!(decide(None), Decision::Untrusted);
+    assert_eq!(decide(Some(TrustLevel::Container)), Decision::Untrusted);
+    assert_eq!(decide(Some(TrustLevel::Build)), Decision::Allow);
+    assert_eq!(decide(Some(TrustLevel::Manage)), Decision::Allow);
+}
+
+#[test]
+fn access_trust_cannot_break_or_place_blocks() {
+    let alice = MockPlayer::new("alice");
+    let (claims, _) = claimed_by(&alice, &[(0, 0)]);
+    claims.update(key(0, 0), |claim| {
+        claim.trusted.insert(trust::PUBLIC.to_owned(), TrustLevel::Access);
+    });
+    let claim = claims.get(key(0, 0)).unwrap();
+
+    let shopper = claim.trust_with("carol", |subject| subject == trust::PUBLIC);
+    assert_eq!(shopper, Some(TrustLevel::Access));
+    assert_eq!(protection::decide(&claim, shopper, Action::Build), Decision::Untrusted);
+    assert_eq!(protection::decide(&claim, shopper, Action::Interact(None)), Decision::Allow);
+}
+
+#[test]
+fn interactions_follow_the_claims_matrix() {
+    let alice = MockPlayer::new("alice");
+    let (claims, _) = claimed_by(&alice, &[(0, 0)]);
+    let containers = Action::Interact(Some(Interaction::Containers));
+
+    let claim = claims.get(key(0, 0)).unwrap();
+    assert_eq!(protection::decide(&claim, None, Action::Interact(None)), Decision::Untrusted);
+    assert_eq!(
+        protection::decide(&claim, Some(TrustLevel::Access), Action::Interact(None)),
+        Decision::Allow
+    );
+    assert_eq!(
+        protection::decide(&claim, Some(TrustLevel::Access), containers),
+        Decision::Forbidden { level: TrustLevel::Access, action: Interaction::Containers }
+    );
+
+    claims.update(key(0, 0), |claim| {
+        let row = claim.interactions.entry(TrustLevel::Access).or_default();
+        row.insert(Interaction::Containers, true);
+        let row = claim.interactions.entry(TrustLevel::Build).or_default();
+        row.insert(Interaction::Doors, false);
+    });
+    let claim = claims.get(key(0, 0)).unwrap();
+    let doors = Action::Interact(Some(Interaction::Doors));
+    assert_eq!(protection::decide(&claim, Some(TrustLevel::Access), containers), Decision::Allow);
+    assert_eq!(
+        protection::decide(&claim, Some(TrustLevel::Build), doors),
+        Decision::Forbidden { level: TrustLevel::Build, action: Interaction::Doors }
+    );
+    assert_eq!(protection::decide(&claim, Some(TrustLevel::Manage), doors), Decision::Allow);
+}
+
+#[test]
+fn players_stand_in_the_chunk_they_were_put_in() {
+    let alice = MockPlayer::new("alice").in_chunk(-1, 2);
+
+    assert_eq!(ClaimKey::of(&alice).chunk, ChunkPosition::new(-1, 2));
+}
+
+#[test]
+fn heirs_are_never_everyone_the_admin_owner_or_a_group() {
+    assert!(claims::is_player_name("Alice_99"));
+    for name in [trust::PUBLIC, claims::ADMIN_OWNER, "@town", "", "a_name_far_too_long"] {
+        assert!(!claims::is_player_name(name), "{}", name);
+    }
+}
+
+#[test]
+fn replacing_every_claim_reports_the_chunks_that_changed_hands() {
+    let (claims, events) = claimed_by(&MockPlayer::new("alice"), &[(0, 0), (1, 0)]);
+    let (replacement, _) = claimed_by(&MockPlayer::new("bob"), &[(1, 0), (2, 0)]);
+
+    assert_eq!(claims.replace_all(replacement.all(), &events), 1);
+    let events = events.take();
+    let count = |deleted: bool, owner: &str| {
+        events
+            .iter()
+            .filter(|event| match event {
+                ClaimEvent::Deleted { owner: by, .. } => deleted && by == owner,
+                ClaimEvent::Created { owner: by, .. } => !deleted && by == owner,
+                _ => false,
+            })
+            .count()
+    };
+    assert_eq!((count(true, "alice"), count(false, "bob")), (2, 2));
+    assert_eq!(events.len(), 4);
+}
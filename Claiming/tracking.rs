@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::claims::{ClaimId, ClaimKey};
+
+/// The claim a player is standing in, the chunk they are in and who owns it.
+#[derive(Clone)]
+pub struct ClaimPresence {
+    pub id: ClaimId,
+    pub key: ClaimKey,
+    pub owner: String,
+}
+
+pub struct Crossing {
+    pub left: Option<ClaimPresence>,
+    pub entered: Option<ClaimPresence>,
+}
+
+/// Remembers which claim each online player is in, so entry and exit
+/// handling only runs when that actually changes.
+#[derive(Default)]
+pub struct ClaimTracker {
+    current: Mutex<HashMap<String, ClaimPresence>>,
+}
+
+impl ClaimTracker {
+    /// Records where `player` now stands and returns the crossing if they
+    /// moved into a different claim or into or out of the wilderness.
+    pub fn update(&self, player: &str, presence: Option<ClaimPresence>) -> Option<Crossing> {
+        let mut current = self.current.lock().unwrap();
+
+        let previous_id = current.get(player).map(|previous| previous.id);
+        let same_claim = previous_id == presence.as_ref().map(|presence| presence.id);
+
+        let left = match presence.clone() {
+            Some(presence) => current.insert(player.to_owned(), presence),
+            None => current.remove(player),
+        };
+
+        if same_claim {
+            None
+        } else {
+            Some(Crossing {
+                left,
+                entered: presence,
+            })
+        }
+    }
+
+    pub fn current(&self, player: &str) -> Option<ClaimPresence> {
+        self.current.lock().unwrap().get(player).cloned()
+    }
+
+    pub fn forget(&self, player: &str) {
+        self.current.lock().unwrap().remove(player);
+    }
+}
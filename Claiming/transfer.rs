@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::claims::ClaimKey;
+
+/// How long a recipient has to accept a claim transfer.
+const OFFER_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct TransferOffer {
+    pub key: ClaimKey,
+    pub from: String,
+    expires_at: Instant,
+}
+
+/// Claim transfers waiting on the recipient's `/claim accept`, keyed by
+/// recipient name. A newer offer replaces an older one.
+#[derive(Default)]
+pub struct PendingTransfers {
+    offers: Mutex<HashMap<String, TransferOffer>>,
+}
+
+impl PendingTransfers {
+    pub fn offer(&self, recipient: &str, key: ClaimKey, from: &str) {
+        let offer = TransferOffer {
+            key,
+            from: from.to_owned(),
+            expires_at: Instant::now() + OFFER_TIMEOUT,
+        };
+        self.offers.lock().unwrap().insert(recipient.to_owned(), offer);
+    }
+
+    pub fn take(&self, recipient: &str) -> Option<TransferOffer> {
+        self.offers
+            .lock()
+            .unwrap()
+            .remove(recipient)
+            .filter(|offer| offer.expires_at > Instant::now())
+    }
+}
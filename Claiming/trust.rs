@@ -0,0 +1,204 @@
+use std::sync::Arc;
+use feather_server::command::{Command, CommandContext, CommandSender};
+use serde::{Deserialize, Serialize};
+
+use crate::args::{split_location, ArgKind, ArgReader, Syntax};
+use crate::claims::ClaimKey;
+use crate::ratelimit::LimitedAction;
+use crate::LandClaiming;
+
+/// Trust given to everyone is stored under this name. Player names cannot
+/// contain `*`.
+pub const PUBLIC: &str = "*";
+
+/// How much a trusted player may do in a claim. Each level includes the
+/// ones below it; owners always have `Manage`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustLevel {
+    /// Doors, buttons, levers and the like.
+    Access,
+    /// Chests and other containers.
+    Container,
+    /// Placing and breaking blocks.
+    Build,
+    /// Trusting others and changing claim settings.
+    Manage,
+}
+
+impl TrustLevel {
+    pub const ALL: [TrustLevel; 4] =
+        [TrustLevel::Access, TrustLevel::Container, TrustLevel::Build, TrustLevel::Manage];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "access" => Some(TrustLevel::Access),
+            "container" => Some(TrustLevel::Container),
+            "build" => Some(TrustLevel::Build),
+            "manage" => Some(TrustLevel::Manage),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TrustLevel::Access => "access",
+            TrustLevel::Container => "container",
+            TrustLevel::Build => "build",
+            TrustLevel::Manage => "manage",
+        }
+    }
+}
+
+/// The name a `/trust` target is stored under: `public` becomes `PUBLIC`,
+/// while players and `@<group>` are kept as typed. Groups are looked up
+/// when trust is checked, so joining or leaving one takes effect at once.
+pub fn subject_of(target: &str) -> String {
+    if target.eq_ignore_ascii_case("public") {
+        String::from(PUBLIC)
+    } else {
+        target.to_owned()
+    }
+}
+
+/// Trusts or untrusts `target` in the claim at `key` and tells `sender`
+/// how it went.
+fn change_trust(
+    plugin: &LandClaiming,
+    sender: &dyn CommandSender,
+    key: ClaimKey,
+    target: &str,
+    level: Option<TrustLevel>,
+) {
+    let player = sender.as_player();
+    if let Some(player) = player {
+        if !plugin.within_rate_limit(player, LimitedAction::Trust) {
+            return;
+        }
+    }
+
+    let subject = subject_of(target);
+    if let Err(error) = plugin.set_trust(player, key, &subject, level) {
+        return plugin.tell_error(sender, error);
+    }
+
+    let shown = plugin.trust_subject_label(sender, &subject);
+    match level {
+        Some(level) => plugin.tell_sender(
+            sender,
+            "trust.added",
+            &[("player", &shown), ("level", &level.name())],
+        ),
+        None => plugin.tell_sender(sender, "trust.removed", &[("player", &shown)]),
+    }
+}
+
+pub struct TrustCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl TrustCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for TrustCommand {
+    fn execute(&self, ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let usage = self.get_usage();
+        let (args, location) = split_location(&args, 1);
+        let key = match self.plugin.command_target(&ctx.game, sender, location, &usage) {
+            Some(key) => key,
+            None => return,
+        };
+
+        let mut reader = ArgReader::new(args);
+        let target = reader.word();
+        let level = if reader.rest().is_empty() {
+            Some(TrustLevel::Build)
+        } else {
+            reader.trust_level()
+        };
+        match (target, level, reader.rest()) {
+            (Some(target), Some(level), []) => {
+                change_trust(&self.plugin, sender, key, target, Some(level))
+            }
+            _ => self.plugin.tell_sender(sender, "general.usage", &[("usage", &usage)]),
+        }
+    }
+
+    fn tab_complete(
+        &self,
+        ctx: &mut CommandContext,
+        sender: &dyn CommandSender,
+        args: Vec<String>,
+    ) -> Vec<String> {
+        const SYNTAX: Syntax = Syntax::Args(&[ArgKind::TrustSubject, ArgKind::TrustLevel]);
+        self.plugin.complete(&ctx.game, sender, &SYNTAX, &args)
+    }
+
+    fn get_name(&self) -> String {
+        String::from("trust")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from(
+            "/trust <player|public|@group> [access|container|build|manage] [<world> <x> <z>]",
+        )
+    }
+
+    fn get_help(&self) -> String {
+        String::from(
+            "Trusts a player, everyone or a whole group in the claim you are standing in or \
+             at a location.",
+        )
+    }
+}
+
+pub struct UntrustCommand {
+    plugin: Arc<LandClaiming>,
+}
+
+impl UntrustCommand {
+    pub fn new(plugin: Arc<LandClaiming>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl Command for UntrustCommand {
+    fn execute(&self, ctx: &mut CommandContext, sender: &dyn CommandSender, args: Vec<String>) {
+        let usage = self.get_usage();
+        let (args, location) = split_location(&args, 1);
+        let key = match self.plugin.command_target(&ctx.game, sender, location, &usage) {
+            Some(key) => key,
+            None => return,
+        };
+
+        match args {
+            [target] => change_trust(&self.plugin, sender, key, target, None),
+            _ => self.plugin.tell_sender(sender, "general.usage", &[("usage", &usage)]),
+        }
+    }
+
+    fn tab_complete(
+        &self,
+        ctx: &mut CommandContext,
+        sender: &dyn CommandSender,
+        args: Vec<String>,
+    ) -> Vec<String> {
+        const SYNTAX: Syntax = Syntax::Args(&[ArgKind::TrustSubject]);
+        self.plugin.complete(&ctx.game, sender, &SYNTAX, &args)
+    }
+
+    fn get_name(&self) -> String {
+        String::from("untrust")
+    }
+
+    fn get_usage(&self) -> String {
+        String::from("/untrust <player|public|@group> [<world> <x> <z>]")
+    }
+
+    fn get_help(&self) -> String {
+        String::from("Takes away trust given in the claim you are standing in or at a location.")
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use serde::Deserialize;
+
+/// How often claims are checked for upkeep that has fallen due.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Upkeep is charged once per this period.
+pub const UPKEEP_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpkeepCharge {
+    #[default]
+    ClaimBlocks,
+    Currency,
+}
+
+/// Where a claim stands with its upkeep.
+#[derive(Clone, Copy)]
+pub struct UpkeepState {
+    /// When the next charge falls due.
+    pub paid_until: SystemTime,
+    /// When the owner first failed to pay, if they still have not.
+    pub overdue_since: Option<SystemTime>,
+}
+
+impl UpkeepState {
+    /// A claim's first period is free.
+    pub fn starting_now() -> Self {
+        Self {
+            paid_until: SystemTime::now() + UPKEEP_PERIOD,
+            overdue_since: None,
+        }
+    }
+
+    /// Moves the due date on by one period after a successful charge. A
+    /// claim that fell far behind, for example while the server was down,
+    /// is not charged for every period it missed.
+    pub fn paid(&mut self, now: SystemTime) {
+        self.paid_until = self.paid_until.max(now) + UPKEEP_PERIOD;
+        self.overdue_since = None;
+    }
+}
+
+/// Whole days left from `now` until the grace period that began at
+/// `overdue_since` ends, rounded up.
+pub fn grace_days_left(overdue_since: SystemTime, grace: Duration, now: SystemTime) -> u64 {
+    let ends = overdue_since + grace;
+    let left = ends.duration_since(now).unwrap_or_default().as_secs();
+    (left + UPKEEP_PERIOD.as_secs() - 1) / UPKEEP_PERIOD.as_secs()
+}
+
+/// Remembers claims lost to unpaid upkeep until their owners next log in.
+#[derive(Default)]
+pub struct Upkeep {
+    lost: Mutex<HashMap<String, Vec<(i32, i32)>>>,
+}
+
+impl Upkeep {
+    pub fn record_lost(&self, owner: &str, chunk: (i32, i32)) {
+        self.lost.lock().unwrap().entry(owner.to_owned()).or_default().push(chunk);
+    }
+
+    pub fn take_lost(&self, owner: &str) -> Vec<(i32, i32)> {
+        self.lost.lock().unwrap().remove(owner).unwrap_or_default()
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::{HashSet, VecDeque};
+use feather_core::world::ChunkPosition;
+
+use crate::claims::{ClaimDenial, CHUNK_SIZE};
+use crate::config::{BannedRegion, ValidationConfig};
+use crate::expansion;
+
+/// Chunks an enclosure search may cover before the land it started in
+/// counts as open rather than walled in.
+const ENCLOSURE_SEARCH_LIMIT: usize = 4096;
+
+/// Rules every new claim chunk has to pass, whether it comes from
+/// `/claim`, an expansion, auto-claim, an import or another plugin.
+pub struct ClaimRules<'a> {
+    pub config: &'a ValidationConfig,
+    pub world: &'a str,
+    /// Half the side of the world border in blocks, centred on 0, 0.
+    pub border_radius: Option<u32>,
+}
+
+impl ClaimRules<'_> {
+    /// Checks `chunks` about to be claimed for `owner`. `owner_at` looks up
+    /// who holds a chunk in the same world. Chunks `owner` already holds
+    /// are not an overlap.
+    pub fn check(
+        &self,
+        owner: &str,
+        chunks: &[ChunkPosition],
+        owner_at: impl Fn(ChunkPosition) -> Option<String>,
+    ) -> Result<(), ClaimDenial> {
+        for &chunk in chunks {
+            if let Some(radius) = self.border_radius {
+                if !inside_border(chunk, radius) {
+                    return Err(ClaimDenial::OutsideBorder { radius });
+                }
+            }
+            if let Some(region) = self.banned_region(chunk) {
+                return Err(ClaimDenial::BannedRegion { name: region.name.clone() });
+            }
+        }
+
+        for &chunk in chunks {
+            match owner_at(chunk) {
+                Some(other) if other != owner => return Err(ClaimDenial::Overlap { owner: other }),
+                _ => {}
+            }
+        }
+
+        self.check_buffer(owner, chunks, &owner_at)?;
+        if self.config.prevent_enclosure {
+            check_enclosure(owner, chunks, &owner_at)?;
+        }
+        Ok(())
+    }
+
+    fn check_buffer(
+        &self,
+        owner: &str,
+        chunks: &[ChunkPosition],
+        owner_at: &impl Fn(ChunkPosition) -> Option<String>,
+    ) -> Result<(), ClaimDenial> {
+        let buffer = self.config.buffer_chunks as i32;
+        if buffer == 0 {
+            return Ok(());
+        }
+        for &chunk in chunks {
+            for x in chunk.x - buffer..=chunk.x + buffer {
+                for z in chunk.z - buffer..=chunk.z + buffer {
+                    match owner_at(ChunkPosition::new(x, z)) {
+                        Some(other) if other != owner => {
+                            let buffer = self.config.buffer_chunks;
+                            return Err(ClaimDenial::TooClose { owner: other, buffer });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn banned_region(&self, chunk: ChunkPosition) -> Option<&BannedRegion> {
+        self.config
+            .banned_regions
+            .iter()
+            .filter(|region| region.world == self.world)
+            .find(|region| region.touches(chunk))
+    }
+}
+
+/// Refuses `chunks` if, together with the land `owner` already holds, they
+/// would wall in someone else's claim, leaving its owner no way out but
+/// across `owner`'s land.
+fn check_enclosure(
+    owner: &str,
+    chunks: &[ChunkPosition],
+    owner_at: &impl Fn(ChunkPosition) -> Option<String>,
+) -> Result<(), ClaimDenial> {
+    let new: HashSet<ChunkPosition> = chunks.iter().copied().collect();
+    let is_wall =
+        |chunk: ChunkPosition| new.contains(&chunk) || owner_at(chunk).as_deref() == Some(owner);
+
+    let mut seen = HashSet::new();
+    for start in chunks.iter().flat_map(|&chunk| expansion::neighbours(chunk)) {
+        if seen.contains(&start) || is_wall(start) {
+            continue;
+        }
+        if let Some(enclosed) = enclosed_owner(start, &is_wall, owner_at, &mut seen) {
+            return Err(ClaimDenial::Encloses { owner: enclosed });
+        }
+    }
+    Ok(())
+}
+
+/// Fills outwards from `start` up to the walls. If the area is closed off,
+/// returns someone whose claim is inside it.
+fn enclosed_owner(
+    start: ChunkPosition,
+    is_wall: &impl Fn(ChunkPosition) -> bool,
+    owner_at: &impl Fn(ChunkPosition) -> Option<String>,
+    seen: &mut HashSet<ChunkPosition>,
+) -> Option<String> {
+    let mut queue = VecDeque::from([start]);
+    seen.insert(start);
+    let mut area = 0;
+    let mut enclosed = None;
+
+    while let Some(chunk) = queue.pop_front() {
+        area += 1;
+        if area > ENCLOSURE_SEARCH_LIMIT {
+            return None;
+        }
+        if enclosed.is_none() {
+            enclosed = owner_at(chunk);
+        }
+        for next in expansion::neighbours(chunk) {
+            if !seen.contains(&next) && !is_wall(next) {
+                seen.insert(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    enclosed
+}
+
+/// Whether every block of `chunk` is inside a border `radius` blocks from
+/// the centre of the world.
+fn inside_border(chunk: ChunkPosition, radius: u32) -> bool {
+    let radius = i64::from(radius);
+    let (min_x, min_z) = (i64::from(chunk.x * CHUNK_SIZE), i64::from(chunk.z * CHUNK_SIZE));
+    let size = i64::from(CHUNK_SIZE);
+
+    min_x >= -radius && min_z >= -radius && min_x + size <= radius && min_z + size <= radius
+}
+
+impl BannedRegion {
+    /// Whether any block of `chunk` lies inside the region.
+    fn touches(&self, chunk: ChunkPosition) -> bool {
+        let radius = i64::from(self.radius);
+        let (min_x, min_z) = (i64::from(chunk.x * CHUNK_SIZE), i64::from(chunk.z * CHUNK_SIZE));
+        let max = i64::from(CHUNK_SIZE) - 1;
+        let (x, z) = (i64::from(self.x), i64::from(self.z));
+
+        let overlaps_x = min_x <= x + radius && min_x + max >= x - radius;
+        let overlaps_z = min_z <= z + radius && min_z + max >= z - radius;
+        overlaps_x && overlaps_z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn closing_a_ring_around_another_claim_is_refused() {
+        let mut owners = HashMap::new();
+        owners.insert(ChunkPosition::new(0, 0), "bob".to_owned());
+        for (x, z) in [(-1, -1), (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1)] {
+            owners.insert(ChunkPosition::new(x, z), "mallory".to_owned());
+        }
+        let owner_at = |chunk| owners.get(&chunk).cloned();
+        let config = ValidationConfig::default();
+        let rules = ClaimRules { config: &config, world: "world", border_radius: None };
+
+        let gap = [ChunkPosition::new(-1, 0)];
+        assert_eq!(
+            rules.check("mallory", &gap, owner_at),
+            Err(ClaimDenial::Encloses { owner: "bob".to_owned() })
+        );
+        assert_eq!(rules.check("bob", &gap, owner_at), Ok(()));
+        assert_eq!(rules.check("mallory", &[ChunkPosition::new(-2, 0)], owner_at), Ok(()));
+    }
+}
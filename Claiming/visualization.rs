@@ -0,0 +1,144 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use feather_core::blocks::BlockId;
+use feather_core::world::ChunkPosition;
+use feather_server::{player::Player, util::BlockPosition, Game};
+
+use crate::claims::CHUNK_SIZE;
+
+/// How long an outline stays visible before the real blocks are restored.
+const OUTLINE_DURATION: Duration = Duration::from_secs(10);
+
+/// Distance between gold markers along each edge of the outline.
+const EDGE_MARKER_SPACING: i32 = 4;
+
+/// Sends temporary fake blocks to players outlining the outer border of a
+/// claim.
+///
+/// Outlines follow the player's height as they move and are reverted once
+/// they expire, so nothing is ever written to the world.
+#[derive(Default)]
+pub struct ClaimVisualizer {
+    active: Mutex<HashMap<String, Outline>>,
+}
+
+struct Outline {
+    chunks: Vec<ChunkPosition>,
+    y: i32,
+    blocks: Vec<BlockPosition>,
+    expires_at: Instant,
+}
+
+impl ClaimVisualizer {
+    pub fn show(&self, player: &Player, chunks: Vec<ChunkPosition>) {
+        let mut active = self.active.lock().unwrap();
+
+        if let Some(previous) = active.remove(player.name()) {
+            revert(player, &previous);
+        }
+
+        let y = ground_level(player);
+        let outline = Outline {
+            blocks: draw(player, &chunks, y),
+            chunks,
+            y,
+            expires_at: Instant::now() + OUTLINE_DURATION,
+        };
+        active.insert(player.name().to_owned(), outline);
+    }
+
+    pub fn refresh(&self, player: &Player) {
+        let mut active = self.active.lock().unwrap();
+
+        let outline = match active.get_mut(player.name()) {
+            Some(outline) => outline,
+            None => return,
+        };
+
+        if outline.expires_at <= Instant::now() {
+            revert(player, outline);
+            active.remove(player.name());
+            return;
+        }
+
+        let y = ground_level(player);
+        if y != outline.y {
+            revert(player, outline);
+            outline.y = y;
+            outline.blocks = draw(player, &outline.chunks, y);
+        }
+    }
+
+    pub fn expire(&self, game: &Game) {
+        let now = Instant::now();
+
+        self.active.lock().unwrap().retain(|name, outline| {
+            if outline.expires_at > now {
+                return true;
+            }
+
+            if let Some(player) = game.server.player(name) {
+                revert(player, outline);
+            }
+            false
+        });
+    }
+}
+
+fn ground_level(player: &Player) -> i32 {
+    player.position().y.floor() as i32 - 1
+}
+
+/// Outlines the edges of `chunks` that do not border another chunk of the
+/// same claim, with glowstone where each chunk's edge ends.
+fn draw(player: &Player, chunks: &[ChunkPosition], y: i32) -> Vec<BlockPosition> {
+    let claimed: HashSet<(i32, i32)> = chunks.iter().map(|chunk| (chunk.x, chunk.z)).collect();
+    let mut drawn = HashSet::new();
+    let mut blocks = Vec::new();
+
+    for chunk in chunks {
+        let min_x = chunk.x * CHUNK_SIZE;
+        let min_z = chunk.z * CHUNK_SIZE;
+        let max_x = min_x + CHUNK_SIZE - 1;
+        let max_z = min_z + CHUNK_SIZE - 1;
+
+        let north = !claimed.contains(&(chunk.x, chunk.z - 1));
+        let south = !claimed.contains(&(chunk.x, chunk.z + 1));
+        let west = !claimed.contains(&(chunk.x - 1, chunk.z));
+        let east = !claimed.contains(&(chunk.x + 1, chunk.z));
+
+        for x in min_x..=max_x {
+            for z in min_z..=max_z {
+                let on_x_edge = (x == min_x && west) || (x == max_x && east);
+                let on_z_edge = (z == min_z && north) || (z == max_z && south);
+                let at_end_x = x == min_x || x == max_x;
+                let at_end_z = z == min_z || z == max_z;
+
+                let block = if (on_x_edge && at_end_z) || (on_z_edge && at_end_x) {
+                    BlockId::glowstone()
+                } else if (on_x_edge && (z - min_z) % EDGE_MARKER_SPACING == 0)
+                    || (on_z_edge && (x - min_x) % EDGE_MARKER_SPACING == 0)
+                {
+                    BlockId::gold_block()
+                } else {
+                    continue;
+                };
+
+                if drawn.insert((x, z)) {
+                    let pos = BlockPosition::new(x, y, z);
+                    player.send_block_change(pos, block);
+                    blocks.push(pos);
+                }
+            }
+        }
+    }
+    blocks
+}
+
+fn revert(player: &Player, outline: &Outline) {
+    for &pos in &outline.blocks {
+        let actual = player.world().block_at(pos).unwrap_or_else(BlockId::air);
+        player.send_block_change(pos, actual);
+    }
+}
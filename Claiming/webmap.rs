@@ -0,0 +1,334 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use feather_server::Game;
+use serde_json::{json, Value};
+
+use crate::claims::{ClaimId, CHUNK_SIZE};
+use crate::config::WebMapConfig;
+use crate::residency::IndexedClaim;
+
+/// How often pending claim changes are folded into the export.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How many revisions a deleted claim is remembered for `/changes`.
+const REMOVAL_REVISIONS: u64 = 100;
+
+/// How long a client may take to send its request or read the answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The longest request line read. Anything after it is ignored.
+const MAX_REQUEST_LINE: u64 = 8 * 1024;
+
+#[derive(Default)]
+struct State {
+    revision: u64,
+    /// The latest feature for every claim, with the revision it last
+    /// changed in. Deleted claims stay as `None` for `REMOVAL_REVISIONS`
+    /// so `/changes` can report them.
+    features: HashMap<ClaimId, (u64, Option<Value>)>,
+    /// The newest revision whose removals have been forgotten.
+    forgotten: u64,
+}
+
+impl State {
+    fn collection(&self) -> Value {
+        let features: Vec<&Value> =
+            self.features.values().filter_map(|(_, feature)| feature.as_ref()).collect();
+        json!({ "type": "FeatureCollection", "revision": self.revision, "features": features })
+    }
+
+    /// What changed after revision `since`. A client further behind than
+    /// the removals still remembered gets `reset` and every claim, and has
+    /// to drop what it had.
+    fn changes_since(&self, since: u64) -> Value {
+        let reset = since < self.forgotten;
+        let mut updated = Vec::new();
+        let mut removed = Vec::new();
+        for (id, (revision, feature)) in &self.features {
+            if reset || *revision > since {
+                match feature {
+                    Some(feature) => updated.push(feature.clone()),
+                    None => removed.push(id.0),
+                }
+            }
+        }
+        json!({
+            "revision": self.revision,
+            "reset": reset,
+            "updated": updated,
+            "removed": if reset { Vec::new() } else { removed },
+        })
+    }
+
+    /// Forgets claims deleted more than `REMOVAL_REVISIONS` ago.
+    fn forget_removals(&mut self) {
+        let cutoff = match self.revision.checked_sub(REMOVAL_REVISIONS) {
+            Some(cutoff) => cutoff,
+            None => return,
+        };
+        let before = self.features.len();
+        self.features.retain(|_, (revision, feature)| feature.is_some() || *revision > cutoff);
+        if self.features.len() < before {
+            self.forgotten = self.forgotten.max(cutoff);
+        }
+    }
+}
+
+/// Publishes claim outlines as GeoJSON for web maps such as Dynmap or
+/// BlueMap, written to a file and optionally served over HTTP.
+///
+/// Claim changes only mark the export dirty; the export itself is rebuilt
+/// by a scheduled task once per refresh interval, and only the claims that
+/// actually changed get a new revision.
+pub struct WebMap {
+    state: Arc<RwLock<State>>,
+    dirty: Arc<AtomicBool>,
+    endpoint: Mutex<Option<Endpoint>>,
+}
+
+/// The running HTTP endpoint.
+struct Endpoint {
+    address: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Default for WebMap {
+    fn default() -> Self {
+        Self {
+            state: Arc::default(),
+            dirty: Arc::new(AtomicBool::new(true)),
+            endpoint: Mutex::default(),
+        }
+    }
+}
+
+impl WebMap {
+    /// A flag the claim listeners set whenever a claim changes.
+    pub fn dirty_flag(&self) -> Arc<AtomicBool> {
+        self.dirty.clone()
+    }
+
+    /// Starts the HTTP endpoint if one is configured. Changing the port
+    /// takes a restart.
+    pub fn start(&self, config: &WebMapConfig) {
+        let mut endpoint = self.endpoint.lock().unwrap();
+        if !config.enabled || config.http_port == 0 || endpoint.is_some() {
+            return;
+        }
+
+        let listener = match TcpListener::bind((config.http_bind.as_str(), config.http_port)) {
+            Ok(listener) => listener,
+            Err(err) => {
+                log::warn!("Could not start the LandClaiming web map endpoint: {}", err);
+                return;
+            }
+        };
+        let address = match listener.local_addr() {
+            Ok(address) => address,
+            Err(err) => {
+                log::warn!("Could not start the LandClaiming web map endpoint: {}", err);
+                return;
+            }
+        };
+        let state = self.state.clone();
+        let stopping = Arc::new(AtomicBool::new(false));
+        let stop = stopping.clone();
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Err(err) = respond(stream, &state) {
+                    log::debug!("Web map request failed: {}", err);
+                }
+            }
+        });
+        *endpoint = Some(Endpoint { address, stopping, thread });
+    }
+
+    /// Stops the HTTP endpoint and waits until its port is free again, so
+    /// the plugin can be enabled again without a restart.
+    pub fn stop(&self) {
+        let endpoint = match self.endpoint.lock().unwrap().take() {
+            Some(endpoint) => endpoint,
+            None => return,
+        };
+        endpoint.stopping.store(true, Ordering::SeqCst);
+
+        // The thread waits for a connection, so make one for it to see that
+        // it has to stop.
+        let mut address = endpoint.address;
+        if address.ip().is_unspecified() {
+            address.set_ip(Ipv4Addr::LOCALHOST.into());
+        }
+        if let Err(err) = TcpStream::connect_timeout(&address, REQUEST_TIMEOUT) {
+            log::warn!("Could not stop the LandClaiming web map endpoint: {}", err);
+            return;
+        }
+        if endpoint.thread.join().is_err() {
+            log::warn!("The LandClaiming web map endpoint stopped with a panic");
+        }
+    }
+
+    /// Rebuilds the export if anything changed. `claims` is only called
+    /// then, and has to list every claim, loaded or not; any it leaves out
+    /// are taken off the map.
+    pub fn refresh(
+        &self,
+        game: &Game,
+        config: &WebMapConfig,
+        claims: impl FnOnce() -> Vec<IndexedClaim>,
+    ) {
+        if !config.enabled {
+            return;
+        }
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        let mut current: HashMap<ClaimId, Value> = claims()
+            .iter()
+            .map(|claim| {
+                let world = game
+                    .server
+                    .world(claim.world)
+                    .map_or_else(|| String::from("?"), |world| world.name().to_owned());
+                (claim.id, feature(claim, &world))
+            })
+            .collect();
+
+        let mut state = self.state.write().unwrap();
+        let revision = state.revision + 1;
+        let mut changed = false;
+
+        for (id, (changed_in, feature)) in state.features.iter_mut() {
+            let latest = current.remove(id);
+            if *feature != latest {
+                *feature = latest;
+                *changed_in = revision;
+                changed = true;
+            }
+        }
+        for (id, feature) in current {
+            state.features.insert(id, (revision, Some(feature)));
+            changed = true;
+        }
+
+        if !changed {
+            return;
+        }
+        state.revision = revision;
+        state.forget_removals();
+
+        if let Err(err) = write_file(Path::new(&config.file), &state.collection()) {
+            log::warn!("Could not write the LandClaiming web map export: {}", err);
+        }
+    }
+}
+
+/// A claim as a GeoJSON feature, one square per chunk in block coordinates.
+fn feature(claim: &IndexedClaim, world: &str) -> Value {
+    let squares: Vec<Value> = claim
+        .chunks()
+        .iter()
+        .map(|chunk| {
+            let (x, z) = (chunk.x * CHUNK_SIZE, chunk.z * CHUNK_SIZE);
+            let (x2, z2) = (x + CHUNK_SIZE, z + CHUNK_SIZE);
+            json!([[[x, z], [x2, z], [x2, z2], [x, z2], [x, z]]])
+        })
+        .collect();
+
+    json!({
+        "type": "Feature",
+        "id": claim.id.0,
+        "geometry": { "type": "MultiPolygon", "coordinates": squares },
+        "properties": {
+            "world": world,
+            "owner": claim.owner,
+            "area": claim.chunks().len() * (CHUNK_SIZE * CHUNK_SIZE) as usize,
+            "color": color_of(&claim.owner),
+        },
+    })
+}
+
+/// A stable colour per owner so their land looks the same across exports.
+fn color_of(owner: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    owner.hash(&mut hasher);
+    format!("#{:06x}", hasher.finish() & 0xff_ffff)
+}
+
+fn write_file(path: &Path, contents: &Value) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents.to_string())
+}
+
+/// Serves `GET /claims.geojson` with every claim and
+/// `GET /changes?since=<revision>` with what changed after a revision.
+/// Clients that stall are given up on after `REQUEST_TIMEOUT`, so one of
+/// them cannot hold up the others.
+fn respond(mut stream: TcpStream, state: &RwLock<State>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_LINE)).read_line(&mut request_line)?;
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let body = {
+        let state = state.read().unwrap();
+        match target.split_once('?') {
+            Some(("/changes", query)) => {
+                let since = query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("since="))
+                    .and_then(|since| since.parse().ok())
+                    .unwrap_or(0);
+                Some(state.changes_since(since))
+            }
+            _ if target == "/claims.geojson" || target == "/" => Some(state.collection()),
+            _ => None,
+        }
+    };
+
+    let (status, body) = match body {
+        Some(body) => ("200 OK", body.to_string()),
+        None => ("404 Not Found", String::from("{}")),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_removals_are_forgotten_and_reset_clients_behind_them() {
+        let mut state = State::default();
+        state.features.insert(ClaimId(1), (1, None));
+        state.features.insert(ClaimId(2), (1, Some(json!({ "id": 2 }))));
+        state.revision = REMOVAL_REVISIONS + 1;
+        state.forget_removals();
+
+        assert_eq!(state.features.len(), 1);
+        assert_eq!(state.changes_since(0)["reset"], json!(true));
+        assert_eq!(state.changes_since(1)["reset"], json!(false));
+    }
+}